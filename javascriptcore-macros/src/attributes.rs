@@ -0,0 +1,377 @@
+//! Parsing of the `#[js(…)]` attributes used by the derive macros and by
+//! `#[js_class]`.
+
+use syn::{Attribute, ExprPath, LitStr, Token};
+
+/// A renaming rule, as used by `#[js(rename_all = "…")]`.
+#[derive(Clone, Copy)]
+pub(crate) enum RenameRule {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(literal: &LitStr) -> syn::Result<Self> {
+        Ok(match literal.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    literal,
+                    "unknown rule, expected one of `lowercase`, `UPPERCASE`, `PascalCase`, \
+                     `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case` or \
+                     `SCREAMING-KEBAB-CASE`",
+                ))
+            }
+        })
+    }
+
+    /// Applies the rule to `name`, which can be either a `snake_case` field
+    /// name or a `PascalCase` variant name.
+    pub(crate) fn apply(self, name: &str) -> String {
+        let words = split_words(name);
+        let capitalize = |word: &str| {
+            let mut characters = word.chars();
+
+            characters
+                .next()
+                .map(|first| first.to_uppercase().chain(characters).collect())
+                .unwrap_or_default()
+        };
+
+        match self {
+            Self::Lower => words.concat(),
+            Self::Upper => words.concat().to_uppercase(),
+            Self::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| {
+                    if index == 0 {
+                        word.clone()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            Self::Snake => words.join("_"),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+            Self::Kebab => words.join("-"),
+            Self::ScreamingKebab => words.join("-").to_uppercase(),
+        }
+    }
+}
+
+/// Splits `name` into lowercase words, at underscores and at lowercase to
+/// uppercase transitions.
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_is_lowercase = false;
+
+    for character in name.chars() {
+        if character == '_' {
+            words.push(std::mem::take(&mut word));
+            previous_is_lowercase = false;
+
+            continue;
+        }
+
+        if character.is_uppercase() && previous_is_lowercase {
+            words.push(std::mem::take(&mut word));
+        }
+
+        previous_is_lowercase = character.is_lowercase() || character.is_ascii_digit();
+        word.extend(character.to_lowercase());
+    }
+
+    words.push(word);
+    words.retain(|word| !word.is_empty());
+
+    words
+}
+
+/// How an enum is represented in JavaScript.
+pub(crate) enum Representation {
+    /// `{ "Variant": content }`, or `"Variant"` for unit variants.
+    External,
+    /// `{ tag: "Variant", ...fields }`.
+    Internal { tag: String },
+    /// `{ tag: "Variant", content: content }`.
+    Adjacent { tag: String, content: String },
+    /// `content`.
+    Untagged,
+}
+
+/// Attributes on a `struct` or an `enum`.
+pub(crate) struct ContainerAttributes {
+    pub(crate) rename_all: Option<RenameRule>,
+    pub(crate) representation: Representation,
+}
+
+impl ContainerAttributes {
+    pub(crate) fn parse(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut rename_all = None;
+        let mut tag = None;
+        let mut content = None;
+        let mut untagged = None;
+
+        for attribute in js_attributes(attributes) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    rename_all = Some(RenameRule::parse(&meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("tag") {
+                    tag = Some(meta.value()?.parse::<LitStr>()?);
+                } else if meta.path.is_ident("content") {
+                    content = Some(meta.value()?.parse::<LitStr>()?);
+                } else if meta.path.is_ident("untagged") {
+                    untagged = Some(meta.path.clone());
+                } else {
+                    return Err(meta.error(
+                        "unknown attribute, expected `rename_all`, `tag`, `content` or `untagged`",
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let representation = match (tag, content, untagged) {
+            (None, None, None) => Representation::External,
+            (Some(tag), None, None) => Representation::Internal { tag: tag.value() },
+            (Some(tag), Some(content), None) => Representation::Adjacent {
+                tag: tag.value(),
+                content: content.value(),
+            },
+            (None, None, Some(_)) => Representation::Untagged,
+            (None, Some(content), _) => {
+                return Err(syn::Error::new_spanned(
+                    content,
+                    "`content` can only be used together with `tag`",
+                ))
+            }
+            (_, _, Some(untagged)) => {
+                return Err(syn::Error::new_spanned(
+                    untagged,
+                    "`untagged` cannot be used together with `tag` or `content`",
+                ))
+            }
+        };
+
+        Ok(Self {
+            rename_all,
+            representation,
+        })
+    }
+}
+
+/// Attributes on an enum variant.
+pub(crate) struct VariantAttributes {
+    pub(crate) rename: Option<String>,
+    pub(crate) rename_all: Option<RenameRule>,
+}
+
+impl VariantAttributes {
+    pub(crate) fn parse(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut rename = None;
+        let mut rename_all = None;
+
+        for attribute in js_attributes(attributes) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    rename_all = Some(RenameRule::parse(&meta.value()?.parse()?)?);
+                } else {
+                    return Err(meta.error("unknown attribute, expected `rename` or `rename_all`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(Self { rename, rename_all })
+    }
+}
+
+/// What to do when a field is missing.
+pub(crate) enum FieldDefault {
+    /// The property is required.
+    None,
+    /// Use `Default::default()`.
+    Trait,
+    /// Call the given function.
+    Path(ExprPath),
+}
+
+/// Attributes on a field.
+pub(crate) struct FieldAttributes {
+    pub(crate) rename: Option<String>,
+    pub(crate) skip: bool,
+    pub(crate) default: FieldDefault,
+}
+
+impl FieldAttributes {
+    pub(crate) fn parse(attributes: &[Attribute], is_named: bool) -> syn::Result<Self> {
+        let mut rename = None;
+        let mut skip = false;
+        let mut default = FieldDefault::None;
+
+        for attribute in js_attributes(attributes) {
+            attribute.parse_nested_meta(|meta| {
+                if !is_named {
+                    return Err(meta.error("attributes are only supported on named fields"));
+                }
+
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("default") {
+                    default = if meta.input.peek(Token![=]) {
+                        FieldDefault::Path(meta.value()?.parse::<LitStr>()?.parse()?)
+                    } else {
+                        FieldDefault::Trait
+                    };
+                } else {
+                    return Err(
+                        meta.error("unknown attribute, expected `rename`, `skip` or `default`")
+                    );
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(Self {
+            rename,
+            skip,
+            default,
+        })
+    }
+}
+
+/// Attributes of `#[js_class(…)]`.
+pub(crate) struct ClassAttributes {
+    pub(crate) name: Option<String>,
+}
+
+impl ClassAttributes {
+    pub(crate) fn parse(attributes: proc_macro::TokenStream) -> syn::Result<Self> {
+        let mut name = None;
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+
+                Ok(())
+            } else {
+                Err(meta.error("unknown attribute, expected `name`"))
+            }
+        });
+
+        syn::parse::Parser::parse(parser, attributes)?;
+
+        Ok(Self { name })
+    }
+}
+
+/// How a function of an `impl` block annotated by `#[js_class]` is exposed.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum MemberKind {
+    /// `#[js(constructor)]`
+    Constructor,
+    /// `#[js(method)]`
+    Method,
+    /// `#[js(getter)]`
+    Getter,
+    /// `#[js(setter)]`
+    Setter,
+    /// `#[js(static)]`
+    Static,
+}
+
+/// Attributes on a function of an `impl` block annotated by `#[js_class]`.
+pub(crate) struct MemberAttributes {
+    pub(crate) kind: MemberKind,
+    pub(crate) name: Option<String>,
+}
+
+impl MemberAttributes {
+    /// Returns `None` if the function isn't exposed to JavaScript.
+    pub(crate) fn parse(attributes: &[Attribute]) -> syn::Result<Option<Self>> {
+        let mut kind = None;
+        let mut name = None;
+
+        for attribute in js_attributes(attributes) {
+            attribute.parse_nested_meta(|meta| {
+                let member_kind = if meta.path.is_ident("constructor") {
+                    MemberKind::Constructor
+                } else if meta.path.is_ident("method") {
+                    MemberKind::Method
+                } else if meta.path.is_ident("getter") {
+                    MemberKind::Getter
+                } else if meta.path.is_ident("setter") {
+                    MemberKind::Setter
+                } else if meta.path.is_ident("static") {
+                    MemberKind::Static
+                } else if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?.value());
+
+                    return Ok(());
+                } else {
+                    return Err(meta.error(
+                        "unknown attribute, expected `constructor`, `method`, `getter`, \
+                         `setter`, `static` or `name`",
+                    ));
+                };
+
+                if kind.replace(member_kind).is_some() {
+                    return Err(meta.error("a function can only be exposed once"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        match (kind, name) {
+            (Some(kind), name) => Ok(Some(Self { kind, name })),
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(syn::Error::new_spanned(
+                js_attributes(attributes).next(),
+                "`name` requires one of `constructor`, `method`, `getter`, `setter` or `static`",
+            )),
+        }
+    }
+}
+
+/// Removes the `#[js(…)]` attributes, which aren't known by the compiler
+/// outside of derive macros.
+pub(crate) fn remove_js_attributes(attributes: &mut Vec<Attribute>) {
+    attributes.retain(|attribute| !attribute.path().is_ident("js"));
+}
+
+fn js_attributes(attributes: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("js"))
+}
@@ -0,0 +1,300 @@
+//! Implementation of the class callback attribute macros, like
+//! `get_property_callback`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ItemFn;
+
+use crate::lifetimes;
+
+/// The class callbacks that can be generated.
+#[derive(Clone, Copy)]
+pub(crate) enum Callback {
+    Initialize,
+    Finalize,
+    HasProperty,
+    GetProperty,
+    SetProperty,
+    DeleteProperty,
+    GetPropertyNames,
+    HasInstance,
+    ConvertToType,
+}
+
+impl Callback {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Initialize => "initialize_callback",
+            Self::Finalize => "finalize_callback",
+            Self::HasProperty => "has_property_callback",
+            Self::GetProperty => "get_property_callback",
+            Self::SetProperty => "set_property_callback",
+            Self::DeleteProperty => "delete_property_callback",
+            Self::GetPropertyNames => "get_property_names_callback",
+            Self::HasInstance => "has_instance_callback",
+            Self::ConvertToType => "convert_to_type_callback",
+        }
+    }
+
+    /// Returns the raw parameters, the raw return type (with its arrow), the Rust
+    /// signature, and the body calling `func` of the callback.
+    fn parts(self) -> (TokenStream, TokenStream, TokenStream, TokenStream) {
+        // Most callbacks start by receiving a context and an object.
+        let context_and_object = quote! {
+            // Let's not drop `ctx`, otherwise it will close the context.
+            let ctx = ::core::mem::ManuallyDrop::new(javascriptcore::JSContext::from_raw(raw_ctx as *mut _));
+            let object = javascriptcore::JSObject::from_raw(raw_ctx, object);
+        };
+        let property_name = quote! {
+            let property_name = javascriptcore::macro_support::string_from_raw(property_name);
+        };
+
+        match self {
+            Self::Initialize => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                },
+                quote!(),
+                quote!(
+                    for<'ctx> fn(&'ctx javascriptcore::JSContext, &javascriptcore::JSObject<'ctx>)
+                ),
+                quote! {
+                    #context_and_object
+
+                    // There is nowhere to throw an exception to.
+                    javascriptcore::macro_support::catch_panic_without_exception(|| func(&ctx, &object));
+                },
+            ),
+            Self::Finalize => (
+                quote! {
+                    object: javascriptcore::sys::JSObjectRef,
+                },
+                quote!(),
+                quote!(fn(*mut ::core::ffi::c_void)),
+                quote! {
+                    let private_data = javascriptcore::sys::JSObjectGetPrivate(object);
+
+                    // There is nowhere to throw an exception to.
+                    javascriptcore::macro_support::catch_panic_without_exception(|| func(private_data));
+                },
+            ),
+            Self::HasProperty => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    property_name: javascriptcore::sys::JSStringRef,
+                },
+                quote!(-> bool),
+                quote! {
+                    for<'ctx> fn(&'ctx javascriptcore::JSContext, &javascriptcore::JSObject<'ctx>, javascriptcore::JSString) -> bool
+                },
+                quote! {
+                    #context_and_object
+                    #property_name
+
+                    // There is nowhere to throw an exception to.
+                    let mut has_property = false;
+                    javascriptcore::macro_support::catch_panic_without_exception(|| {
+                        has_property = func(&ctx, &object, property_name);
+                    });
+
+                    has_property
+                },
+            ),
+            Self::GetProperty => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    property_name: javascriptcore::sys::JSStringRef,
+                    exception: *mut javascriptcore::sys::JSValueRef,
+                },
+                quote!(-> *const javascriptcore::sys::OpaqueJSValue),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        javascriptcore::JSString,
+                    ) -> ::core::result::Result<::core::option::Option<javascriptcore::JSValue<'ctx>>, javascriptcore::JSException>
+                },
+                quote! {
+                    #context_and_object
+                    #property_name
+
+                    let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                        func(&ctx, &object, property_name)
+                    });
+
+                    javascriptcore::macro_support::return_optional_value(result, exception)
+                },
+            ),
+            Self::SetProperty => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    property_name: javascriptcore::sys::JSStringRef,
+                    value: javascriptcore::sys::JSValueRef,
+                    exception: *mut javascriptcore::sys::JSValueRef,
+                },
+                quote!(-> bool),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        javascriptcore::JSString,
+                        javascriptcore::JSValue<'ctx>,
+                    ) -> ::core::result::Result<bool, javascriptcore::JSException>
+                },
+                quote! {
+                    #context_and_object
+                    #property_name
+                    let value = javascriptcore::JSValue::from_raw(raw_ctx, value);
+
+                    let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                        func(&ctx, &object, property_name, value)
+                    });
+
+                    javascriptcore::macro_support::return_bool(result, exception)
+                },
+            ),
+            Self::DeleteProperty => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    property_name: javascriptcore::sys::JSStringRef,
+                    exception: *mut javascriptcore::sys::JSValueRef,
+                },
+                quote!(-> bool),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        javascriptcore::JSString,
+                    ) -> ::core::result::Result<bool, javascriptcore::JSException>
+                },
+                quote! {
+                    #context_and_object
+                    #property_name
+
+                    let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                        func(&ctx, &object, property_name)
+                    });
+
+                    javascriptcore::macro_support::return_bool(result, exception)
+                },
+            ),
+            Self::GetPropertyNames => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    property_names: javascriptcore::sys::JSPropertyNameAccumulatorRef,
+                },
+                quote!(),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        &javascriptcore::JSPropertyNameAccumulator,
+                    )
+                },
+                quote! {
+                    #context_and_object
+                    let property_names = javascriptcore::JSPropertyNameAccumulator::from_raw(property_names);
+
+                    // There is nowhere to throw an exception to.
+                    javascriptcore::macro_support::catch_panic_without_exception(|| {
+                        func(&ctx, &object, &property_names)
+                    });
+                },
+            ),
+            Self::HasInstance => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    possible_instance: javascriptcore::sys::JSValueRef,
+                    exception: *mut javascriptcore::sys::JSValueRef,
+                },
+                quote!(-> bool),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        javascriptcore::JSValue<'ctx>,
+                    ) -> ::core::result::Result<bool, javascriptcore::JSException>
+                },
+                quote! {
+                    #context_and_object
+                    let possible_instance = javascriptcore::JSValue::from_raw(raw_ctx, possible_instance);
+
+                    let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                        func(&ctx, &object, possible_instance)
+                    });
+
+                    javascriptcore::macro_support::return_bool(result, exception)
+                },
+            ),
+            Self::ConvertToType => (
+                quote! {
+                    raw_ctx: javascriptcore::sys::JSContextRef,
+                    object: javascriptcore::sys::JSObjectRef,
+                    r#type: javascriptcore::sys::JSType,
+                    exception: *mut javascriptcore::sys::JSValueRef,
+                },
+                quote!(-> *const javascriptcore::sys::OpaqueJSValue),
+                quote! {
+                    for<'ctx> fn(
+                        &'ctx javascriptcore::JSContext,
+                        &javascriptcore::JSObject<'ctx>,
+                        javascriptcore::JSType,
+                    ) -> ::core::result::Result<::core::option::Option<javascriptcore::JSValue<'ctx>>, javascriptcore::JSException>
+                },
+                quote! {
+                    #context_and_object
+
+                    let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                        func(&ctx, &object, r#type)
+                    });
+
+                    javascriptcore::macro_support::return_optional_value(result, exception)
+                },
+            ),
+        }
+    }
+}
+
+pub(crate) fn callback(
+    callback: Callback,
+    item: proc_macro::TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut function = syn::parse::<ItemFn>(item).map_err(|error| {
+        syn::Error::new(
+            error.span(),
+            format!("#[{}] must apply on a valid function", callback.name()),
+        )
+    })?;
+    lifetimes::bind_context_lifetime(&mut function.sig);
+    let function_visibility = &function.vis;
+    let function_name = &function.sig.ident;
+    let generics = lifetimes::without_lifetimes(&function.sig.generics);
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let turbofish = type_generics.as_turbofish();
+    let (raw_parameters, raw_return, signature, body) = callback.parts();
+
+    Ok(quote! {
+        #function_visibility unsafe extern "C" fn #function_name #impl_generics (
+            #raw_parameters
+        ) #raw_return
+        #where_clause
+        {
+            // Isolate the `#function` inside its own block to avoid collisions with variables.
+            // Let's use also this as an opportunity to type check the function being annotated.
+            let func: #signature = {
+                #function
+
+                #function_name #turbofish
+            };
+
+            #body
+        }
+    })
+}
@@ -0,0 +1,560 @@
+//! Implementation of the `js_class` attribute macro.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{punctuated::Punctuated, FnArg, ImplItem, ImplItemFn, ItemImpl, Type};
+
+use crate::{
+    attributes::{remove_js_attributes, ClassAttributes, MemberAttributes, MemberKind, RenameRule},
+    function, lifetimes,
+};
+
+/// A function of the `impl` block exposed to JavaScript.
+struct Member {
+    kind: MemberKind,
+    /// The name of the property in JavaScript.
+    name: String,
+    function: ImplItemFn,
+}
+
+/// How the receiver of a function is borrowed.
+enum Receiver {
+    /// No receiver.
+    None,
+    /// `&self`.
+    Shared,
+    /// `&mut self`.
+    Exclusive,
+}
+
+fn receiver(function: &ImplItemFn) -> syn::Result<Receiver> {
+    match function.sig.receiver() {
+        None => Ok(Receiver::None),
+        Some(receiver) if receiver.reference.is_none() => Err(syn::Error::new_spanned(
+            receiver,
+            "#[js_class] functions must take `&self` or `&mut self`, not `self`",
+        )),
+        Some(receiver) if receiver.mutability.is_some() => Ok(Receiver::Exclusive),
+        Some(_) => Ok(Receiver::Shared),
+    }
+}
+
+/// Returns the parameters of `function`, without its receiver.
+fn parameters(function: &ImplItemFn) -> Punctuated<FnArg, syn::token::Comma> {
+    function
+        .sig
+        .inputs
+        .iter()
+        .filter(|input| matches!(input, FnArg::Typed(_)))
+        .cloned()
+        .collect()
+}
+
+fn is_context(input: &FnArg) -> bool {
+    matches!(input, FnArg::Typed(pattern) if matches!(&*pattern.ty, Type::Reference(reference) if function::is_named(&reference.elem, "JSContext")))
+}
+
+impl Member {
+    fn new(kind: MemberKind, name: Option<String>, function: &ImplItemFn) -> syn::Result<Self> {
+        let rust_name = function.sig.ident.to_string();
+        let name = match (name, kind) {
+            (Some(name), _) => name,
+            (None, MemberKind::Setter) => match rust_name.strip_prefix("set_") {
+                Some(name) => RenameRule::Camel.apply(name),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &function.sig.ident,
+                        "the name of a setter must start with `set_`, or be given with \
+                         `#[js(setter, name = \"…\")]`",
+                    ))
+                }
+            },
+            (None, _) => RenameRule::Camel.apply(&rust_name),
+        };
+
+        let has_receiver = !matches!(receiver(function)?, Receiver::None);
+
+        match kind {
+            MemberKind::Constructor | MemberKind::Static if has_receiver => {
+                return Err(syn::Error::new_spanned(
+                    &function.sig,
+                    "constructors and static methods cannot take `self`",
+                ))
+            }
+            MemberKind::Method | MemberKind::Getter | MemberKind::Setter if !has_receiver => {
+                return Err(syn::Error::new_spanned(
+                    &function.sig,
+                    "methods, getters and setters must take `&self` or `&mut self`",
+                ))
+            }
+            _ => {}
+        }
+
+        let values = function
+            .sig
+            .inputs
+            .iter()
+            .filter(|input| matches!(input, FnArg::Typed(_)) && !is_context(input))
+            .count();
+
+        match kind {
+            MemberKind::Getter if values != 0 => {
+                return Err(syn::Error::new_spanned(
+                    &function.sig,
+                    "a getter cannot take parameters, except `&JSContext`",
+                ))
+            }
+            MemberKind::Setter if values != 1 => {
+                return Err(syn::Error::new_spanned(
+                    &function.sig,
+                    "a setter must take exactly one value, and optionally `&JSContext`",
+                ))
+            }
+            _ => {}
+        }
+
+        Ok(Self {
+            kind,
+            name,
+            function: function.clone(),
+        })
+    }
+
+    fn callback_name(&self) -> syn::Ident {
+        let prefix = match self.kind {
+            MemberKind::Constructor => "constructor",
+            MemberKind::Method => "method",
+            MemberKind::Getter => "getter",
+            MemberKind::Setter => "setter",
+            MemberKind::Static => "static",
+        };
+
+        format_ident!("js_{}_{}", prefix, self.function.sig.ident)
+    }
+
+    /// Generates the statements borrowing the data of `object` as `this`,
+    /// and the expression passing it to the function.
+    fn borrow_this(
+        &self,
+        self_ty: &Type,
+        class_name: &str,
+        object: TokenStream,
+    ) -> syn::Result<(TokenStream, Option<TokenStream>)> {
+        Ok(match receiver(&self.function)? {
+            Receiver::None => (quote!(), None),
+            Receiver::Shared => (
+                quote! {
+                    let this = javascriptcore::macro_support::this_data::<#self_ty>(ctx, #object, #class_name)?;
+                    let this = javascriptcore::macro_support::borrow(ctx, this, #class_name)?;
+                },
+                Some(quote!(::core::ops::Deref::deref(&this))),
+            ),
+            Receiver::Exclusive => (
+                quote! {
+                    let this = javascriptcore::macro_support::this_data::<#self_ty>(ctx, #object, #class_name)?;
+                    let mut this = javascriptcore::macro_support::borrow_mut(ctx, this, #class_name)?;
+                },
+                Some(quote!(::core::ops::DerefMut::deref_mut(&mut this))),
+            ),
+        })
+    }
+
+    fn callback(&self, self_ty: &Type, class_name: &str) -> syn::Result<TokenStream> {
+        let callback_name = self.callback_name();
+        let function_name = &self.function.sig.ident;
+        let callee = quote!(<#self_ty>::#function_name);
+        let output = &self.function.sig.output;
+
+        Ok(match self.kind {
+            MemberKind::Constructor => {
+                let (prelude, call) = function::call(&parameters(&self.function), callee, None)?;
+                let value = if matches!(output, syn::ReturnType::Type(_, ty) if function::is_named(ty, "Result"))
+                {
+                    quote!(javascriptcore::macro_support::into_result(ctx, #call)?)
+                } else {
+                    call
+                };
+
+                quote! {
+                    #[javascriptcore::constructor_callback]
+                    fn #callback_name(
+                        ctx: &javascriptcore::JSContext,
+                        _constructor: &javascriptcore::JSObject,
+                        arguments: &[javascriptcore::JSValue],
+                    ) -> ::core::result::Result<javascriptcore::JSValue, javascriptcore::JSException> {
+                        #prelude
+                        let value: #self_ty = #value;
+
+                        js_new_object(ctx, value).map(::core::convert::Into::into)
+                    }
+                }
+            }
+            MemberKind::Method | MemberKind::Static => {
+                let (borrow, leading) =
+                    self.borrow_this(self_ty, class_name, quote!(this_object))?;
+                // Static methods ignore `this`.
+                let this_object = if leading.is_some() {
+                    quote!(this_object)
+                } else {
+                    quote!(_this_object)
+                };
+                let (prelude, call) = function::call(&parameters(&self.function), callee, leading)?;
+                let return_value = function::return_value(output, call);
+
+                quote! {
+                    #[javascriptcore::function_callback]
+                    fn #callback_name(
+                        ctx: &javascriptcore::JSContext,
+                        _function: ::core::option::Option<&javascriptcore::JSObject>,
+                        #this_object: ::core::option::Option<&javascriptcore::JSObject>,
+                        arguments: &[javascriptcore::JSValue],
+                    ) -> ::core::result::Result<javascriptcore::JSValue, javascriptcore::JSException> {
+                        #borrow
+                        #prelude
+
+                        #return_value
+                    }
+                }
+            }
+            MemberKind::Getter => {
+                let (borrow, leading) = self.borrow_this(
+                    self_ty,
+                    class_name,
+                    quote!(::core::option::Option::Some(object)),
+                )?;
+                let arguments = leading
+                    .into_iter()
+                    .chain(parameters(&self.function).into_iter().map(|_| quote!(ctx)));
+                let return_value = function::return_value(output, quote!(#callee(#(#arguments),*)));
+
+                quote! {
+                    #[javascriptcore::get_property_callback]
+                    fn #callback_name(
+                        ctx: &javascriptcore::JSContext,
+                        object: &javascriptcore::JSObject,
+                        _property_name: javascriptcore::JSString,
+                    ) -> ::core::result::Result<
+                        ::core::option::Option<javascriptcore::JSValue>,
+                        javascriptcore::JSException,
+                    > {
+                        #borrow
+                        let value: ::core::result::Result<javascriptcore::JSValue, javascriptcore::JSException> = {
+                            #return_value
+                        };
+
+                        value.map(::core::option::Option::Some)
+                    }
+                }
+            }
+            MemberKind::Setter => {
+                let name = &self.name;
+                let (borrow, leading) = self.borrow_this(
+                    self_ty,
+                    class_name,
+                    quote!(::core::option::Option::Some(object)),
+                )?;
+                let arguments =
+                    leading
+                        .into_iter()
+                        .chain(parameters(&self.function).into_iter().map(|input| {
+                            if is_context(&input) {
+                                quote!(ctx)
+                            } else {
+                                quote!(value)
+                            }
+                        }));
+                let return_value = function::return_value(output, quote!(#callee(#(#arguments),*)));
+
+                quote! {
+                    #[javascriptcore::set_property_callback]
+                    fn #callback_name(
+                        ctx: &javascriptcore::JSContext,
+                        object: &javascriptcore::JSObject,
+                        _property_name: javascriptcore::JSString,
+                        value: javascriptcore::JSValue,
+                    ) -> ::core::result::Result<bool, javascriptcore::JSException> {
+                        // Convert the value before borrowing, as it may run scripts.
+                        let value = javascriptcore::macro_support::in_property(#name, || {
+                            javascriptcore::FromJSValue::from_js_value(&value)
+                        })?;
+                        #borrow
+                        let result: ::core::result::Result<javascriptcore::JSValue, javascriptcore::JSException> = {
+                            #return_value
+                        };
+
+                        result.map(|_| true)
+                    }
+                }
+            }
+        })
+    }
+}
+
+pub(crate) fn js_class(
+    attributes: ClassAttributes,
+    mut item: ItemImpl,
+) -> syn::Result<TokenStream> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "#[js_class] must apply on an inherent `impl` block",
+        ));
+    }
+
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[js_class] cannot apply on a generic `impl` block",
+        ));
+    }
+
+    let self_ty = (*item.self_ty).clone();
+    let class_name = match attributes.name {
+        Some(name) => name,
+        None => match &self_ty {
+            Type::Path(path) if path.qself.is_none() => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &self_ty,
+                    "the class name must be given with `#[js_class(name = \"…\")]`",
+                ))
+            }
+        },
+    };
+
+    let mut members = Vec::new();
+
+    for impl_item in &mut item.items {
+        if let ImplItem::Fn(function) = impl_item {
+            if let Some(attributes) = MemberAttributes::parse(&function.attrs)? {
+                lifetimes::bind_context_lifetime(&mut function.sig);
+                members.push(Member::new(attributes.kind, attributes.name, function)?);
+            }
+
+            remove_js_attributes(&mut function.attrs);
+        }
+    }
+
+    let mut constructors = members
+        .iter()
+        .filter(|member| member.kind == MemberKind::Constructor);
+    let constructor = constructors.next();
+
+    if let Some(constructor) = constructors.next() {
+        return Err(syn::Error::new_spanned(
+            &constructor.function.sig,
+            "a class can only have one constructor",
+        ));
+    }
+
+    for setter in members
+        .iter()
+        .filter(|member| member.kind == MemberKind::Setter)
+    {
+        if !members
+            .iter()
+            .any(|member| member.kind == MemberKind::Getter && member.name == setter.name)
+        {
+            return Err(syn::Error::new_spanned(
+                &setter.function.sig,
+                format!("the setter of `{}` has no matching getter", setter.name),
+            ));
+        }
+    }
+
+    let callbacks = members
+        .iter()
+        .map(|member| member.callback(&self_ty, &class_name))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Without a constructor, the class cannot be instantiated from JavaScript.
+    let (default_constructor, constructor_callback) = match constructor {
+        Some(constructor) => (quote!(), constructor.callback_name()),
+        None => {
+            let message = format!("`{class_name}` cannot be constructed from JavaScript");
+
+            (
+                quote! {
+                    #[javascriptcore::constructor_callback]
+                    fn js_constructor(
+                        ctx: &javascriptcore::JSContext,
+                        _constructor: &javascriptcore::JSObject,
+                        _arguments: &[javascriptcore::JSValue],
+                    ) -> ::core::result::Result<javascriptcore::JSValue, javascriptcore::JSException> {
+                        ::core::result::Result::Err(
+                            javascriptcore::macro_support::builtin_error(ctx, "TypeError", #message),
+                        )
+                    }
+                },
+                format_ident!("js_constructor"),
+            )
+        }
+    };
+
+    Ok(expand(
+        &item,
+        &self_ty,
+        &class_name,
+        &members,
+        callbacks,
+        default_constructor,
+        constructor_callback,
+    ))
+}
+
+fn expand(
+    item: &ItemImpl,
+    self_ty: &Type,
+    class_name: &str,
+    members: &[Member],
+    callbacks: Vec<TokenStream>,
+    default_constructor: TokenStream,
+    constructor_callback: syn::Ident,
+) -> TokenStream {
+    let methods = members
+        .iter()
+        .filter(|member| member.kind == MemberKind::Method)
+        .map(|member| {
+            let name = &member.name;
+            let callback = member.callback_name();
+
+            quote! {
+                .static_function(
+                    #name,
+                    ::core::option::Option::Some(#callback),
+                    javascriptcore::sys::kJSPropertyAttributeDontEnum,
+                )
+            }
+        });
+
+    let accessors = members
+        .iter()
+        .filter(|member| member.kind == MemberKind::Getter)
+        .map(|getter| {
+            let name = &getter.name;
+            let getter_callback = getter.callback_name();
+            let setter = members
+                .iter()
+                .find(|member| member.kind == MemberKind::Setter && member.name == getter.name);
+
+            match setter {
+                Some(setter) => {
+                    let setter_callback = setter.callback_name();
+
+                    quote! {
+                        .static_value(
+                            #name,
+                            ::core::option::Option::Some(#getter_callback),
+                            ::core::option::Option::Some(#setter_callback),
+                            javascriptcore::sys::kJSPropertyAttributeDontDelete,
+                        )
+                    }
+                }
+                None => quote! {
+                    .static_value(
+                        #name,
+                        ::core::option::Option::Some(#getter_callback),
+                        ::core::option::Option::None,
+                        javascriptcore::sys::kJSPropertyAttributeDontDelete
+                            | javascriptcore::sys::kJSPropertyAttributeReadOnly,
+                    )
+                },
+            }
+        });
+
+    let statics = members
+        .iter()
+        .filter(|member| member.kind == MemberKind::Static)
+        .map(|member| {
+            let name = &member.name;
+            let callback = member.callback_name();
+
+            quote! {
+                constructor.set_property(
+                    #name,
+                    javascriptcore::JSValue::new_function(ctx, #name, ::core::option::Option::Some(#callback)),
+                )?;
+            }
+        });
+
+    quote! {
+        #item
+
+        const _: () = {
+            static JS_CLASS: javascriptcore::macro_support::ClassCell =
+                javascriptcore::macro_support::ClassCell::new();
+
+            /// Returns the class of the instances, shared by all contexts.
+            fn js_class(
+                ctx: &javascriptcore::JSContext,
+            ) -> ::core::result::Result<javascriptcore::sys::JSClassRef, javascriptcore::JSException> {
+                JS_CLASS.get_or_try_init(|| {
+                    javascriptcore::JSClass::builder(ctx, #class_name).and_then(|builder| {
+                        builder
+                            #(#methods)*
+                            #(#accessors)*
+                            .build()
+                    })
+                })
+            }
+
+            fn js_new_object(
+                ctx: &javascriptcore::JSContext,
+                value: #self_ty,
+            ) -> ::core::result::Result<javascriptcore::JSObject<'_>, javascriptcore::JSException> {
+                let class = js_class(ctx)?;
+
+                // SAFETY: The class has been returned by `ClassCell`, and is never released.
+                ::core::result::Result::Ok(unsafe {
+                    javascriptcore::macro_support::new_object_with_data(
+                        ctx,
+                        class,
+                        ::core::cell::RefCell::new(value),
+                    )
+                })
+            }
+
+            #default_constructor
+            #(#callbacks)*
+
+            impl #self_ty {
+                /// Defines the JavaScript constructor of this type on the
+                /// global object of `ctx`, and returns it.
+                pub fn register(
+                    ctx: &javascriptcore::JSContext,
+                ) -> ::core::result::Result<javascriptcore::JSObject<'_>, javascriptcore::JSException> {
+                    let class = js_class(ctx)?;
+
+                    // SAFETY: The class is never released.
+                    let constructor = unsafe {
+                        javascriptcore::macro_support::new_constructor(
+                            ctx,
+                            class,
+                            ::core::option::Option::Some(#constructor_callback),
+                        )
+                    };
+                    #(#statics)*
+
+                    ctx.global_object()?
+                        .set_property(#class_name, ::core::clone::Clone::clone(&*constructor))?;
+
+                    ::core::result::Result::Ok(constructor)
+                }
+
+                /// Moves this value into a new JavaScript object, whose
+                /// methods and accessors are those of this type.
+                pub fn into_js_object(
+                    self,
+                    ctx: &javascriptcore::JSContext,
+                ) -> ::core::result::Result<javascriptcore::JSObject<'_>, javascriptcore::JSException> {
+                    js_new_object(ctx, self)
+                }
+            }
+        };
+    }
+}
@@ -0,0 +1,594 @@
+//! Implementation of the `ToJSValue` and `FromJSValue` derive macros.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, parse_quote, Data, DeriveInput, Fields, Generics, Ident, Index, Member};
+
+use crate::attributes::{
+    ContainerAttributes, FieldAttributes, FieldDefault, RenameRule, Representation,
+    VariantAttributes,
+};
+
+/// A field of a struct or of an enum variant.
+struct Field {
+    /// How to access the field, e.g. `name` or `0`.
+    member: Member,
+    /// The name of the local variable the field is bound to.
+    binding: Ident,
+    /// The name of the JavaScript property.
+    key: String,
+    attributes: FieldAttributes,
+}
+
+/// The shape of a struct or of an enum variant.
+enum Shape {
+    Unit,
+    Newtype(Field),
+    Tuple(Vec<Field>),
+    Struct(Vec<Field>),
+}
+
+impl Shape {
+    fn parse(fields: &Fields, rename_all: Option<RenameRule>) -> syn::Result<Self> {
+        let is_named = matches!(fields, Fields::Named(_));
+        let mut parsed = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let attributes = FieldAttributes::parse(&field.attrs, is_named)?;
+
+                Ok(match &field.ident {
+                    Some(ident) => {
+                        let name = ident.unraw().to_string();
+
+                        Field {
+                            member: Member::Named(ident.clone()),
+                            binding: format_ident!("__field_{}", name),
+                            key: match (&attributes.rename, rename_all) {
+                                (Some(rename), _) => rename.clone(),
+                                (None, Some(rule)) => rule.apply(&name),
+                                (None, None) => name,
+                            },
+                            attributes,
+                        }
+                    }
+                    None => Field {
+                        member: Member::Unnamed(Index::from(index)),
+                        binding: format_ident!("__field_{}", index),
+                        key: String::new(),
+                        attributes,
+                    },
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(match fields {
+            Fields::Unit => Self::Unit,
+            Fields::Named(_) => Self::Struct(parsed),
+            Fields::Unnamed(_) if parsed.len() == 1 => Self::Newtype(parsed.remove(0)),
+            Fields::Unnamed(_) => Self::Tuple(parsed),
+        })
+    }
+
+    /// Builds a pattern binding the fields of `path`.
+    fn pattern(&self, path: &TokenStream) -> TokenStream {
+        match self {
+            Self::Unit => quote!(#path),
+            Self::Newtype(Field { binding, .. }) => quote!(#path(#binding)),
+            Self::Tuple(fields) => {
+                let bindings = fields.iter().map(|field| &field.binding);
+
+                quote!(#path(#(#bindings),*))
+            }
+            Self::Struct(fields) => {
+                let fields = fields.iter().filter(|field| !field.attributes.skip).map(
+                    |Field {
+                         member, binding, ..
+                     }| quote!(#member: #binding),
+                );
+
+                quote!(#path { #(#fields,)* .. })
+            }
+        }
+    }
+
+    /// Builds an expression converting the fields bound by
+    /// [`Self::pattern`] into a `JSValue`. Struct shapes start with the
+    /// `tag` property if any.
+    fn conversion_to_js(&self, tag: Option<(&str, &str)>) -> TokenStream {
+        match self {
+            Self::Unit => quote!(javascriptcore::JSValue::new_undefined(ctx)),
+            Self::Newtype(Field { binding, .. }) => {
+                quote!(javascriptcore::ToJSValue::to_js_value(#binding, ctx)?)
+            }
+            Self::Tuple(fields) => {
+                let bindings = fields.iter().map(|field| &field.binding);
+
+                quote! {
+                    javascriptcore::JSValue::new_array(
+                        ctx,
+                        &[#(javascriptcore::ToJSValue::to_js_value(#bindings, ctx)?),*],
+                    )?
+                }
+            }
+            Self::Struct(fields) => {
+                let tag = tag.map(|(tag, name)| set_property(tag, quote!(#name)));
+                let fields = fields
+                    .iter()
+                    .filter(|field| !field.attributes.skip)
+                    .map(|Field { binding, key, .. }| set_property(key, quote!(#binding)));
+
+                quote! {
+                    {
+                        let object = javascriptcore::JSObject::new(ctx);
+                        #tag
+                        #(#fields)*
+
+                        javascriptcore::JSValue::from(object)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds an expression converting the `JSValue` named `value` into
+    /// `path`.
+    fn conversion_from_js(&self, path: &TokenStream, value: &Ident) -> TokenStream {
+        match self {
+            Self::Unit => quote! {
+                {
+                    <() as javascriptcore::FromJSValue>::from_js_value(#value)?;
+
+                    #path
+                }
+            },
+            Self::Newtype(_) => {
+                quote!(#path(javascriptcore::FromJSValue::from_js_value(#value)?))
+            }
+            Self::Tuple(fields) => {
+                let length = fields.len() as u32;
+                let indices = 0..length;
+
+                quote! {
+                    {
+                        let array = javascriptcore::macro_support::expect_array_of_length(#value, #length)?;
+
+                        #path(#(javascriptcore::macro_support::get_element(&array, #indices)?),*)
+                    }
+                }
+            }
+            Self::Struct(fields) => {
+                let fields = fields.iter().map(|field| {
+                    let Field {
+                        member,
+                        key,
+                        attributes,
+                        ..
+                    } = field;
+
+                    let value = match (&attributes.default, attributes.skip) {
+                        (FieldDefault::None, true) | (FieldDefault::Trait, true) => {
+                            quote!(::core::default::Default::default())
+                        }
+                        (FieldDefault::Path(default), true) => quote!(#default()),
+                        (FieldDefault::None, false) => {
+                            quote!(javascriptcore::macro_support::get_property(&object, #key)?)
+                        }
+                        (FieldDefault::Trait, false) => quote! {
+                            javascriptcore::macro_support::get_optional_property(&object, #key)?
+                                .unwrap_or_default()
+                        },
+                        (FieldDefault::Path(default), false) => quote! {
+                            javascriptcore::macro_support::get_optional_property(&object, #key)?
+                                .unwrap_or_else(#default)
+                        },
+                    };
+
+                    quote!(#member: #value)
+                });
+
+                quote! {
+                    {
+                        let object = javascriptcore::macro_support::expect_object(#value)?;
+
+                        #path { #(#fields),* }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An enum variant.
+struct Variant {
+    ident: Ident,
+    /// The name of the variant in JavaScript.
+    name: String,
+    shape: Shape,
+}
+
+/// Builds a statement storing `value` in the property `key` of `object`.
+fn set_property(key: &str, value: TokenStream) -> TokenStream {
+    quote! {
+        javascriptcore::macro_support::set_property(ctx, &object, #key, #value)?;
+    }
+}
+
+fn parse_variants(
+    input: &DeriveInput,
+    container: &ContainerAttributes,
+) -> syn::Result<Option<Vec<Variant>>> {
+    let Data::Enum(data) = &input.data else {
+        return Ok(None);
+    };
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "cannot derive conversions for an enum without variants",
+        ));
+    }
+
+    data.variants
+        .iter()
+        .map(|variant| {
+            let attributes = VariantAttributes::parse(&variant.attrs)?;
+            let ident = variant.ident.clone();
+            let name = match (attributes.rename, container.rename_all) {
+                (Some(rename), _) => rename,
+                (None, Some(rule)) => rule.apply(&ident.unraw().to_string()),
+                (None, None) => ident.unraw().to_string(),
+            };
+            let shape = Shape::parse(&variant.fields, attributes.rename_all)?;
+
+            if let (Representation::Internal { .. }, Shape::Tuple(_)) =
+                (&container.representation, &shape)
+            {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "internally tagged enums cannot contain tuple variants",
+                ));
+            }
+
+            Ok(Variant { ident, name, shape })
+        })
+        .collect::<syn::Result<_>>()
+        .map(Some)
+}
+
+/// Adds `bound` to each type parameter of `input`.
+fn add_bounds(input: &mut DeriveInput, bound: TokenStream) {
+    let parameters = input
+        .generics
+        .type_params()
+        .map(|parameter| parameter.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+
+    for parameter in parameters {
+        where_clause
+            .predicates
+            .push(parse_quote!(#parameter: #bound));
+    }
+}
+
+/// Returns the generics of an implementation of a conversion trait, whose
+/// lifetime parameter is `'ctx`: the one of the type if it declares it, e.g.
+/// to hold a `JSValue<'ctx>`, or a new one.
+fn with_context_lifetime(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+
+    if !generics
+        .lifetimes()
+        .any(|parameter| parameter.lifetime.ident == "ctx")
+    {
+        generics.params.insert(0, parse_quote!('ctx));
+    }
+
+    generics
+}
+
+pub(crate) fn derive_to_js_value(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttributes::parse(&input.attrs)?;
+
+    let body = match parse_variants(&input, &container)? {
+        Some(variants) => {
+            let arms = variants.iter().map(|Variant { ident, name, shape }| {
+                let pattern = shape.pattern(&quote!(Self::#ident));
+                let value = match (&container.representation, shape) {
+                    (Representation::External, Shape::Unit) => {
+                        quote!(javascriptcore::JSValue::new_string(ctx, #name))
+                    }
+                    (Representation::External, shape) => {
+                        let content = shape.conversion_to_js(None);
+
+                        quote! {
+                            {
+                                let content = #content;
+                                let object = javascriptcore::JSObject::new(ctx);
+                                javascriptcore::macro_support::set_property(ctx, &object, #name, &content)?;
+
+                                javascriptcore::JSValue::from(object)
+                            }
+                        }
+                    }
+                    (Representation::Internal { tag }, Shape::Unit) => {
+                        Shape::Struct(Vec::new()).conversion_to_js(Some((tag, name)))
+                    }
+                    (Representation::Internal { tag }, Shape::Newtype(Field { binding, .. })) => {
+                        quote! {
+                            {
+                                let value = javascriptcore::ToJSValue::to_js_value(#binding, ctx)?;
+                                let object = javascriptcore::macro_support::expect_object(&value)?;
+                                javascriptcore::macro_support::set_property(ctx, &object, #tag, #name)?;
+
+                                value
+                            }
+                        }
+                    }
+                    (Representation::Internal { tag }, shape) => {
+                        shape.conversion_to_js(Some((tag, name)))
+                    }
+                    (Representation::Adjacent { tag, .. }, Shape::Unit) => {
+                        Shape::Struct(Vec::new()).conversion_to_js(Some((tag, name)))
+                    }
+                    (Representation::Adjacent { tag, content: key }, shape) => {
+                        let content = shape.conversion_to_js(None);
+
+                        quote! {
+                            {
+                                let content = #content;
+                                let object = javascriptcore::JSObject::new(ctx);
+                                javascriptcore::macro_support::set_property(ctx, &object, #tag, #name)?;
+                                javascriptcore::macro_support::set_property(ctx, &object, #key, &content)?;
+
+                                javascriptcore::JSValue::from(object)
+                            }
+                        }
+                    }
+                    (Representation::Untagged, Shape::Unit) => {
+                        quote!(javascriptcore::JSValue::new_null(ctx))
+                    }
+                    (Representation::Untagged, shape) => shape.conversion_to_js(None),
+                };
+
+                quote!(#pattern => #value)
+            });
+
+            quote! {
+                ::core::result::Result::Ok(match self {
+                    #(#arms,)*
+                })
+            }
+        }
+        None => {
+            let Data::Struct(data) = &input.data else {
+                return Err(syn::Error::new_spanned(
+                    &input,
+                    "unions are not supported, only structs and enums",
+                ));
+            };
+
+            let shape = Shape::parse(&data.fields, container.rename_all)?;
+            let pattern = shape.pattern(&quote!(Self));
+            let value = shape.conversion_to_js(None);
+
+            quote! {
+                let #pattern = self;
+                let value = #value;
+
+                ::core::result::Result::Ok(value)
+            }
+        }
+    };
+
+    add_bounds(&mut input, quote!(javascriptcore::ToJSValue<'ctx>));
+    let name = &input.ident;
+    let generics = with_context_lifetime(&input.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics javascriptcore::ToJSValue<'ctx> for #name #type_generics #where_clause {
+            fn to_js_value(
+                &self,
+                ctx: &'ctx javascriptcore::JSContext,
+            ) -> ::core::result::Result<javascriptcore::JSValue<'ctx>, javascriptcore::JSException> {
+                #body
+            }
+        }
+    })
+}
+
+pub(crate) fn derive_from_js_value(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttributes::parse(&input.attrs)?;
+    let value = Ident::new("value", Span::call_site());
+    let content = Ident::new("content", Span::call_site());
+
+    let body = match parse_variants(&input, &container)? {
+        Some(variants) => {
+            let names = variants.iter().map(|variant| &variant.name);
+            let unknown_variant = quote! {
+                ::core::result::Result::Err(javascriptcore::macro_support::unknown_variant(
+                    value,
+                    &name,
+                    &[#(#names),*],
+                ))
+            };
+
+            match &container.representation {
+                Representation::External => {
+                    let (unit_variants, other_variants) = variants
+                        .iter()
+                        .partition::<Vec<_>, _>(|variant| matches!(variant.shape, Shape::Unit));
+
+                    let unit_arms = unit_variants.iter().map(|Variant { ident, name, .. }| {
+                        quote!(#name => ::core::result::Result::Ok(Self::#ident))
+                    });
+                    let from_string = quote! {
+                        let name = <::std::string::String as javascriptcore::FromJSValue>::from_js_value(value)?;
+
+                        match name.as_str() {
+                            #(#unit_arms,)*
+                            _ => #unknown_variant,
+                        }
+                    };
+
+                    if other_variants.is_empty() {
+                        from_string
+                    } else {
+                        let arms = other_variants.iter().map(|Variant { ident, name, shape }| {
+                            let from = shape.conversion_from_js(&quote!(Self::#ident), &content);
+
+                            quote! {
+                                #name => javascriptcore::macro_support::in_property(#name, || {
+                                    ::core::result::Result::Ok(#from)
+                                })
+                            }
+                        });
+                        let from_string = (!unit_variants.is_empty()).then(|| {
+                            quote! {
+                                if value.is_string() {
+                                    return { #from_string };
+                                }
+                            }
+                        });
+
+                        quote! {
+                            #from_string
+
+                            let object = javascriptcore::macro_support::expect_object(value)?;
+                            let (name, content) = javascriptcore::macro_support::single_property(&object)?;
+                            let content = &content;
+
+                            match name.as_str() {
+                                #(#arms,)*
+                                _ => #unknown_variant,
+                            }
+                        }
+                    }
+                }
+                Representation::Internal { tag } => {
+                    let arms = variants.iter().map(|Variant { ident, name, shape }| {
+                        let from = match shape {
+                            Shape::Unit => quote!(Self::#ident),
+                            shape => shape.conversion_from_js(&quote!(Self::#ident), &value),
+                        };
+
+                        quote!(#name => ::core::result::Result::Ok(#from))
+                    });
+
+                    quote! {
+                        let object = javascriptcore::macro_support::expect_object(value)?;
+                        let name: ::std::string::String =
+                            javascriptcore::macro_support::get_property(&object, #tag)?;
+
+                        match name.as_str() {
+                            #(#arms,)*
+                            _ => #unknown_variant,
+                        }
+                    }
+                }
+                Representation::Adjacent { tag, content: key } => {
+                    let arms = variants.iter().map(|Variant { ident, name, shape }| {
+                        let from = match shape {
+                            Shape::Unit => quote!(::core::result::Result::Ok(Self::#ident)),
+                            shape => {
+                                let from =
+                                    shape.conversion_from_js(&quote!(Self::#ident), &content);
+
+                                quote! {
+                                    {
+                                        let content = &object.get_property(#key);
+
+                                        javascriptcore::macro_support::in_property(#key, || {
+                                            ::core::result::Result::Ok(#from)
+                                        })
+                                    }
+                                }
+                            }
+                        };
+
+                        quote!(#name => #from)
+                    });
+
+                    quote! {
+                        let object = javascriptcore::macro_support::expect_object(value)?;
+                        let name: ::std::string::String =
+                            javascriptcore::macro_support::get_property(&object, #tag)?;
+
+                        match name.as_str() {
+                            #(#arms,)*
+                            _ => #unknown_variant,
+                        }
+                    }
+                }
+                Representation::Untagged => {
+                    let attempts =
+                        variants
+                            .iter()
+                            .map(|Variant { ident, shape, .. }| match shape {
+                                Shape::Unit => quote! {
+                                    if value.is_null() {
+                                        return ::core::result::Result::Ok(Self::#ident);
+                                    }
+                                },
+                                shape => {
+                                    let from =
+                                        shape.conversion_from_js(&quote!(Self::#ident), &value);
+
+                                    quote! {
+                                        if let ::core::option::Option::Some(variant) =
+                                            javascriptcore::macro_support::attempt(|| {
+                                                ::core::result::Result::Ok(#from)
+                                            })
+                                        {
+                                            return ::core::result::Result::Ok(variant);
+                                        }
+                                    }
+                                }
+                            });
+                    let message = format!(
+                        "data did not match any variant of untagged enum `{}`",
+                        input.ident
+                    );
+
+                    quote! {
+                        #(#attempts)*
+
+                        ::core::result::Result::Err(javascriptcore::macro_support::type_error(value, #message))
+                    }
+                }
+            }
+        }
+        None => {
+            let Data::Struct(data) = &input.data else {
+                return Err(syn::Error::new_spanned(
+                    &input,
+                    "unions are not supported, only structs and enums",
+                ));
+            };
+
+            let shape = Shape::parse(&data.fields, container.rename_all)?;
+            let from = shape.conversion_from_js(&quote!(Self), &value);
+
+            quote!(::core::result::Result::Ok(#from))
+        }
+    };
+
+    add_bounds(&mut input, quote!(javascriptcore::FromJSValue<'ctx>));
+    let name = &input.ident;
+    let generics = with_context_lifetime(&input.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics javascriptcore::FromJSValue<'ctx> for #name #type_generics #where_clause {
+            fn from_js_value(
+                value: &javascriptcore::JSValue<'ctx>,
+            ) -> ::core::result::Result<Self, javascriptcore::JSException> {
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,260 @@
+//! Implementation of the `js_function` attribute macro.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{punctuated::Punctuated, token::Comma, FnArg, ItemFn, ReturnType, Type};
+
+use crate::lifetimes;
+
+/// The role of a parameter of a function annotated by `js_function`.
+enum Parameter {
+    /// `&JSContext`.
+    Context,
+    /// A JavaScript argument, converted with `FromJSValue`.
+    Argument { index: usize, is_optional: bool },
+    /// `&[JSValue]`, receiving the remaining arguments.
+    Rest,
+}
+
+/// Returns the last segment of the path of `ty`, if any.
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        Type::Group(group) => last_segment(&group.elem),
+        Type::Paren(paren) => last_segment(&paren.elem),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_named(ty: &Type, name: &str) -> bool {
+    last_segment(ty).is_some_and(|segment| segment.ident == name)
+}
+
+fn classify(inputs: &Punctuated<FnArg, Comma>) -> syn::Result<Vec<Parameter>> {
+    let mut parameters = Vec::new();
+    let mut index = 0;
+
+    for input in inputs {
+        let ty = match input {
+            FnArg::Typed(pattern) => &*pattern.ty,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "#[js_function] cannot apply on methods",
+                ))
+            }
+        };
+
+        if matches!(parameters.last(), Some(Parameter::Rest)) {
+            return Err(syn::Error::new_spanned(
+                input,
+                "the `&[JSValue]` rest parameter must be the last parameter",
+            ));
+        }
+
+        let parameter = match ty {
+            Type::Reference(reference) if is_named(&reference.elem, "JSContext") => {
+                Parameter::Context
+            }
+            Type::Reference(reference) if matches!(&*reference.elem, Type::Slice(slice) if is_named(&slice.elem, "JSValue")) => {
+                Parameter::Rest
+            }
+            Type::Reference(_) => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "arguments must be owned types implementing `FromJSValue`, \
+                     `&JSContext`, or `&[JSValue]`",
+                ))
+            }
+            _ => {
+                index += 1;
+
+                Parameter::Argument {
+                    index: index - 1,
+                    is_optional: is_named(ty, "Option"),
+                }
+            }
+        };
+
+        parameters.push(parameter);
+    }
+
+    Ok(parameters)
+}
+
+/// Generates the statements checking the number of arguments and converting
+/// them, and the expression calling `callee` with them, for a function whose
+/// parameters are `inputs`.
+///
+/// The generated code expects `ctx: &JSContext` and `arguments: &[JSValue]`
+/// in scope. `leading` is passed before the other arguments, e.g. a receiver.
+pub(crate) fn call(
+    inputs: &Punctuated<FnArg, Comma>,
+    callee: TokenStream,
+    leading: Option<TokenStream>,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let parameters = classify(inputs)?;
+
+    // Arguments after the last required one are optional.
+    let positional = parameters
+        .iter()
+        .filter(|parameter| matches!(parameter, Parameter::Argument { .. }))
+        .count();
+    let required = parameters
+        .iter()
+        .filter_map(|parameter| match parameter {
+            Parameter::Argument {
+                index,
+                is_optional: false,
+            } => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let maximum = if matches!(parameters.last(), Some(Parameter::Rest)) {
+        quote!(::core::option::Option::None)
+    } else {
+        quote!(::core::option::Option::Some(#positional))
+    };
+
+    let conversions = parameters.iter().filter_map(|parameter| match parameter {
+        Parameter::Argument { index, .. } => {
+            let binding = format_ident!("argument_{}", index);
+
+            Some(quote! {
+                let #binding = javascriptcore::macro_support::argument(ctx, arguments, #index)?;
+            })
+        }
+        _ => None,
+    });
+
+    let call_arguments =
+        leading
+            .into_iter()
+            .chain(parameters.iter().map(|parameter| match parameter {
+                Parameter::Context => quote!(ctx),
+                Parameter::Argument { index, .. } => {
+                    let binding = format_ident!("argument_{}", index);
+
+                    quote!(#binding)
+                }
+                Parameter::Rest => {
+                    quote!(javascriptcore::macro_support::rest_arguments(arguments, #positional))
+                }
+            }));
+
+    Ok((
+        quote! {
+            javascriptcore::macro_support::check_arity(ctx, arguments.len(), #required, #maximum)?;
+            #(#conversions)*
+        },
+        quote!(#callee (#(#call_arguments),*)),
+    ))
+}
+
+/// Generates the expression converting the value returned by `call` into a
+/// `Result<JSValue, JSException>`, according to the `output` type.
+pub(crate) fn return_value(output: &ReturnType, call: TokenStream) -> TokenStream {
+    match output {
+        ReturnType::Type(_, ty) if is_named(ty, "Result") => {
+            quote!(javascriptcore::macro_support::return_result(ctx, #call))
+        }
+        ReturnType::Type(_, ty) if !matches!(&**ty, Type::Tuple(tuple) if tuple.elems.is_empty()) =>
+        {
+            quote!(javascriptcore::macro_support::return_value(ctx, #call))
+        }
+        _ => quote! {
+            #call;
+
+            javascriptcore::macro_support::return_value(ctx, ())
+        },
+    }
+}
+
+pub(crate) fn js_function(mut function: ItemFn) -> syn::Result<TokenStream> {
+    lifetimes::bind_context_lifetime(&mut function.sig);
+    let function_visibility = &function.vis;
+    let function_name = &function.sig.ident;
+    let generics = lifetimes::without_lifetimes(&function.sig.generics);
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let mut call_generics = generics.clone();
+    call_generics.params.insert(0, syn::parse_quote!('ctx));
+    let (call_generics, _, _) = call_generics.split_for_impl();
+    let turbofish = type_generics.as_turbofish();
+
+    let (prelude, call) = call(
+        &function.sig.inputs,
+        quote!(#function_name #turbofish),
+        None,
+    )?;
+    let return_value = return_value(&function.sig.output, call);
+
+    Ok(quote! {
+        #function_visibility unsafe extern "C" fn #function_name #impl_generics (
+            raw_ctx: javascriptcore::sys::JSContextRef,
+            _function: javascriptcore::sys::JSObjectRef,
+            _this_object: javascriptcore::sys::JSObjectRef,
+            argument_count: usize,
+            arguments: *const javascriptcore::sys::JSValueRef,
+            exception: *mut javascriptcore::sys::JSValueRef,
+        ) -> *const javascriptcore::sys::OpaqueJSValue
+        #where_clause
+        {
+            use ::core::{mem::ManuallyDrop, ops::Not, ptr, result::Result, slice};
+            use ::std::vec::Vec;
+            use javascriptcore::{sys::JSValueRef, JSContext, JSException, JSValue};
+
+            // Isolate the `#function` inside its own function to avoid collisions with
+            // variables. This function also converts the arguments and the returned value.
+            fn call #call_generics (
+                ctx: &'ctx JSContext,
+                arguments: &[JSValue<'ctx>],
+            ) -> Result<JSValue<'ctx>, JSException>
+            #where_clause
+            {
+                #function
+
+                #prelude
+
+                #return_value
+            }
+
+            // This should never happen, it's simply a paranoid precaution.
+            assert!(raw_ctx.is_null().not(), "`JSContextRef` is null");
+
+            // Let's not drop `ctx`, otherwise it will close the context.
+            let ctx = ManuallyDrop::new(JSContext::from_raw(raw_ctx as *mut _));
+
+            let arguments = if argument_count == 0 || arguments.is_null() {
+                Vec::new()
+            } else {
+                unsafe { slice::from_raw_parts(arguments, argument_count) }
+                    .iter()
+                    .map(|value| JSValue::from_raw(raw_ctx, *value))
+                    .collect::<Vec<_>>()
+            };
+
+            // A panic must not unwind into JavaScriptCore.
+            let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                call #turbofish (&ctx, arguments.as_slice())
+            });
+
+            match result {
+                Ok(value) => {
+                    // Ensure `exception` contains a null pointer.
+                    *exception = ptr::null_mut();
+
+                    // Return the result.
+                    value.into()
+                }
+                Err(exc) => {
+                    // Fill the exception.
+                    *exception = JSValueRef::from(exc) as *mut _;
+
+                    // Return a null pointer for the result.
+                    ptr::null()
+                }
+            }
+        }
+    })
+}
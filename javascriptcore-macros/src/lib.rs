@@ -1,6 +1,13 @@
 use proc_macro::TokenStream;
 use quote::quote;
 
+mod attributes;
+mod callbacks;
+mod class;
+mod derive;
+mod function;
+mod lifetimes;
+
 /// Transforms a Rust function into a C function for being used as a JavaScript callback.
 ///
 /// This `function_callback` procedural macro transforms a Rust function of type:
@@ -16,18 +23,23 @@ use quote::quote;
 ///
 /// into a `javascriptcore_sys::JSObjectCallAsFunctionCallback` function.
 ///
+/// A panic in the function never unwinds into JavaScriptCore: it is caught, and
+/// handled according to `javascriptcore::panic_strategy()`.
+///
 /// Check the documentation of `javascriptcore::JSValue::new_function` to learn more.
 #[proc_macro_attribute]
 pub fn function_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
-    let function = syn::parse::<syn::ItemFn>(item)
+    let mut function = syn::parse::<syn::ItemFn>(item)
         .expect("#[function_callback] must apply on a valid function");
+    lifetimes::bind_context_lifetime(&mut function.sig);
     let function_visibility = &function.vis;
     let function_name = &function.sig.ident;
-    let function_generics = &function.sig.generics.params;
-    let function_where_clause = &function.sig.generics.where_clause;
+    let generics = lifetimes::without_lifetimes(&function.sig.generics);
+    let (function_generics, type_generics, function_where_clause) = generics.split_for_impl();
+    let turbofish = type_generics.as_turbofish();
 
     quote! {
-        #function_visibility unsafe extern "C" fn #function_name < #function_generics > (
+        #function_visibility unsafe extern "C" fn #function_name #function_generics (
             raw_ctx: javascriptcore::sys::JSContextRef,
             function: javascriptcore::sys::JSObjectRef,
             this_object: javascriptcore::sys::JSObjectRef,
@@ -76,19 +88,21 @@ pub fn function_callback(_attributes: TokenStream, item: TokenStream) -> TokenSt
             // Isolate the `#function` inside its own block to avoid collisions with variables.
             // Let's use also this as an opportunity to type check the function being annotated by
             // `function_callback`.
-            let func: fn(
-                &JSContext,
-                Option<&JSObject>,
-                Option<&JSObject>,
-                &[JSValue],
-            ) -> Result<JSValue, JSException> = {
+            let func: for<'ctx> fn(
+                &'ctx JSContext,
+                Option<&JSObject<'ctx>>,
+                Option<&JSObject<'ctx>>,
+                &[JSValue<'ctx>],
+            ) -> Result<JSValue<'ctx>, javascriptcore::JSException> = {
                 #function
 
-                #function_name ::< #function_generics >
+                #function_name #turbofish
             };
 
-            // Second, call the original function.
-            let result = func(&ctx, function, this_object, arguments.as_slice());
+            // Second, call the original function. A panic must not unwind into JavaScriptCore.
+            let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                func(&ctx, function, this_object, arguments.as_slice())
+            });
 
             // Finally, let's handle the result, including the exception.
             match result {
@@ -112,6 +126,136 @@ pub fn function_callback(_attributes: TokenStream, item: TokenStream) -> TokenSt
     .into()
 }
 
+/// Transforms a Rust function with typed arguments into a C function for
+/// being used as a JavaScript callback.
+///
+/// Contrary to `function_callback`, the annotated function has a natural Rust
+/// signature. Each parameter is one of:
+///
+/// * `&JSContext`: the context the function is called in,
+/// * a type implementing `javascriptcore::FromJSValue`: a JavaScript
+///   argument, converted automatically. Trailing `Option<T>` parameters are
+///   optional: they receive `None` when the argument is missing,
+/// * `&[JSValue]`: a rest parameter receiving the remaining arguments. It
+///   must be the last parameter.
+///
+/// The function can return any type implementing `javascriptcore::ToJSValue`,
+/// or a `Result<T, E>` where `T` implements `ToJSValue` and `E` implements
+/// `javascriptcore::IntoJSException`. Note that the macro recognizes a
+/// `Result` by its name.
+///
+/// ```rust,ignore
+/// #[js_function]
+/// fn add(a: f64, b: f64) -> f64 {
+///     a + b
+/// }
+///
+/// #[js_function]
+/// fn greet(ctx: &JSContext, name: String, punctuation: Option<char>) -> Result<String, JSException> {
+///     Ok(format!("Hello, {name}{}", punctuation.unwrap_or('!')))
+/// }
+///
+/// let add = JSValue::new_function(&ctx, "add", Some(add));
+/// ```
+///
+/// Calling the function with the wrong number of arguments, or with an
+/// argument that cannot be converted, throws a `TypeError` mentioning the
+/// index of the argument, e.g. ``argument 1: expected `Number`, got
+/// `String` ``. When the function has no rest parameter, extra arguments
+/// are rejected too.
+///
+/// A panic in the function never unwinds into JavaScriptCore: it is caught, and
+/// handled according to `javascriptcore::panic_strategy()`.
+///
+/// Check the documentation of `javascriptcore::JSValue::new_function` to learn more.
+#[proc_macro_attribute]
+pub fn js_function(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    syn::parse::<syn::ItemFn>(item)
+        .and_then(function::js_function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Exposes a Rust type to JavaScript as a class, from an `impl` block.
+///
+/// The functions of the block annotated with `#[js(…)]` are exposed:
+///
+/// * `#[js(constructor)]`: the constructor, called by `new`. It returns
+///   `Self`, or a `Result<Self, E>`. There can be at most one constructor;
+///   without one, the class cannot be instantiated from JavaScript,
+/// * `#[js(method)]`: a method of the instances, taking `&self` or
+///   `&mut self`,
+/// * `#[js(getter)]`: a property of the instances, read with `&self` or
+///   `&mut self`,
+/// * `#[js(setter)]`: the setter of a property, taking `&mut self` (or
+///   `&self`) and the new value. Its name must start with `set_`, and it
+///   requires a getter,
+/// * `#[js(static)]`: a function of the constructor.
+///
+/// Names are converted to `camelCase`, and can be given with
+/// `#[js(method, name = "…")]`. The class is named after the type, unless
+/// given with `#[js_class(name = "…")]`. Parameters and returned values
+/// follow the same rules as with `js_function`.
+///
+/// The macro generates two functions in the `impl` block:
+///
+/// * `register(ctx: &JSContext) -> Result<JSObject, JSException>` defines
+///   the constructor on the global object, and returns it,
+/// * `into_js_object(self, ctx: &JSContext) -> Result<JSObject, JSException>`
+///   moves a value into a new instance.
+///
+/// ```rust,ignore
+/// struct Counter {
+///     count: u32,
+/// }
+///
+/// #[js_class]
+/// impl Counter {
+///     #[js(constructor)]
+///     fn new(start: Option<u32>) -> Self {
+///         Self { count: start.unwrap_or(0) }
+///     }
+///
+///     #[js(method)]
+///     fn increment(&mut self) -> u32 {
+///         self.count += 1;
+///         self.count
+///     }
+///
+///     #[js(getter)]
+///     fn count(&self) -> u32 {
+///         self.count
+///     }
+///
+///     #[js(setter)]
+///     fn set_count(&mut self, count: u32) {
+///         self.count = count;
+///     }
+///
+///     #[js(static)]
+///     fn maximum() -> u32 {
+///         u32::MAX
+///     }
+/// }
+///
+/// Counter::register(&ctx)?;
+/// evaluate_script(&ctx, "const counter = new Counter(1); counter.increment()", None, "test.js", 1)?;
+/// ```
+///
+/// The instances hold a `RefCell<Self>` as private data: a `&mut self`
+/// method cannot be called while another method of the same instance is
+/// running, e.g. when it calls back into JavaScript. An `Error` is thrown
+/// instead.
+#[proc_macro_attribute]
+pub fn js_class(attributes: TokenStream, item: TokenStream) -> TokenStream {
+    attributes::ClassAttributes::parse(attributes)
+        .and_then(|attributes| {
+            syn::parse::<syn::ItemImpl>(item).and_then(|item| class::js_class(attributes, item))
+        })
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 /// Transforms a Rust function into a C function for being used as a JavaScript
 /// constructor callback.
 ///
@@ -127,18 +271,23 @@ pub fn function_callback(_attributes: TokenStream, item: TokenStream) -> TokenSt
 ///
 /// into a `javascriptcore_sys::JSObjectCallAsConstructorCallback` function.
 ///
+/// A panic in the function never unwinds into JavaScriptCore: it is caught, and
+/// handled according to `javascriptcore::panic_strategy()`.
+///
 /// Check the documentation of `javascriptcore::JSClass::new` to learn more.
 #[proc_macro_attribute]
 pub fn constructor_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
-    let constructor = syn::parse::<syn::ItemFn>(item)
+    let mut constructor = syn::parse::<syn::ItemFn>(item)
         .expect("#[constructor_callback] must apply on a valid function");
+    lifetimes::bind_context_lifetime(&mut constructor.sig);
     let constructor_visibility = &constructor.vis;
     let constructor_name = &constructor.sig.ident;
-    let constructor_generics = &constructor.sig.generics.params;
-    let constructor_where_clause = &constructor.sig.generics.where_clause;
+    let generics = lifetimes::without_lifetimes(&constructor.sig.generics);
+    let (constructor_generics, type_generics, constructor_where_clause) = generics.split_for_impl();
+    let turbofish = type_generics.as_turbofish();
 
     quote! {
-        #constructor_visibility unsafe extern "C" fn #constructor_name < #constructor_generics >(
+        #constructor_visibility unsafe extern "C" fn #constructor_name #constructor_generics (
             raw_ctx: javascriptcore::sys::JSContextRef,
             constructor: javascriptcore::sys::JSObjectRef,
             argument_count: usize,
@@ -173,18 +322,21 @@ pub fn constructor_callback(_attributes: TokenStream, item: TokenStream) -> Toke
             // Isolate the `#constructor` inside its own block to avoid collisions with variables.
             // Let's use also this as an opportunity to type check the constructor being annotated by
             // `constructor_callback`.
-            let ctor: fn(
-                &JSContext,
-                &JSObject,
-                &[JSValue],
-            ) -> Result<JSValue, JSException> = {
+            let ctor: for<'ctx> fn(
+                &'ctx JSContext,
+                &JSObject<'ctx>,
+                &[JSValue<'ctx>],
+            ) -> Result<JSValue<'ctx>, javascriptcore::JSException> = {
                 #constructor
 
-                #constructor_name ::< #constructor_generics >
+                #constructor_name #turbofish
             };
 
-            // Second, call the original constructor.
-            let result = ctor(&ctx, &constructor, arguments.as_slice());
+            // Second, call the original constructor. A panic must not unwind into
+            // JavaScriptCore.
+            let result = javascriptcore::macro_support::catch_panic(&ctx, || {
+                ctor(&ctx, &constructor, arguments.as_slice())
+            });
 
             // Finally, let's handle the result, including the exception.
             match result {
@@ -207,3 +359,290 @@ pub fn constructor_callback(_attributes: TokenStream, item: TokenStream) -> Toke
     }
     .into()
 }
+
+/// Derives `javascriptcore::ToJSValue` for a struct or an enum.
+///
+/// Structs with named fields are converted into plain objects, one property
+/// per field. Newtype structs are converted as their single field, tuple
+/// structs as arrays, and unit structs as `undefined`.
+///
+/// Enums are externally tagged by default: a unit variant is converted into
+/// its name, and other variants into an object with a single property, named
+/// after the variant, holding the variant's content.
+///
+/// ```rust,ignore
+/// #[derive(ToJSValue, FromJSValue)]
+/// #[js(rename_all = "camelCase")]
+/// struct Point {
+///     x_coordinate: f64,
+///     y_coordinate: f64,
+///     #[js(skip)]
+///     cached_length: Option<f64>,
+/// }
+///
+/// // `{ xCoordinate: 1, yCoordinate: 2 }`
+/// let value = Point { x_coordinate: 1., y_coordinate: 2., cached_length: None }.to_js_value(&ctx)?;
+/// ```
+///
+/// # Container attributes
+///
+/// * `#[js(rename_all = "…")]`: renames all the fields of a struct, or all
+///   the variants of an enum, according to a convention: `lowercase`,
+///   `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`,
+///   `SCREAMING_SNAKE_CASE`, `kebab-case` or `SCREAMING-KEBAB-CASE`.
+/// * `#[js(tag = "type")]`: internally tagged enum, represented as
+///   `{ type: "Variant", ...fields }`. Tuple variants aren't supported, and
+///   newtype variants must hold a value converted into an object.
+/// * `#[js(tag = "t", content = "c")]`: adjacently tagged enum, represented
+///   as `{ t: "Variant", c: content }`.
+/// * `#[js(untagged)]`: untagged enum, represented as the variant's content,
+///   with unit variants represented as `null`.
+///
+/// # Variant attributes
+///
+/// * `#[js(rename = "…")]`: the name of the variant in JavaScript.
+/// * `#[js(rename_all = "…")]`: renames all the fields of the variant.
+///
+/// # Field attributes
+///
+/// * `#[js(rename = "…")]`: the name of the property in JavaScript.
+/// * `#[js(skip)]`: the field is not converted. When converting back, it is
+///   filled with its default value.
+/// * `#[js(default)]` and `#[js(default = "path::to::function")]`: when
+///   converting back, a missing (i.e. `undefined`) property is filled with
+///   `Default::default()`, or with the value returned by the function.
+#[proc_macro_derive(ToJSValue, attributes(js))]
+pub fn derive_to_js_value(item: TokenStream) -> TokenStream {
+    syn::parse::<syn::DeriveInput>(item)
+        .and_then(derive::derive_to_js_value)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `javascriptcore::FromJSValue` for a struct or an enum.
+///
+/// This is the counterpart of the `ToJSValue` derive macro: it reads the
+/// same representation, and supports the same `#[js(…)]` attributes. A
+/// property that doesn't have the expected type results in a `TypeError`
+/// mentioning the property, e.g. ``property `x`: expected `Number`, got
+/// `String` ``.
+///
+/// Untagged enums try each variant in order, and pick the first one that
+/// converts successfully.
+#[proc_macro_derive(FromJSValue, attributes(js))]
+pub fn derive_from_js_value(item: TokenStream) -> TokenStream {
+    syn::parse::<syn::DeriveInput>(item)
+        .and_then(derive::derive_from_js_value)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as an initialize callback, called when an object is first created.
+///
+/// This `initialize_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(context: &JSContext, object: &JSObject)
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectInitializeCallback` function.
+///
+/// Since there is nowhere to throw an exception to, a panic is swallowed once reported,
+/// unless the panic strategy is to abort.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::initialize` to learn more.
+#[proc_macro_attribute]
+pub fn initialize_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::Initialize, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a finalize callback, called when an object is prepared for garbage collection.
+///
+/// This `finalize_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(private_data: *mut c_void)
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectFinalizeCallback` function.
+///
+/// The function receives the private data of the object, so that it can be released. No
+/// JavaScript API can be called from a finalizer. Since there is nowhere to throw an
+/// exception to, a panic is swallowed once reported, unless the panic strategy is to abort.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::finalize` to learn more.
+#[proc_macro_attribute]
+pub fn finalize_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::Finalize, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a has-property callback, called when checking whether an object has a property.
+///
+/// This `has_property_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(context: &JSContext, object: &JSObject, property_name: JSString) -> bool
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectHasPropertyCallback` function.
+///
+/// Since there is nowhere to throw an exception to, a panic is swallowed once reported,
+/// unless the panic strategy is to abort, and `false` is returned.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::has_property` to learn more.
+#[proc_macro_attribute]
+pub fn has_property_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::HasProperty, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a get-property callback, called when getting a property's value.
+///
+/// This `get_property_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(
+///     context: &JSContext,
+///     object: &JSObject,
+///     property_name: JSString,
+/// ) -> Result<Option<JSValue>, JSException>
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectGetPropertyCallback` function.
+///
+/// Returning `Ok(None)` forwards the request to the static values, the parent class, and
+/// the prototype chain. Such a function can also be used as the getter of a static value,
+/// see `javascriptcore::JSClassBuilder::static_value`.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::get_property` to learn more.
+#[proc_macro_attribute]
+pub fn get_property_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::GetProperty, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a set-property callback, called when setting a property's value.
+///
+/// This `set_property_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(
+///     context: &JSContext,
+///     object: &JSObject,
+///     property_name: JSString,
+///     value: JSValue,
+/// ) -> Result<bool, JSException>
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectSetPropertyCallback` function.
+///
+/// Returning `Ok(false)` forwards the request to the static values, the parent class, and
+/// the default object. Such a function can also be used as the setter of a static value,
+/// see `javascriptcore::JSClassBuilder::static_value`.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::set_property` to learn more.
+#[proc_macro_attribute]
+pub fn set_property_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::SetProperty, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a delete-property callback, called when deleting a property.
+///
+/// This `delete_property_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(
+///     context: &JSContext,
+///     object: &JSObject,
+///     property_name: JSString,
+/// ) -> Result<bool, JSException>
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectDeletePropertyCallback` function.
+///
+/// Returning `Ok(false)` forwards the request to the static values, the parent class, and
+/// the default object.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::delete_property` to learn more.
+#[proc_macro_attribute]
+pub fn delete_property_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::DeleteProperty, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a get-property-names callback, called when collecting the names of an object's properties.
+///
+/// This `get_property_names_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(context: &JSContext, object: &JSObject, property_names: &JSPropertyNameAccumulator)
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectGetPropertyNamesCallback` function.
+///
+/// The names added to the accumulator are combined with the static values and functions,
+/// and the properties of the parent class.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::get_property_names` to learn more.
+#[proc_macro_attribute]
+pub fn get_property_names_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::GetPropertyNames, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a has-instance callback, called when an object is the target of `instanceof`.
+///
+/// This `has_instance_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(
+///     context: &JSContext,
+///     constructor: &JSObject,
+///     possible_instance: JSValue,
+/// ) -> Result<bool, JSException>
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectHasInstanceCallback` function.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::has_instance` to learn more.
+#[proc_macro_attribute]
+pub fn has_instance_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::HasInstance, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Transforms a Rust function into a C function for being used as a convert-to-type callback, called when converting an object to a particular JavaScript type.
+///
+/// This `convert_to_type_callback` procedural macro transforms a Rust function of type:
+///
+/// ```rust,ignore
+/// fn(
+///     context: &JSContext,
+///     object: &JSObject,
+///     r#type: JSType,
+/// ) -> Result<Option<JSValue>, JSException>
+/// ```
+///
+/// into a `javascriptcore_sys::JSObjectConvertToTypeCallback` function.
+///
+/// Returning `Ok(None)` forwards the request to the parent class, and to the default
+/// conversion.
+///
+/// Check the documentation of `javascriptcore::JSClassBuilder::convert_to_type` to learn more.
+#[proc_macro_attribute]
+pub fn convert_to_type_callback(_attributes: TokenStream, item: TokenStream) -> TokenStream {
+    callbacks::callback(callbacks::Callback::ConvertToType, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,132 @@
+//! Binding of the lifetime of the context in the signatures of the annotated
+//! functions.
+
+use proc_macro2::Span;
+use syn::{
+    FnArg, GenericArgument, GenericParam, Generics, Lifetime, LifetimeParam, PathArguments,
+    ReturnType, Signature, Type,
+};
+
+/// The types borrowing the context they have been created in.
+const BORROWING_TYPES: [&str; 4] = ["JSValue", "JSObject", "JSTypedArray", "JSClass"];
+
+/// Binds the elided lifetimes of `&JSContext`, and of the types borrowing a
+/// context like `JSValue`, to a `'ctx` lifetime parameter, added to the
+/// generics of `signature` if needed.
+///
+/// Without it, a function like `fn(&JSContext, &[JSValue]) -> JSValue` would
+/// be rejected, as the lifetime of the returned value cannot be elided.
+/// Explicit lifetimes are left as is.
+pub(crate) fn bind_context_lifetime(signature: &mut Signature) {
+    let lifetime = Lifetime::new("'ctx", Span::call_site());
+    let mut is_bound = false;
+
+    for input in &mut signature.inputs {
+        if let FnArg::Typed(pattern) = input {
+            is_bound |= bind(&mut pattern.ty, &lifetime);
+        }
+    }
+
+    if let ReturnType::Type(_, ty) = &mut signature.output {
+        is_bound |= bind(ty, &lifetime);
+    }
+
+    if is_bound
+        && !signature
+            .generics
+            .lifetimes()
+            .any(|parameter| parameter.lifetime == lifetime)
+    {
+        signature
+            .generics
+            .params
+            .insert(0, GenericParam::Lifetime(LifetimeParam::new(lifetime)));
+    }
+}
+
+/// Returns `generics` without its lifetime parameters.
+///
+/// The lifetimes bound by [`bind_context_lifetime`] are late-bound: they
+/// cannot be given explicitly, e.g. in a turbofish.
+pub(crate) fn without_lifetimes(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    generics.params = generics
+        .params
+        .into_iter()
+        .filter(|parameter| !matches!(parameter, GenericParam::Lifetime(_)))
+        .collect();
+
+    generics
+}
+
+fn is_elided(lifetime: Option<&Lifetime>) -> bool {
+    !matches!(lifetime, Some(lifetime) if lifetime.ident != "_")
+}
+
+/// Binds the elided lifetimes of `ty` to `lifetime`, and returns whether one
+/// has been bound.
+fn bind(ty: &mut Type, lifetime: &Lifetime) -> bool {
+    match ty {
+        Type::Reference(reference) => {
+            let mut is_bound = false;
+
+            if is_elided(reference.lifetime.as_ref())
+                && matches!(&*reference.elem, Type::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == "JSContext"))
+            {
+                reference.lifetime = Some(lifetime.clone());
+                is_bound = true;
+            }
+
+            bind(&mut reference.elem, lifetime) || is_bound
+        }
+        Type::Path(path) if path.qself.is_none() => {
+            let mut is_bound = false;
+
+            for segment in &mut path.path.segments {
+                match &mut segment.arguments {
+                    PathArguments::None
+                        if BORROWING_TYPES.iter().any(|name| segment.ident == name) =>
+                    {
+                        segment.arguments =
+                            PathArguments::AngleBracketed(syn::parse_quote!(<#lifetime>));
+                        is_bound = true;
+                    }
+                    PathArguments::AngleBracketed(arguments) => {
+                        for argument in &mut arguments.args {
+                            match argument {
+                                GenericArgument::Lifetime(elided)
+                                    if elided.ident == "_"
+                                        && BORROWING_TYPES
+                                            .iter()
+                                            .any(|name| segment.ident == name) =>
+                                {
+                                    *elided = lifetime.clone();
+                                    is_bound = true;
+                                }
+                                GenericArgument::Type(ty) => is_bound |= bind(ty, lifetime),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            is_bound
+        }
+        Type::Slice(slice) => bind(&mut slice.elem, lifetime),
+        Type::Array(array) => bind(&mut array.elem, lifetime),
+        Type::Paren(paren) => bind(&mut paren.elem, lifetime),
+        Type::Group(group) => bind(&mut group.elem, lifetime),
+        Type::Tuple(tuple) => {
+            let mut is_bound = false;
+
+            for ty in &mut tuple.elems {
+                is_bound |= bind(ty, lifetime);
+            }
+
+            is_bound
+        }
+        _ => false,
+    }
+}
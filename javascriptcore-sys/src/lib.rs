@@ -226,6 +226,8 @@ pub enum JSType {
     Object = 5,
     /// A primitive symbol value.
     Symbol = 6,
+    /// A primitive `BigInt` value.
+    BigInt = 7,
 }
 
 /// A constant identifying the Typed Array type of a [`JSObjectRef`].
@@ -586,6 +588,174 @@ extern "C" {
     pub fn JSValueUnprotect(ctx: JSContextRef, value: JSValueRef);
 }
 
+/// A constant identifying the result of a comparison of two values,
+/// as returned by [`JSValueCompare`].
+#[cfg(feature = "bigint")]
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum JSRelationCondition {
+    /// The values cannot be compared, e.g. one of them is `NaN`.
+    Undefined = 0,
+    /// The left value is equal to the right value.
+    Equal = 1,
+    /// The left value is greater than the right value.
+    GreaterThan = 2,
+    /// The left value is less than the right value.
+    LessThan = 3,
+}
+
+#[cfg(feature = "bigint")]
+extern "C" {
+    /// Creates a JavaScript `BigInt` from a double.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `value`: The value to assign to the new `BigInt`. It must be
+    ///   an integer.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any, e.g. a `RangeError` when `value` isn't
+    ///   an integer. Pass `NULL` if you do not care to store an
+    ///   exception.
+    ///
+    /// Returns a `BigInt`, or `NULL` if an exception is thrown.
+    pub fn JSBigIntCreateWithDouble(
+        ctx: JSContextRef,
+        value: f64,
+        exception: *mut JSValueRef,
+    ) -> JSValueRef;
+
+    /// Creates a JavaScript `BigInt` from a 64-bit signed integer.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `integer`: The value to assign to the new `BigInt`.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns a `BigInt`, or `NULL` if an exception is thrown.
+    pub fn JSBigIntCreateWithInt64(
+        ctx: JSContextRef,
+        integer: i64,
+        exception: *mut JSValueRef,
+    ) -> JSValueRef;
+
+    /// Creates a JavaScript `BigInt` from a 64-bit unsigned integer.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `integer`: The value to assign to the new `BigInt`.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns a `BigInt`, or `NULL` if an exception is thrown.
+    pub fn JSBigIntCreateWithUInt64(
+        ctx: JSContextRef,
+        integer: u64,
+        exception: *mut JSValueRef,
+    ) -> JSValueRef;
+
+    /// Creates a JavaScript `BigInt` from a string, as `BigInt(string)`
+    /// would.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `string`: The [`JSStringRef`] representation of the `BigInt`.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any, e.g. a `SyntaxError` when `string`
+    ///   isn't a valid integer. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns a `BigInt`, or `NULL` if an exception is thrown.
+    pub fn JSBigIntCreateWithString(
+        ctx: JSContextRef,
+        string: JSStringRef,
+        exception: *mut JSValueRef,
+    ) -> JSValueRef;
+
+    /// Tests whether a JavaScript value's type is the `bigint` type.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `value`: The [`JSValueRef`] to test.
+    ///
+    /// Returns `true` if `value`'s type is the `bigint` type, otherwise `false`.
+    pub fn JSValueIsBigInt(ctx: JSContextRef, value: JSValueRef) -> bool;
+
+    /// Converts a JavaScript value to a 64-bit signed integer.
+    ///
+    /// A `BigInt` is truncated modulo 2<sup>64</sup>, other values are
+    /// converted to a number first.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `value`: The [`JSValueRef`] to convert.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns the result of conversion, or `0` if an exception is thrown.
+    pub fn JSValueToInt64(ctx: JSContextRef, value: JSValueRef, exception: *mut JSValueRef) -> i64;
+
+    /// Converts a JavaScript value to a 64-bit unsigned integer.
+    ///
+    /// A `BigInt` is truncated modulo 2<sup>64</sup>, other values are
+    /// converted to a number first.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `value`: The [`JSValueRef`] to convert.
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns the result of conversion, or `0` if an exception is thrown.
+    pub fn JSValueToUInt64(ctx: JSContextRef, value: JSValueRef, exception: *mut JSValueRef)
+        -> u64;
+
+    /// Compares two JavaScript values, as the `<`, `==` and `>`
+    /// operators would.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `left`: The left [`JSValueRef`].
+    /// * `right`: The right [`JSValueRef`].
+    /// * `exception`: A pointer to a [`JSValueRef`] in which to store
+    ///   an exception, if any. Pass `NULL` if you do not care to store
+    ///   an exception.
+    ///
+    /// Returns the [`JSRelationCondition`] of `left` to `right`.
+    pub fn JSValueCompare(
+        ctx: JSContextRef,
+        left: JSValueRef,
+        right: JSValueRef,
+        exception: *mut JSValueRef,
+    ) -> JSRelationCondition;
+
+    /// Compares a JavaScript value with a 64-bit signed integer.
+    ///
+    /// See [`JSValueCompare()`].
+    pub fn JSValueCompareInt64(
+        ctx: JSContextRef,
+        left: JSValueRef,
+        right: i64,
+        exception: *mut JSValueRef,
+    ) -> JSRelationCondition;
+
+    /// Compares a JavaScript value with a 64-bit unsigned integer.
+    ///
+    /// See [`JSValueCompare()`].
+    pub fn JSValueCompareUInt64(
+        ctx: JSContextRef,
+        left: JSValueRef,
+        right: u64,
+        exception: *mut JSValueRef,
+    ) -> JSRelationCondition;
+
+    /// Compares a JavaScript value with a double.
+    ///
+    /// See [`JSValueCompare()`].
+    pub fn JSValueCompareDouble(
+        ctx: JSContextRef,
+        left: JSValueRef,
+        right: f64,
+        exception: *mut JSValueRef,
+    ) -> JSRelationCondition;
+}
+
 /// Specifies that a property has no special attributes.
 pub const kJSPropertyAttributeNone: ::std::os::raw::c_uint = 0;
 /// Specifies that a property is read-only.
@@ -1945,6 +2115,99 @@ extern "C" {
     /// * `name`: The remote debugging name to set on `ctx`.
     pub fn JSGlobalContextSetName(ctx: JSGlobalContextRef, name: JSStringRef);
 }
+
+/// A weak reference to a JavaScript object.
+#[cfg(feature = "weak")]
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct OpaqueJSWeak([u8; 0]);
+
+/// A weak reference to a JavaScript object.
+///
+/// This is part of the private API of JavaScriptCore, exported by
+/// the framework on macOS and by `javascriptcoregtk` on Linux.
+#[cfg(feature = "weak")]
+pub type JSWeakRef = *const OpaqueJSWeak;
+
+#[cfg(feature = "weak")]
+extern "C" {
+    /// Creates a weak reference to a JavaScript object.
+    ///
+    /// * `group`: The context group of `object`.
+    /// * `object`: The [`JSObjectRef`] to reference.
+    ///
+    /// Returns a [`JSWeakRef`] with a reference count of one, which
+    /// doesn't prevent `object` from being garbage collected.
+    ///
+    /// # See also
+    ///
+    /// * [`JSWeakGetObject()`]
+    /// * [`JSWeakRelease()`]
+    pub fn JSWeakCreate(group: JSContextGroupRef, object: JSObjectRef) -> JSWeakRef;
+
+    /// Retains a weak reference.
+    ///
+    /// * `group`: The context group of the referenced object.
+    /// * `weak`: The [`JSWeakRef`] to retain.
+    pub fn JSWeakRetain(group: JSContextGroupRef, weak: JSWeakRef);
+
+    /// Releases a weak reference.
+    ///
+    /// * `group`: The context group of the referenced object.
+    /// * `weak`: The [`JSWeakRef`] to release.
+    pub fn JSWeakRelease(group: JSContextGroupRef, weak: JSWeakRef);
+
+    /// Gets the object referenced by a weak reference.
+    ///
+    /// * `weak`: The [`JSWeakRef`] whose object to get.
+    ///
+    /// Returns the referenced [`JSObjectRef`], or `NULL` if it has
+    /// been garbage collected.
+    pub fn JSWeakGetObject(weak: JSWeakRef) -> JSObjectRef;
+}
+
+/// A map of weakly referenced objects, owned by a global object.
+#[cfg(feature = "weak")]
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct OpaqueJSWeakObjectMap([u8; 0]);
+
+/// A map of weakly referenced objects, owned by a global object.
+///
+/// This is part of the private API of JavaScriptCore, exported by
+/// the framework on macOS and by `javascriptcoregtk` on Linux.
+#[cfg(feature = "weak")]
+pub type JSWeakObjectMapRef = *mut OpaqueJSWeakObjectMap;
+
+/// The callback invoked when a [`JSWeakObjectMapRef`] is destroyed.
+///
+/// * `map`: The [`JSWeakObjectMapRef`] being destroyed.
+/// * `data`: The private data passed to [`JSWeakObjectMapCreate()`].
+#[cfg(feature = "weak")]
+pub type JSWeakMapDestroyedCallback = ::std::option::Option<
+    unsafe extern "C" fn(map: JSWeakObjectMapRef, data: *mut ::std::os::raw::c_void),
+>;
+
+#[cfg(feature = "weak")]
+extern "C" {
+    /// Creates a map of weakly referenced objects, owned by the global
+    /// object of a context.
+    ///
+    /// * `ctx`: The execution context whose global object owns the map.
+    /// * `data`: The private data passed to `destructor`.
+    /// * `destructor`: The callback invoked when the map is destroyed,
+    ///   i.e. when the global object is destroyed.
+    ///
+    /// Returns a [`JSWeakObjectMapRef`], which is only valid as long
+    /// as the global object is alive.
+    pub fn JSWeakObjectMapCreate(
+        ctx: JSContextRef,
+        data: *mut ::std::os::raw::c_void,
+        destructor: JSWeakMapDestroyedCallback,
+    ) -> JSWeakObjectMapRef;
+}
 /// A UTF-16 code unit.
 ///
 /// One, or a sequence of two, can encode any Unicode character. As
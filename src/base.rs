@@ -4,7 +4,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::{sys, JSContext, JSException, JSObject, JSString, JSValue};
+use crate::{sys, JSContext, JSException, JSObject, JSString, JSValue, ScriptDiagnostic};
 use std::ptr;
 
 /// Evaluates a string of JavaScript.
@@ -24,7 +24,13 @@ use std::ptr;
 ///   to `1`.
 ///
 /// Returns either the [`JSValue`] that results from evaluating the script or
-/// the exception that occurred.
+/// the exception that occurred. If the script cannot be parsed, the
+/// `SyntaxError` holds a [`ScriptDiagnostic`], see
+/// [`JSException::diagnostic`].
+///
+/// With the `sourcemap` feature, an inline `//# sourceMappingURL=data:` map
+/// at the end of the script is registered for `source_url`, see
+/// [`JSContext::register_source_map`].
 ///
 /// ```
 /// use javascriptcore::*;
@@ -33,26 +39,57 @@ use std::ptr;
 /// let r = evaluate_script(&ctx, "2 + 2", None, "test.js", 1);
 /// assert_eq!(r.unwrap().as_number().unwrap(), 4.0);
 /// ```
-pub fn evaluate_script<S: Into<JSString>, U: Into<JSString>>(
-    ctx: &JSContext,
+pub fn evaluate_script<'ctx, S: Into<JSString>, U: Into<JSString>>(
+    ctx: &'ctx JSContext,
     script: S,
-    this_object: Option<&JSObject>,
+    this_object: Option<&JSObject<'ctx>>,
     source_url: U,
     starting_line_number: i32,
-) -> Result<JSValue, JSException> {
+) -> Result<JSValue<'ctx>, JSException> {
+    let script = script.into();
+    let source_url = source_url.into();
+
+    if let Some(this_object) = this_object {
+        this_object.check_context(ctx.raw)?;
+    }
+
+    #[cfg(feature = "sourcemap")]
+    crate::source_map::register_inline_source_map(ctx.raw, &source_url, &script);
+
     unsafe {
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let result = sys::JSEvaluateScript(
             ctx.raw,
-            script.into().raw,
+            script.raw,
             this_object.map_or(ptr::null_mut(), |t| t.raw),
-            source_url.into().raw,
+            source_url.raw,
             starting_line_number,
             &mut exception,
         );
 
         if result.is_null() {
-            Err(JSValue::from_raw(ctx.raw, exception).into())
+            let mut exception: JSException = JSValue::from_raw(ctx.raw, exception).into();
+
+            // A `SyntaxError` may also be thrown while the script runs, e.g. by
+            // `JSON.parse()`: only the script failing to parse has a
+            // diagnostic.
+            if exception.name().is_ok_and(|name| name == "SyntaxError")
+                && !sys::JSCheckScriptSyntax(
+                    ctx.raw,
+                    script.raw,
+                    source_url.raw,
+                    starting_line_number,
+                    ptr::null_mut(),
+                )
+            {
+                exception.diagnostic = Some(Box::new(ScriptDiagnostic::new(
+                    &exception,
+                    &script.to_string(),
+                    starting_line_number,
+                )));
+            }
+
+            Err(exception)
         } else {
             Ok(JSValue::from_raw(ctx.raw, result))
         }
@@ -75,7 +112,8 @@ pub fn evaluate_script<S: Into<JSString>, U: Into<JSString>>(
 ///   to `1`.
 ///
 /// Returns `Ok` if the script is syntactically correct, otherwise
-/// returns an [exception](JSException).
+/// returns a [diagnostic](ScriptDiagnostic) of the syntax error, holding
+/// the offending line.
 ///
 /// ```
 /// use javascriptcore::*;
@@ -83,18 +121,23 @@ pub fn evaluate_script<S: Into<JSString>, U: Into<JSString>>(
 /// let ctx = JSContext::default();
 /// let r = check_script_syntax(&ctx, "alert('abc');", "test.js", 1);
 /// assert!(r.is_ok());
+///
+/// let diagnostic = check_script_syntax(&ctx, "alert('abc", "test.js", 1).unwrap_err();
+/// assert_eq!(diagnostic.source_line.as_deref(), Some("alert('abc"));
 /// ```
 pub fn check_script_syntax<S: Into<JSString>, U: Into<JSString>>(
     ctx: &JSContext,
     script: S,
     source_url: U,
     starting_line_number: i32,
-) -> Result<(), JSException> {
+) -> Result<(), ScriptDiagnostic> {
+    let script = script.into();
+
     unsafe {
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let result = sys::JSCheckScriptSyntax(
             ctx.raw,
-            script.into().raw,
+            script.raw,
             source_url.into().raw,
             starting_line_number,
             &mut exception,
@@ -103,7 +146,13 @@ pub fn check_script_syntax<S: Into<JSString>, U: Into<JSString>>(
         if result {
             Ok(())
         } else {
-            Err(JSValue::from_raw(ctx.raw, exception).into())
+            let exception: JSException = JSValue::from_raw(ctx.raw, exception).into();
+
+            Err(ScriptDiagnostic::new(
+                &exception,
+                &script.to_string(),
+                starting_line_number,
+            ))
         }
     }
 }
@@ -111,7 +160,8 @@ pub fn check_script_syntax<S: Into<JSString>, U: Into<JSString>>(
 /// Performs a JavaScript garbage collection.
 ///
 /// JavaScript values that are on the machine stack, in a register,
-/// protected by [`JSValue::protect()`], set as the global object of an
+/// held by a [`Persistent`](crate::Persistent), protected by
+/// [`JSValue::protect()`], set as the global object of an
 /// execution context, or reachable from any such value will not
 /// be collected.
 ///
@@ -133,6 +183,7 @@ pub fn check_script_syntax<S: Into<JSString>, U: Into<JSString>>(
 /// # See also
 ///
 /// * [`JSValue::protect()`]
+/// * [`JSValue::to_persistent()`]
 /// * [`JSValue::unprotect()`]
 pub fn garbage_collect(ctx: &JSContext) {
     unsafe {
@@ -164,6 +215,17 @@ mod tests {
         assert_eq!(r.unwrap().as_number().unwrap(), 4.0);
     }
 
+    #[test]
+    fn exception_outlives_context() {
+        let exception = {
+            let ctx = JSContext::default();
+
+            evaluate_script(&ctx, "throw new RangeError('oops')", None, "test.js", 1).unwrap_err()
+        };
+
+        assert_eq!(exception.name().unwrap(), "RangeError");
+    }
+
     #[test]
     fn can_garbage_collect() {
         let ctx = JSContext::default();
@@ -4,9 +4,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{ffi::CString, ptr};
+use std::{
+    any::Any,
+    collections::BTreeSet,
+    ffi::{c_char, CStr, CString},
+    marker::PhantomData,
+    ptr,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 
-use crate::{sys, JSClass, JSContext, JSException, JSObject, JSValue};
+use crate::{sys, JSClass, JSContext, JSException, JSObject, JSString};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,9 +26,104 @@ enum JSClassError {
 
     #[error("class could not be retained")]
     FailedToRetainClass,
+
+    #[error("property name was invalid (e.g. it contains a NULL character)")]
+    InvalidPropertyName,
+}
+
+/// A raw class that is created once for the whole process, and never
+/// released.
+pub(crate) struct StaticClass(pub(crate) sys::JSClassRef);
+
+// SAFETY: A `JSClassRef` is immutable once created, and can be used from any
+// thread.
+unsafe impl Send for StaticClass {}
+unsafe impl Sync for StaticClass {}
+
+/// The typed private data of an object, see [`JSClass::new_object_with_data`].
+///
+/// It's boxed once more to get a thin pointer.
+pub(crate) type PrivateData = Box<dyn Any>;
+
+/// The addresses of the private data allocated by this crate, so that it's
+/// never confused with a private pointer set by other means, e.g. with
+/// `JSObjectSetPrivate`.
+static PRIVATE_DATA: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+
+fn private_data_addresses() -> MutexGuard<'static, BTreeSet<usize>> {
+    PRIVATE_DATA.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the private data of `object`, if it has been allocated by
+/// [`new_object_with_data()`].
+///
+/// # Safety
+///
+/// Ensure `object` is valid, and outlives the returned reference.
+pub(crate) unsafe fn private_data<'a>(object: sys::JSObjectRef) -> Option<&'a PrivateData> {
+    let private_data = sys::JSObjectGetPrivate(object).cast::<PrivateData>();
+
+    if private_data.is_null() || !private_data_addresses().contains(&(private_data as usize)) {
+        return None;
+    }
+
+    Some(&*private_data)
 }
 
-impl JSClass {
+/// Creates the class of the objects of `class` holding private data.
+///
+/// It inherits from `class`, and drops the data when its objects are
+/// finalized. Only [`new_object_with_data()`] instantiates it, so that the
+/// objects of `class` can manage their private pointer freely.
+///
+/// # Safety
+///
+/// Ensure `class` is valid.
+unsafe fn new_data_class(
+    class: sys::JSClassRef,
+    name: &CStr,
+    attributes: sys::JSClassAttributes,
+) -> sys::JSClassRef {
+    unsafe extern "C" fn finalize(object: sys::JSObjectRef) {
+        let private_data = sys::JSObjectGetPrivate(object).cast::<PrivateData>();
+
+        if private_data.is_null() || !private_data_addresses().remove(&(private_data as usize)) {
+            return;
+        }
+
+        // The finalizers of the parent classes are called next: don't let
+        // them see the dropped data.
+        sys::JSObjectSetPrivate(object, ptr::null_mut());
+        crate::panic::catch_panic_without_exception(|| drop(Box::from_raw(private_data)));
+    }
+
+    let class_definition = sys::JSClassDefinition {
+        className: name.as_ptr(),
+        // Keep the prototype chain of `class`, with or without prototype.
+        attributes,
+        parentClass: class,
+        finalize: Some(finalize),
+        ..Default::default()
+    };
+
+    sys::JSClassCreate(&class_definition)
+}
+
+/// Creates an object of `class`, holding `data`.
+///
+/// `class` must have been created by [`new_data_class()`].
+pub(crate) fn new_object_with_data<'ctx, T: 'static>(
+    ctx: sys::JSContextRef,
+    class: sys::JSClassRef,
+    data: T,
+) -> JSObject<'ctx> {
+    let private_data = Box::into_raw(Box::<PrivateData>::new(Box::new(data)));
+    private_data_addresses().insert(private_data as usize);
+
+    unsafe { JSObject::from_raw(ctx, sys::JSObjectMake(ctx, class, private_data.cast())) }
+}
+
+impl<'ctx> JSClass<'ctx> {
     /// Create a new builder to build a [`Self`].
     ///
     /// ```rust
@@ -66,12 +168,15 @@ impl JSClass {
     ///
     /// assert_eq!(result.as_number().unwrap(), 42.);
     //// ```
-    pub fn builder<N>(ctx: &JSContext, name: N) -> Result<JSClassBuilder, JSException>
+    pub fn builder<N>(ctx: &'ctx JSContext, name: N) -> Result<JSClassBuilder<'ctx>, JSException>
     where
         N: Into<Vec<u8>>,
     {
         let Ok(name) = CString::new(name) else {
-            return Err(JSValue::new_string(ctx, JSClassError::InvalidName.to_string()).into());
+            return Err(JSException::new_type_error(
+                ctx,
+                JSClassError::InvalidName.to_string(),
+            ));
         };
 
         let class_definition = sys::JSClassDefinition {
@@ -83,6 +188,11 @@ impl JSClass {
             ctx,
             name,
             class_definition,
+            parent_class: None,
+            static_names: Vec::new(),
+            static_values: Vec::new(),
+            static_functions: Vec::new(),
+            error: None,
         })
     }
 
@@ -91,8 +201,19 @@ impl JSClass {
     /// # Safety
     ///
     /// Ensure `raw` is valid.
-    unsafe fn from_raw(ctx: sys::JSContextRef, raw: sys::JSClassRef, name: CString) -> Self {
-        Self { ctx, raw, name }
+    unsafe fn from_raw(
+        ctx: sys::JSContextRef,
+        raw: sys::JSClassRef,
+        data_class: sys::JSClassRef,
+        name: CString,
+    ) -> Self {
+        Self {
+            ctx,
+            raw,
+            data_class,
+            name,
+            context: PhantomData,
+        }
     }
 
     /// Transform the `Self` into a [`JSObject`].
@@ -108,7 +229,7 @@ impl JSClass {
     ///
     /// assert!(object.is_object_of_class(&class));
     /// ```
-    pub fn new_object(&self) -> JSObject {
+    pub fn new_object(&self) -> JSObject<'ctx> {
         unsafe {
             JSObject::from_raw(
                 self.ctx,
@@ -116,11 +237,66 @@ impl JSClass {
             )
         }
     }
+
+    /// Create a new object of this class, holding `data`.
+    ///
+    /// The data can be retrieved with [`JSObject::private_data`]. It's
+    /// dropped when the object is garbage collected.
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// struct Counter {
+    ///     count: u32,
+    /// }
+    ///
+    /// let ctx = JSContext::default();
+    /// let class = JSClass::builder(&ctx, "Counter").unwrap().build().unwrap();
+    /// let object = class.new_object_with_data(Counter { count: 3 });
+    ///
+    /// assert_eq!(object.private_data::<Counter>().unwrap().count, 3);
+    /// assert!(object.private_data::<String>().is_none());
+    /// ```
+    ///
+    /// To mutate the data, wrap it in a [`RefCell`](std::cell::RefCell),
+    /// and use [`JSObject::private_data_mut`].
+    pub fn new_object_with_data<T: 'static>(&self, data: T) -> JSObject<'ctx> {
+        new_object_with_data(self.ctx, self.data_class, data)
+    }
 }
 
-impl Drop for JSClass {
+impl Drop for JSClass<'_> {
     fn drop(&mut self) {
-        unsafe { sys::JSClassRelease(self.raw) }
+        unsafe {
+            sys::JSClassRelease(self.data_class);
+            sys::JSClassRelease(self.raw);
+        }
+    }
+}
+
+/// A list of property names, filled by a
+/// [`JSObjectGetPropertyNamesCallback`](sys::JSObjectGetPropertyNamesCallback).
+///
+/// See [`crate::get_property_names_callback`].
+pub struct JSPropertyNameAccumulator {
+    raw: sys::JSPropertyNameAccumulatorRef,
+}
+
+impl JSPropertyNameAccumulator {
+    /// Create a new [`Self`] from its raw pointer directly.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `raw` is valid.
+    pub const unsafe fn from_raw(raw: sys::JSPropertyNameAccumulatorRef) -> Self {
+        Self { raw }
+    }
+
+    /// Add a property name.
+    pub fn add_name<S>(&self, name: S)
+    where
+        S: Into<JSString>,
+    {
+        unsafe { sys::JSPropertyNameAccumulatorAddName(self.raw, name.into().raw) }
     }
 }
 
@@ -137,9 +313,26 @@ pub struct JSClassBuilder<'a> {
 
     /// The class definition.
     class_definition: sys::JSClassDefinition,
+
+    /// The parent class, kept borrowed until the class is built.
+    parent_class: Option<&'a JSClass<'a>>,
+
+    /// The names of the static values and functions, which must outlive the
+    /// class definition.
+    static_names: Vec<CString>,
+
+    /// The static values, not yet terminated.
+    static_values: Vec<sys::JSStaticValue>,
+
+    /// The static functions, not yet terminated.
+    static_functions: Vec<sys::JSStaticFunction>,
+
+    /// The first error raised while configuring the class, reported by
+    /// [`JSClassBuilder::build`].
+    error: Option<JSClassError>,
 }
 
-impl JSClassBuilder<'_> {
+impl<'a> JSClassBuilder<'a> {
     /// Set a class constructor, called by [the `new` operator in JavaScript][new].
     ///
     /// The easiest way to generate a [`JSObjectCallAsConstructorCallback`] is by using the
@@ -153,35 +346,307 @@ impl JSClassBuilder<'_> {
         self
     }
 
+    /// Set the parent class. By default, the class inherits from the default
+    /// object class.
+    pub fn parent_class(mut self, parent_class: &'a JSClass<'a>) -> Self {
+        self.parent_class = Some(parent_class);
+
+        self
+    }
+
+    /// Set the class attributes, e.g. [`sys::kJSClassAttributeNoAutomaticPrototype`].
+    pub fn attributes(mut self, attributes: sys::JSClassAttributes) -> Self {
+        self.class_definition.attributes = attributes;
+
+        self
+    }
+
+    /// Declare a static value property, whose value is computed by `getter`,
+    /// and updated by `setter`.
+    ///
+    /// * `name`: The property name.
+    /// * `getter`: Called when the property is read. The easiest way to
+    ///   generate it is by using the [`crate::get_property_callback`]
+    ///   procedural macro.
+    /// * `setter`: Called when the property is written. The easiest way to
+    ///   generate it is by using the [`crate::set_property_callback`]
+    ///   procedural macro. Pass `None` along with
+    ///   [`sys::kJSPropertyAttributeReadOnly`] for a read-only property.
+    /// * `attributes`: The property attributes.
+    pub fn static_value<N>(
+        mut self,
+        name: N,
+        getter: sys::JSObjectGetPropertyCallback,
+        setter: sys::JSObjectSetPropertyCallback,
+        attributes: sys::JSPropertyAttributes,
+    ) -> Self
+    where
+        N: Into<Vec<u8>>,
+    {
+        if let Some(name) = self.static_name(name) {
+            self.static_values.push(sys::JSStaticValue {
+                name,
+                getProperty: getter,
+                setProperty: setter,
+                attributes,
+            });
+        }
+
+        self
+    }
+
+    /// Declare a static function property.
+    ///
+    /// * `name`: The property name.
+    /// * `function`: The function implementation. The easiest way to
+    ///   generate it is by using the [`crate::function_callback`] or the
+    ///   [`crate::js_function`] procedural macros.
+    /// * `attributes`: The property attributes.
+    pub fn static_function<N>(
+        mut self,
+        name: N,
+        function: sys::JSObjectCallAsFunctionCallback,
+        attributes: sys::JSPropertyAttributes,
+    ) -> Self
+    where
+        N: Into<Vec<u8>>,
+    {
+        if let Some(name) = self.static_name(name) {
+            self.static_functions.push(sys::JSStaticFunction {
+                name,
+                callAsFunction: function,
+                attributes,
+            });
+        }
+
+        self
+    }
+
+    /// Store the name of a static property, and return a pointer to it.
+    fn static_name<N>(&mut self, name: N) -> Option<*const c_char>
+    where
+        N: Into<Vec<u8>>,
+    {
+        match CString::new(name) {
+            Ok(name) => {
+                // The pointer remains valid when `name` moves into the vector.
+                let pointer = name.as_ptr();
+                self.static_names.push(name);
+
+                Some(pointer)
+            }
+            Err(_) => {
+                self.error.get_or_insert(JSClassError::InvalidPropertyName);
+
+                None
+            }
+        }
+    }
+
+    /// Set the callback called when an object is first created.
+    ///
+    /// The easiest way to generate a [`JSObjectInitializeCallback`] is by using the
+    /// [`crate::initialize_callback`] procedural macro.
+    ///
+    /// [`JSObjectInitializeCallback`]: sys::JSObjectInitializeCallback
+    pub fn initialize(mut self, initialize: sys::JSObjectInitializeCallback) -> Self {
+        self.class_definition.initialize = initialize;
+
+        self
+    }
+
+    /// Set the callback called when an object is finalized, i.e. prepared for
+    /// garbage collection.
+    ///
+    /// The easiest way to generate a [`JSObjectFinalizeCallback`] is by using the
+    /// [`crate::finalize_callback`] procedural macro.
+    ///
+    /// The private data of objects created with [`JSClass::new_object_with_data`]
+    /// is owned by this crate, and is dropped before this callback is called:
+    /// the private pointer of these objects is then null.
+    ///
+    /// [`JSObjectFinalizeCallback`]: sys::JSObjectFinalizeCallback
+    pub fn finalize(mut self, finalize: sys::JSObjectFinalizeCallback) -> Self {
+        self.class_definition.finalize = finalize;
+
+        self
+    }
+
+    /// Set the callback called when checking whether an object has a
+    /// property. It's an optimisation over [`JSClassBuilder::get_property`]
+    /// for the `in` operator.
+    ///
+    /// The easiest way to generate a [`JSObjectHasPropertyCallback`] is by using the
+    /// [`crate::has_property_callback`] procedural macro.
+    ///
+    /// [`JSObjectHasPropertyCallback`]: sys::JSObjectHasPropertyCallback
+    pub fn has_property(mut self, has_property: sys::JSObjectHasPropertyCallback) -> Self {
+        self.class_definition.hasProperty = has_property;
+
+        self
+    }
+
+    /// Set the callback called when getting a property's value.
+    ///
+    /// The easiest way to generate a [`JSObjectGetPropertyCallback`] is by using the
+    /// [`crate::get_property_callback`] procedural macro.
+    ///
+    /// [`JSObjectGetPropertyCallback`]: sys::JSObjectGetPropertyCallback
+    pub fn get_property(mut self, get_property: sys::JSObjectGetPropertyCallback) -> Self {
+        self.class_definition.getProperty = get_property;
+
+        self
+    }
+
+    /// Set the callback called when setting a property's value.
+    ///
+    /// The easiest way to generate a [`JSObjectSetPropertyCallback`] is by using the
+    /// [`crate::set_property_callback`] procedural macro.
+    ///
+    /// [`JSObjectSetPropertyCallback`]: sys::JSObjectSetPropertyCallback
+    pub fn set_property(mut self, set_property: sys::JSObjectSetPropertyCallback) -> Self {
+        self.class_definition.setProperty = set_property;
+
+        self
+    }
+
+    /// Set the callback called when deleting a property.
+    ///
+    /// The easiest way to generate a [`JSObjectDeletePropertyCallback`] is by using the
+    /// [`crate::delete_property_callback`] procedural macro.
+    ///
+    /// [`JSObjectDeletePropertyCallback`]: sys::JSObjectDeletePropertyCallback
+    pub fn delete_property(mut self, delete_property: sys::JSObjectDeletePropertyCallback) -> Self {
+        self.class_definition.deleteProperty = delete_property;
+
+        self
+    }
+
+    /// Set the callback called when collecting the names of an object's
+    /// properties, e.g. by a `for…in` loop.
+    ///
+    /// The easiest way to generate a [`JSObjectGetPropertyNamesCallback`] is by using the
+    /// [`crate::get_property_names_callback`] procedural macro.
+    ///
+    /// [`JSObjectGetPropertyNamesCallback`]: sys::JSObjectGetPropertyNamesCallback
+    pub fn get_property_names(
+        mut self,
+        get_property_names: sys::JSObjectGetPropertyNamesCallback,
+    ) -> Self {
+        self.class_definition.getPropertyNames = get_property_names;
+
+        self
+    }
+
+    /// Set the callback called when an object is called as a function.
+    ///
+    /// The easiest way to generate a [`JSObjectCallAsFunctionCallback`] is by using the
+    /// [`crate::function_callback`] procedural macro.
+    ///
+    /// [`JSObjectCallAsFunctionCallback`]: sys::JSObjectCallAsFunctionCallback
+    pub fn call_as_function(
+        mut self,
+        call_as_function: sys::JSObjectCallAsFunctionCallback,
+    ) -> Self {
+        self.class_definition.callAsFunction = call_as_function;
+
+        self
+    }
+
+    /// Set the callback called when an object is used as the target of an
+    /// `instanceof` expression.
+    ///
+    /// The easiest way to generate a [`JSObjectHasInstanceCallback`] is by using the
+    /// [`crate::has_instance_callback`] procedural macro.
+    ///
+    /// [`JSObjectHasInstanceCallback`]: sys::JSObjectHasInstanceCallback
+    pub fn has_instance(mut self, has_instance: sys::JSObjectHasInstanceCallback) -> Self {
+        self.class_definition.hasInstance = has_instance;
+
+        self
+    }
+
+    /// Set the callback called when converting an object to a particular
+    /// JavaScript type.
+    ///
+    /// The easiest way to generate a [`JSObjectConvertToTypeCallback`] is by using the
+    /// [`crate::convert_to_type_callback`] procedural macro.
+    ///
+    /// [`JSObjectConvertToTypeCallback`]: sys::JSObjectConvertToTypeCallback
+    pub fn convert_to_type(mut self, convert_to_type: sys::JSObjectConvertToTypeCallback) -> Self {
+        self.class_definition.convertToType = convert_to_type;
+
+        self
+    }
+
     /// Build a [`JSClass`].
-    pub fn build(self) -> Result<JSClass, JSException> {
+    pub fn build(mut self) -> Result<JSClass<'a>, JSException> {
+        if let Some(error) = self.error {
+            return Err(JSException::new_error(self.ctx, error.to_string()));
+        }
+
+        if let Some(parent_class) = self.parent_class {
+            self.class_definition.parentClass = parent_class.raw;
+        }
+
+        // Static values and functions are terminated by an entry with a
+        // `NULL` name.
+        if !self.static_values.is_empty() {
+            self.static_values.push(sys::JSStaticValue {
+                name: ptr::null(),
+                getProperty: None,
+                setProperty: None,
+                attributes: sys::kJSPropertyAttributeNone,
+            });
+            self.class_definition.staticValues = self.static_values.as_ptr();
+        }
+
+        if !self.static_functions.is_empty() {
+            self.static_functions.push(sys::JSStaticFunction {
+                name: ptr::null(),
+                callAsFunction: None,
+                attributes: sys::kJSPropertyAttributeNone,
+            });
+            self.class_definition.staticFunctions = self.static_functions.as_ptr();
+        }
+
         let class = unsafe { sys::JSClassCreate(&self.class_definition) };
 
         if class.is_null() {
-            return Err(JSValue::new_string(
+            return Err(JSException::new_error(
                 self.ctx,
                 JSClassError::FailedToCreateClass.to_string(),
-            )
-            .into());
+            ));
         }
 
         let class = unsafe { sys::JSClassRetain(class) };
 
         if class.is_null() {
-            return Err(JSValue::new_string(
+            return Err(JSException::new_error(
                 self.ctx,
                 JSClassError::FailedToRetainClass.to_string(),
-            )
-            .into());
+            ));
+        }
+
+        let data_class =
+            unsafe { new_data_class(class, &self.name, self.class_definition.attributes) };
+
+        if data_class.is_null() {
+            unsafe { sys::JSClassRelease(class) };
+
+            return Err(JSException::new_error(
+                self.ctx,
+                JSClassError::FailedToCreateClass.to_string(),
+            ));
         }
 
-        Ok(unsafe { JSClass::from_raw(self.ctx.raw, class, self.name) })
+        Ok(unsafe { JSClass::from_raw(self.ctx.raw, class, data_class, self.name) })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{constructor_callback, evaluate_script, function_callback};
+    use crate::{constructor_callback, evaluate_script, function_callback, JSValue};
 
     use super::*;
 
@@ -253,4 +718,409 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn class_with_static_values_and_functions() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::{get_property_callback, js_function, set_property_callback, JSString};
+
+        #[get_property_callback]
+        fn get_answer(
+            ctx: &JSContext,
+            object: &JSObject,
+            _property_name: JSString,
+        ) -> Result<Option<JSValue>, JSException> {
+            let stored = object.get_property("_answer");
+
+            Ok(Some(if stored.is_undefined() {
+                JSValue::new_number(ctx, 42.)
+            } else {
+                stored
+            }))
+        }
+
+        #[set_property_callback]
+        fn set_answer(
+            _ctx: &JSContext,
+            object: &JSObject,
+            _property_name: JSString,
+            value: JSValue,
+        ) -> Result<bool, JSException> {
+            object.set_property("_answer", value)?;
+
+            Ok(true)
+        }
+
+        #[js_function]
+        fn double(x: f64) -> f64 {
+            x * 2.
+        }
+
+        let ctx = JSContext::default();
+        let class = JSClass::builder(&ctx, "Foo")?
+            .static_value(
+                "answer",
+                Some(get_answer),
+                Some(set_answer),
+                sys::kJSPropertyAttributeDontDelete,
+            )
+            .static_value(
+                "constant",
+                Some(get_answer),
+                None,
+                sys::kJSPropertyAttributeReadOnly,
+            )
+            .static_function("double", Some(double), sys::kJSPropertyAttributeNone)
+            .build()?;
+
+        ctx.global_object()?
+            .set_property("foo", class.new_object().into())?;
+
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert_eq!(eval("foo.answer")?.as_number()?, 42.);
+        assert_eq!(eval("foo.answer = 7; foo.answer")?.as_number()?, 7.);
+        assert_eq!(eval("foo.constant = 7; foo.constant")?.as_number()?, 42.);
+        assert_eq!(eval("foo.double(21)")?.as_number()?, 42.);
+
+        // A child class inherits the static properties of its parent.
+        let child = JSClass::builder(&ctx, "Bar")?
+            .parent_class(&class)
+            .build()?;
+        ctx.global_object()?
+            .set_property("bar", child.new_object().into())?;
+
+        assert_eq!(eval("bar.double(bar.constant)")?.as_number()?, 84.);
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_invalid_static_name() {
+        let ctx = JSContext::default();
+
+        assert!(JSClass::builder(&ctx, "Foo")
+            .unwrap()
+            .static_function("a\0b", None, sys::kJSPropertyAttributeNone)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn class_with_dynamic_properties() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::{
+            delete_property_callback, get_property_callback, get_property_names_callback,
+            has_property_callback, set_property_callback, JSPropertyNameAccumulator, JSString,
+        };
+
+        #[has_property_callback]
+        fn has_property(_ctx: &JSContext, _object: &JSObject, property_name: JSString) -> bool {
+            property_name == "magic"
+        }
+
+        #[get_property_callback]
+        fn get_property(
+            ctx: &JSContext,
+            _object: &JSObject,
+            property_name: JSString,
+        ) -> Result<Option<JSValue>, JSException> {
+            Ok((property_name == "magic").then(|| JSValue::new_number(ctx, 42.)))
+        }
+
+        #[set_property_callback]
+        fn set_property(
+            ctx: &JSContext,
+            _object: &JSObject,
+            property_name: JSString,
+            _value: JSValue,
+        ) -> Result<bool, JSException> {
+            if property_name == "magic" {
+                return Err(JSException::new_type_error(ctx, "`magic` is read-only"));
+            }
+
+            Ok(false)
+        }
+
+        #[delete_property_callback]
+        fn delete_property(
+            _ctx: &JSContext,
+            _object: &JSObject,
+            property_name: JSString,
+        ) -> Result<bool, JSException> {
+            Ok(property_name == "magic")
+        }
+
+        #[get_property_names_callback]
+        fn get_property_names(
+            _ctx: &JSContext,
+            _object: &JSObject,
+            property_names: &JSPropertyNameAccumulator,
+        ) {
+            property_names.add_name("magic");
+        }
+
+        let ctx = JSContext::default();
+        let class = JSClass::builder(&ctx, "Foo")?
+            .has_property(Some(has_property))
+            .get_property(Some(get_property))
+            .set_property(Some(set_property))
+            .delete_property(Some(delete_property))
+            .get_property_names(Some(get_property_names))
+            .build()?;
+
+        ctx.global_object()?
+            .set_property("foo", class.new_object().into())?;
+
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert!(eval("'magic' in foo")?.as_boolean());
+        assert_eq!(eval("foo.magic")?.as_number()?, 42.);
+        assert!(eval("foo.other")?.is_undefined());
+        assert!(eval("foo.magic = 1").is_err());
+        assert_eq!(eval("foo.other = 1; foo.other")?.as_number()?, 1.);
+        assert!(eval("delete foo.magic")?.as_boolean());
+        assert_eq!(
+            eval(
+                "const names = []; for (const name in foo) names.push(name); names.sort().join()"
+            )?
+            .as_string()?,
+            "magic,other"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_conversion_and_instance_checks() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::{convert_to_type_callback, function_callback, has_instance_callback, JSType};
+
+        #[convert_to_type_callback]
+        fn convert_to_type(
+            ctx: &JSContext,
+            _object: &JSObject,
+            r#type: JSType,
+        ) -> Result<Option<JSValue>, JSException> {
+            Ok(match r#type {
+                JSType::Number => Some(JSValue::new_number(ctx, 42.)),
+                JSType::String => Some(JSValue::new_string(ctx, "forty-two")),
+                _ => None,
+            })
+        }
+
+        #[has_instance_callback]
+        fn has_instance(
+            _ctx: &JSContext,
+            _constructor: &JSObject,
+            possible_instance: JSValue,
+        ) -> Result<bool, JSException> {
+            Ok(possible_instance.is_number())
+        }
+
+        #[function_callback]
+        fn call_as_function(
+            ctx: &JSContext,
+            _function: Option<&JSObject>,
+            _this_object: Option<&JSObject>,
+            arguments: &[JSValue],
+        ) -> Result<JSValue, JSException> {
+            Ok(JSValue::new_number(ctx, arguments.len() as f64))
+        }
+
+        let ctx = JSContext::default();
+        let class = JSClass::builder(&ctx, "Foo")?
+            .convert_to_type(Some(convert_to_type))
+            .has_instance(Some(has_instance))
+            .call_as_function(Some(call_as_function))
+            .build()?;
+
+        ctx.global_object()?
+            .set_property("Foo", class.new_object().into())?;
+
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert_eq!(eval("Foo * 2")?.as_number()?, 84.);
+        assert_eq!(eval("`${Foo}`")?.as_string()?, "forty-two");
+        assert!(eval("1 instanceof Foo")?.as_boolean());
+        assert!(!eval("'1' instanceof Foo")?.as_boolean());
+        assert_eq!(eval("Foo(1, 2, 3)")?.as_number()?, 3.);
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_initialize_and_finalize() -> Result<(), JSException> {
+        use std::{
+            ffi::c_void,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        use crate as javascriptcore;
+        use crate::{finalize_callback, garbage_collect, initialize_callback};
+
+        static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+        #[initialize_callback]
+        fn initialize(ctx: &JSContext, object: &JSObject) {
+            object
+                .set_property("initialized", JSValue::new_boolean(ctx, true))
+                .unwrap();
+        }
+
+        #[finalize_callback]
+        fn finalize(_private_data: *mut c_void) {
+            FINALIZED.fetch_add(1, Ordering::SeqCst);
+        }
+
+        {
+            let ctx = JSContext::default();
+            let class = JSClass::builder(&ctx, "Foo")?
+                .initialize(Some(initialize))
+                .finalize(Some(finalize))
+                .build()?;
+
+            let object = class.new_object();
+            assert!(object.get_property("initialized").as_boolean());
+
+            garbage_collect(&ctx);
+        }
+
+        // Releasing the context finalizes its objects.
+        assert_eq!(FINALIZED.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_from_impl_block() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::js_class;
+
+        struct Counter {
+            count: u32,
+        }
+
+        #[js_class]
+        impl Counter {
+            #[js(constructor)]
+            fn new(start: Option<u32>) -> Self {
+                Self {
+                    count: start.unwrap_or(0),
+                }
+            }
+
+            #[js(method)]
+            fn increment(&mut self, step: Option<u32>) -> u32 {
+                self.count += step.unwrap_or(1);
+                self.count
+            }
+
+            #[js(method)]
+            fn call_with_count(
+                &self,
+                ctx: &JSContext,
+                callback: JSObject,
+            ) -> Result<JSValue, JSException> {
+                callback.call_as_function(None, &[JSValue::new_number(ctx, self.count.into())])
+            }
+
+            #[js(getter)]
+            fn count(&self) -> u32 {
+                self.count
+            }
+
+            #[js(setter)]
+            fn set_count(&mut self, count: u32) {
+                self.count = count;
+            }
+
+            #[js(getter, name = "isZero")]
+            fn is_zero(&self) -> bool {
+                self.count == 0
+            }
+
+            #[js(static)]
+            fn maximum() -> u32 {
+                u32::MAX
+            }
+        }
+
+        let ctx = JSContext::default();
+        Counter::register(&ctx)?;
+        assert!(ctx.global_object()?.has_property("Counter"));
+
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert_eq!(
+            eval("const counter = new Counter(2); counter.increment(); counter.increment(3)")?
+                .as_number()?,
+            6.
+        );
+        assert!(eval("counter instanceof Counter")?.as_boolean());
+        assert_eq!(eval("counter.count")?.as_number()?, 6.);
+        assert_eq!(eval("counter.count = 1; counter.count")?.as_number()?, 1.);
+        assert!(!eval("counter.isZero")?.as_boolean());
+        assert!(eval("new Counter().isZero")?.as_boolean());
+        assert_eq!(eval("Counter.maximum()")?.as_number()?, f64::from(u32::MAX));
+        assert_eq!(
+            eval("counter.callWithCount((count) => count * 2)")?.as_number()?,
+            2.
+        );
+
+        // Conversion errors.
+        let error = eval("counter.count = 'one'").unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+
+        let error = eval("new Counter('one')").unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+
+        // `this` must be an instance.
+        let error = eval("counter.increment.call({})").unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+
+        // `&mut self` methods cannot be called while the instance is in use.
+        let error = eval("counter.callWithCount(() => counter.increment())").unwrap_err();
+        assert_eq!(error.name()?, "Error");
+
+        // Values can be moved into instances from Rust.
+        let object = Counter { count: 42 }.into_js_object(&ctx)?;
+        assert_eq!(object.get_property("count").as_number()?, 42.);
+
+        ctx.global_object()?.set_property("answer", object.into())?;
+        assert!(eval("answer instanceof Counter")?.as_boolean());
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_from_impl_block_without_constructor() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::js_class;
+
+        struct Token(String);
+
+        #[js_class(name = "SecretToken")]
+        impl Token {
+            #[js(method)]
+            fn reveal(&self) -> String {
+                self.0.clone()
+            }
+        }
+
+        let ctx = JSContext::default();
+        Token::register(&ctx)?;
+
+        let error = evaluate_script(&ctx, "new SecretToken()", None, "test.js", 1).unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+
+        ctx.global_object()?.set_property(
+            "token",
+            Token(String::from("hunter2")).into_js_object(&ctx)?.into(),
+        )?;
+        let result = evaluate_script(&ctx, "token.reveal()", None, "test.js", 1)?;
+        assert_eq!(result.as_string()?, "hunter2");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,286 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for Rust closures as JavaScript functions.
+//!
+//! A closure is boxed into the private data of an object of a dedicated
+//! class, whose `callAsFunction` callback calls the closure, and whose
+//! `finalize` callback drops it.
+
+use std::{cell::RefCell, ffi::CStr, ptr, slice, sync::OnceLock};
+
+use crate::{
+    class::StaticClass, panic::catch_panic, sys, JSContext, JSException, JSObject, JSString,
+    JSValue,
+};
+
+/// The signature of a closure implementing a JavaScript function.
+pub(crate) type Function = dyn for<'ctx> Fn(
+    &'ctx JSContext,
+    Option<&JSObject<'ctx>>,
+    &[JSValue<'ctx>],
+) -> Result<JSValue<'ctx>, JSException>;
+
+/// The signature of a mutable closure implementing a JavaScript function.
+pub(crate) type FunctionMut = dyn for<'ctx> FnMut(
+    &'ctx JSContext,
+    Option<&JSObject<'ctx>>,
+    &[JSValue<'ctx>],
+) -> Result<JSValue<'ctx>, JSException>;
+
+/// The private data of a closure function.
+pub(crate) enum Closure {
+    Fn(Box<Function>),
+    FnMut(RefCell<Box<FunctionMut>>),
+}
+
+/// The class of the closure functions, shared by all contexts.
+fn closure_class() -> sys::JSClassRef {
+    static CLASS: OnceLock<StaticClass> = OnceLock::new();
+
+    CLASS
+        .get_or_init(|| {
+            const NAME: &CStr = c"RustClosure";
+
+            let class_definition = sys::JSClassDefinition {
+                className: NAME.as_ptr(),
+                callAsFunction: Some(call_as_function),
+                finalize: Some(finalize),
+                ..Default::default()
+            };
+
+            StaticClass(unsafe { sys::JSClassCreate(&class_definition) })
+        })
+        .0
+}
+
+/// Creates a function object whose implementation is `closure`.
+pub(crate) fn new_function<'ctx>(
+    ctx: &'ctx JSContext,
+    name: JSString,
+    closure: Closure,
+) -> JSValue<'ctx> {
+    let private_data = Box::into_raw(Box::new(closure));
+    let function = unsafe { sys::JSObjectMake(ctx.raw, closure_class(), private_data.cast()) };
+
+    // Define `name` before changing the prototype, as `Function.prototype.name`
+    // is read-only and would shadow it.
+    unsafe {
+        let name_property = JSString::from("name");
+
+        sys::JSObjectSetProperty(
+            ctx.raw,
+            function,
+            name_property.raw,
+            sys::JSValueMakeString(ctx.raw, name.raw),
+            sys::kJSPropertyAttributeReadOnly | sys::kJSPropertyAttributeDontEnum,
+            ptr::null_mut(),
+        );
+    }
+
+    // Make the function behave like any other function, with `call`, `apply`,
+    // `bind` etc.
+    if let Ok(function_prototype) = ctx
+        .global_object()
+        .map(|global_object| global_object.get_property("Function"))
+        .and_then(|function_constructor| function_constructor.as_object())
+        .map(|function_constructor| function_constructor.get_property("prototype"))
+    {
+        unsafe { sys::JSObjectSetPrototype(ctx.raw, function, function_prototype.raw) };
+    }
+
+    unsafe { JSValue::from_raw(ctx.raw, function) }
+}
+
+unsafe extern "C" fn call_as_function(
+    raw_ctx: sys::JSContextRef,
+    function: sys::JSObjectRef,
+    this_object: sys::JSObjectRef,
+    argument_count: usize,
+    arguments: *const sys::JSValueRef,
+    exception: *mut sys::JSValueRef,
+) -> sys::JSValueRef {
+    let ctx = JSContext::borrow_raw(raw_ctx);
+    let closure = &*sys::JSObjectGetPrivate(function).cast::<Closure>();
+
+    let this_object = JSObject::from_raw(raw_ctx, this_object);
+    let this_object = (!this_object.raw.is_null()).then_some(&this_object);
+
+    let arguments = if argument_count == 0 || arguments.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(arguments, argument_count)
+            .iter()
+            .map(|value| JSValue::from_raw(raw_ctx, *value))
+            .collect::<Vec<_>>()
+    };
+
+    let result = catch_panic(&ctx, || match closure {
+        Closure::Fn(function) => function(&ctx, this_object, &arguments),
+        Closure::FnMut(function) => match function.try_borrow_mut() {
+            Ok(mut function) => function(&ctx, this_object, &arguments),
+            Err(_) => Err(JSException::new_builtin(
+                raw_ctx,
+                "Error",
+                "a `FnMut` closure cannot be called reentrantly",
+            )),
+        },
+    });
+
+    // The returned value, or the exception, may come from another context
+    // group.
+    let result = result.and_then(|value| value.check_context(raw_ctx).map(|()| value));
+
+    match result {
+        Ok(value) => {
+            *exception = ptr::null_mut();
+
+            value.raw
+        }
+        Err(error) => {
+            let error = match error.underlying_value().check_context(raw_ctx) {
+                Ok(()) => error,
+                Err(type_error) => type_error,
+            };
+            *exception = error.into();
+
+            ptr::null()
+        }
+    }
+}
+
+unsafe extern "C" fn finalize(function: sys::JSObjectRef) {
+    let private_data = sys::JSObjectGetPrivate(function).cast::<Closure>();
+
+    if !private_data.is_null() {
+        drop(Box::from_raw(private_data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{evaluate_script, garbage_collect, JSContext, JSException, JSValue};
+
+    #[test]
+    fn closure_captures_state() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+        let factor = 3.;
+
+        let multiply =
+            JSValue::new_function_from_closure(&ctx, "multiply", move |ctx, _this, arguments| {
+                let number = arguments
+                    .first()
+                    .map(JSValue::as_number)
+                    .transpose()?
+                    .unwrap_or(0.);
+
+                Ok(JSValue::new_number(ctx, number * factor))
+            });
+        ctx.global_object()?.set_property("multiply", multiply)?;
+
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert_eq!(eval("multiply(2)")?.as_number()?, 6.);
+        assert_eq!(eval("multiply.call(null, 3)")?.as_number()?, 9.);
+        assert_eq!(eval("multiply.name")?.as_string()?, "multiply");
+        assert_eq!(eval("typeof multiply")?.as_string()?, "function");
+        assert!(eval("multiply instanceof Function")?.as_boolean());
+
+        Ok(())
+    }
+
+    #[test]
+    fn closure_receives_this() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        let get_x = JSValue::new_function_from_closure(&ctx, "getX", |ctx, this, _arguments| {
+            Ok(this.map_or_else(
+                || JSValue::new_undefined(ctx),
+                |this| this.get_property("x"),
+            ))
+        });
+        ctx.global_object()?.set_property("getX", get_x)?;
+
+        let result = evaluate_script(&ctx, "({ x: 42, getX }).getX()", None, "test.js", 1)?;
+        assert_eq!(result.as_number()?, 42.);
+
+        Ok(())
+    }
+
+    #[test]
+    fn closure_mut() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+        let mut counter = 0.;
+
+        let increment = JSValue::new_function_from_closure_mut(
+            &ctx,
+            "increment",
+            move |ctx, _this, _arguments| {
+                counter += 1.;
+
+                Ok(JSValue::new_number(ctx, counter))
+            },
+        );
+        ctx.global_object()?.set_property("increment", increment)?;
+
+        let result = evaluate_script(&ctx, "increment(); increment()", None, "test.js", 1)?;
+        assert_eq!(result.as_number()?, 2.);
+
+        Ok(())
+    }
+
+    #[test]
+    fn closure_mut_reentrancy() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        let reenter =
+            JSValue::new_function_from_closure_mut(&ctx, "reenter", |_ctx, _this, arguments| {
+                arguments[0].as_object()?.call_as_function(None, &[])
+            });
+        ctx.global_object()?.set_property("reenter", reenter)?;
+
+        let error =
+            evaluate_script(&ctx, "reenter(() => reenter())", None, "test.js", 1).unwrap_err();
+        assert_eq!(error.name()?, "Error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn closure_is_dropped() -> Result<(), JSException> {
+        struct Guard(Rc<Cell<bool>>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(false));
+
+        {
+            let ctx = JSContext::default();
+            let guard = Guard(dropped.clone());
+
+            let _function =
+                JSValue::new_function_from_closure(&ctx, "f", move |ctx, _this, _arguments| {
+                    let _ = &guard;
+
+                    Ok(JSValue::new_undefined(ctx))
+                });
+
+            assert!(!dropped.get());
+            garbage_collect(&ctx);
+        }
+
+        // Releasing the context finalizes its objects.
+        assert!(dropped.get());
+
+        Ok(())
+    }
+}
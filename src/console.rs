@@ -0,0 +1,1006 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A `console` object for a [`JSContext`].
+//!
+//! This module is available when the `console` feature is enabled. A
+//! context doesn't have a `console` by default: [`install`] defines one on
+//! its global object, writing to a [`Sink`]. The arguments are formatted
+//! like in browsers, with `printf`-like substitutions (`%s`, `%d`, `%i`,
+//! `%f`, `%o`, `%O`, `%c`), and objects inspected like in Node.js.
+//!
+//! The provided sinks write to the standard output and error
+//! ([`StdioSink`]), to a `Vec` ([`CaptureSink`]), to the `log` crate
+//! (`LogSink`, with the `log` feature) or to `tracing` (`TracingSink`,
+//! with the `tracing` feature). Any closure receiving a [`Message`] is a
+//! sink too.
+//!
+//! ```
+//! # use javascriptcore::{console::{self, CaptureSink, Level}, evaluate_script, JSContext};
+//! let ctx = JSContext::default();
+//! let sink = CaptureSink::default();
+//! console::install(&ctx, sink.clone()).unwrap();
+//!
+//! evaluate_script(
+//!     &ctx,
+//!     "console.log('%s has %d items', 'cart', 3, { total: 42 });
+//!      console.warn([1, 'two', null]);",
+//!     None,
+//!     "test.js",
+//!     1,
+//! )
+//! .unwrap();
+//!
+//! let messages = sink.take();
+//! assert_eq!(messages[0].level, Level::Info);
+//! assert_eq!(messages[0].text, "cart has 3 items { total: 42 }");
+//! assert_eq!(messages[1].level, Level::Warn);
+//! assert_eq!(messages[1].text, "[ 1, 'two', null ]");
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+    time::Instant,
+};
+
+use crate::{sys, JSContext, JSException, JSObject, JSType, JSValue};
+
+/// The level of a console message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// `console.trace()`.
+    Trace,
+    /// `console.debug()`.
+    Debug,
+    /// `console.log()`, `console.info()`, and the other informative
+    /// methods, like `console.count()`.
+    Info,
+    /// `console.warn()`.
+    Warn,
+    /// `console.error()`, and the failed `console.assert()`.
+    Error,
+}
+
+/// A message written to the console.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    /// The level of the message.
+    pub level: Level,
+    /// The formatted arguments, without indentation.
+    pub text: String,
+    /// The number of groups the message is in, see `console.group()`.
+    pub group_depth: usize,
+}
+
+impl fmt::Display for Message {
+    /// Formats the text, indented by two spaces per group.
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indentation = "  ".repeat(self.group_depth);
+
+        for (index, line) in self.text.split('\n').enumerate() {
+            if index > 0 {
+                formatter.write_str("\n")?;
+            }
+
+            write!(formatter, "{indentation}{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The destination of the console messages.
+pub trait Sink {
+    /// Writes `message`.
+    fn write(&self, message: &Message);
+}
+
+impl<F> Sink for F
+where
+    F: Fn(&Message),
+{
+    fn write(&self, message: &Message) {
+        self(message)
+    }
+}
+
+/// A sink writing the warnings and the errors to the standard error, and
+/// the other messages to the standard output.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdioSink;
+
+impl Sink for StdioSink {
+    fn write(&self, message: &Message) {
+        // There is nowhere to report a closed output to.
+        let _ = match message.level {
+            Level::Warn | Level::Error => writeln!(io::stderr(), "{message}"),
+            _ => writeln!(io::stdout(), "{message}"),
+        };
+    }
+}
+
+/// A sink keeping the messages, e.g. to check them in tests.
+///
+/// The clones share the messages, so that a clone can be installed while
+/// the original is kept to read them.
+#[derive(Clone, Debug, Default)]
+pub struct CaptureSink {
+    messages: Rc<RefCell<Vec<Message>>>,
+}
+
+impl CaptureSink {
+    /// Returns a copy of the messages written so far.
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.borrow().clone()
+    }
+
+    /// Returns the messages written so far, and forgets them.
+    pub fn take(&self) -> Vec<Message> {
+        self.messages.take()
+    }
+}
+
+impl Sink for CaptureSink {
+    fn write(&self, message: &Message) {
+        self.messages.borrow_mut().push(message.clone());
+    }
+}
+
+/// A sink writing to the `log` crate, with the `javascript` target.
+///
+/// This requires the `log` feature.
+#[cfg(feature = "log")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogSink;
+
+#[cfg(feature = "log")]
+impl Sink for LogSink {
+    fn write(&self, message: &Message) {
+        let level = match message.level {
+            Level::Trace => log::Level::Trace,
+            Level::Debug => log::Level::Debug,
+            Level::Info => log::Level::Info,
+            Level::Warn => log::Level::Warn,
+            Level::Error => log::Level::Error,
+        };
+
+        log::log!(target: "javascript", level, "{message}");
+    }
+}
+
+/// A sink emitting `tracing` events, with the `javascript` target.
+///
+/// This requires the `tracing` feature.
+#[cfg(feature = "tracing")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl Sink for TracingSink {
+    fn write(&self, message: &Message) {
+        use tracing::{event, Level as TracingLevel};
+
+        // The level of an event must be a constant.
+        match message.level {
+            Level::Trace => event!(target: "javascript", TracingLevel::TRACE, "{message}"),
+            Level::Debug => event!(target: "javascript", TracingLevel::DEBUG, "{message}"),
+            Level::Info => event!(target: "javascript", TracingLevel::INFO, "{message}"),
+            Level::Warn => event!(target: "javascript", TracingLevel::WARN, "{message}"),
+            Level::Error => event!(target: "javascript", TracingLevel::ERROR, "{message}"),
+        }
+    }
+}
+
+/// The state of an installed console.
+struct Console {
+    sink: Box<dyn Sink>,
+    group_depth: Cell<usize>,
+    timers: RefCell<HashMap<String, Instant>>,
+    counts: RefCell<HashMap<String, u64>>,
+}
+
+impl Console {
+    fn write(&self, level: Level, text: String) {
+        self.sink.write(&Message {
+            level,
+            text,
+            group_depth: self.group_depth.get(),
+        });
+    }
+}
+
+/// The signature of the implementation of a console method.
+type Method = fn(&Console, &JSContext, &[JSValue<'_>]) -> Result<(), JSException>;
+
+/// Defines `console` on the global object of `ctx`, writing to `sink`.
+///
+/// It implements `log`, `info`, `debug`, `warn`, `error`, `trace`,
+/// `assert`, `count`, `countReset`, `time`, `timeLog`, `timeEnd`, `group`,
+/// `groupCollapsed` and `groupEnd`.
+pub fn install<S: Sink + 'static>(ctx: &JSContext, sink: S) -> Result<(), JSException> {
+    let console = Rc::new(Console {
+        sink: Box::new(sink),
+        group_depth: Cell::new(0),
+        timers: RefCell::default(),
+        counts: RefCell::default(),
+    });
+
+    let methods: [(&str, Method); 15] = [
+        ("log", |console, ctx, arguments| {
+            console.write(Level::Info, format(ctx, arguments)?);
+            Ok(())
+        }),
+        ("info", |console, ctx, arguments| {
+            console.write(Level::Info, format(ctx, arguments)?);
+            Ok(())
+        }),
+        ("debug", |console, ctx, arguments| {
+            console.write(Level::Debug, format(ctx, arguments)?);
+            Ok(())
+        }),
+        ("warn", |console, ctx, arguments| {
+            console.write(Level::Warn, format(ctx, arguments)?);
+            Ok(())
+        }),
+        ("error", |console, ctx, arguments| {
+            console.write(Level::Error, format(ctx, arguments)?);
+            Ok(())
+        }),
+        ("trace", trace),
+        ("assert", assert),
+        ("count", |console, _ctx, arguments| {
+            let label = label(arguments)?;
+            let count = {
+                let mut counts = console.counts.borrow_mut();
+                let count = counts.entry(label.clone()).or_default();
+                *count += 1;
+                *count
+            };
+
+            console.write(Level::Info, format!("{label}: {count}"));
+            Ok(())
+        }),
+        ("countReset", |console, _ctx, arguments| {
+            let label = label(arguments)?;
+
+            if console.counts.borrow_mut().remove(&label).is_none() {
+                console.write(Level::Warn, format!("Count for '{label}' does not exist"));
+            }
+            Ok(())
+        }),
+        ("time", |console, _ctx, arguments| {
+            let label = label(arguments)?;
+
+            if console.timers.borrow().contains_key(&label) {
+                console.write(Level::Warn, format!("Timer '{label}' already exists"));
+            } else {
+                console.timers.borrow_mut().insert(label, Instant::now());
+            }
+            Ok(())
+        }),
+        ("timeLog", |console, ctx, arguments| {
+            time_log(console, ctx, arguments, false)
+        }),
+        ("timeEnd", |console, ctx, arguments| {
+            time_log(console, ctx, arguments, true)
+        }),
+        ("group", group),
+        ("groupCollapsed", group),
+        ("groupEnd", |console, _ctx, _arguments| {
+            console
+                .group_depth
+                .set(console.group_depth.get().saturating_sub(1));
+            Ok(())
+        }),
+    ];
+
+    let object = JSObject::new(ctx);
+
+    for (name, method) in methods {
+        let console = Rc::clone(&console);
+        let function =
+            JSValue::new_function_from_closure(ctx, name, move |ctx, _this, arguments| {
+                method(&console, ctx, arguments)?;
+
+                Ok(JSValue::new_undefined(ctx))
+            });
+
+        object.set_property(name, function)?;
+    }
+
+    ctx.global_object()?.set_property("console", object.into())
+}
+
+/// `console.trace()`: writes the arguments, and the stack trace.
+fn trace(console: &Console, ctx: &JSContext, arguments: &[JSValue<'_>]) -> Result<(), JSException> {
+    let mut text = String::from("Trace");
+
+    if !arguments.is_empty() {
+        text.push_str(": ");
+        text.push_str(&format(ctx, arguments)?);
+    }
+
+    // The stack of a new error starts at the caller of `console.trace()`.
+    for frame in JSException::new_error(ctx, "").stack_frames() {
+        text.push_str(&format!("\n    at {frame}"));
+    }
+
+    console.write(Level::Trace, text);
+    Ok(())
+}
+
+/// `console.assert()`: writes the arguments as an error if the assertion,
+/// the first argument, is falsy.
+fn assert(
+    console: &Console,
+    ctx: &JSContext,
+    arguments: &[JSValue<'_>],
+) -> Result<(), JSException> {
+    if arguments.first().is_some_and(JSValue::as_boolean) {
+        return Ok(());
+    }
+
+    let text = match arguments.get(1..) {
+        Some(arguments) if !arguments.is_empty() => {
+            format!("Assertion failed: {}", format(ctx, arguments)?)
+        }
+        _ => String::from("Assertion failed"),
+    };
+
+    console.write(Level::Error, text);
+    Ok(())
+}
+
+/// `console.group()`: writes the label, if any, and indents the next
+/// messages.
+fn group(console: &Console, ctx: &JSContext, arguments: &[JSValue<'_>]) -> Result<(), JSException> {
+    if !arguments.is_empty() {
+        console.write(Level::Info, format(ctx, arguments)?);
+    }
+
+    console.group_depth.set(console.group_depth.get() + 1);
+    Ok(())
+}
+
+/// `console.timeLog()` and `console.timeEnd()`: writes the time elapsed
+/// since `console.time()`.
+fn time_log(
+    console: &Console,
+    ctx: &JSContext,
+    arguments: &[JSValue<'_>],
+    end: bool,
+) -> Result<(), JSException> {
+    let label = label(arguments)?;
+    let start = if end {
+        console.timers.borrow_mut().remove(&label)
+    } else {
+        console.timers.borrow().get(&label).copied()
+    };
+
+    let Some(start) = start else {
+        console.write(Level::Warn, format!("Timer '{label}' does not exist"));
+        return Ok(());
+    };
+
+    let milliseconds = start.elapsed().as_secs_f64() * 1000.;
+    let mut text = format!("{label}: {milliseconds:.3}ms");
+
+    if !end && arguments.len() > 1 {
+        text.push(' ');
+        text.push_str(&format(ctx, &arguments[1..])?);
+    }
+
+    console.write(Level::Info, text);
+    Ok(())
+}
+
+/// Returns the label of a counter or a timer, the first of `arguments`.
+fn label(arguments: &[JSValue<'_>]) -> Result<String, JSException> {
+    match arguments.first() {
+        Some(label) if !label.is_undefined() => Ok(label.as_string()?.to_string()),
+        _ => Ok(String::from("default")),
+    }
+}
+
+/// Formats the arguments of a console method: the substitutions of the
+/// first argument if it's a string, then the other arguments, separated by
+/// spaces. Strings are written as is, and other values are inspected.
+fn format(ctx: &JSContext, arguments: &[JSValue<'_>]) -> Result<String, JSException> {
+    let mut parts = Vec::with_capacity(arguments.len());
+    let mut arguments = arguments.iter();
+
+    if let Some(first) = arguments
+        .as_slice()
+        .first()
+        .filter(|first| first.is_string())
+    {
+        arguments.next();
+        parts.push(substitute(
+            ctx,
+            &first.as_string()?.to_string(),
+            &mut arguments,
+        )?);
+    }
+
+    for argument in arguments {
+        parts.push(if argument.is_string() {
+            argument.as_string()?.to_string()
+        } else {
+            inspect(ctx, argument, 0, &mut Vec::new())?
+        });
+    }
+
+    Ok(parts.join(" "))
+}
+
+/// Replaces the substitutions of `format` with the next `arguments`.
+fn substitute<'a, 'ctx: 'a>(
+    ctx: &JSContext,
+    format: &str,
+    arguments: &mut impl Iterator<Item = &'a JSValue<'ctx>>,
+) -> Result<String, JSException> {
+    let mut text = String::with_capacity(format.len());
+    let mut characters = format.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        if character != '%' {
+            text.push(character);
+            continue;
+        }
+
+        let Some(&specifier) = characters.peek() else {
+            text.push('%');
+            break;
+        };
+
+        if specifier == '%' {
+            characters.next();
+            text.push('%');
+            continue;
+        }
+
+        if !matches!(specifier, 's' | 'd' | 'i' | 'f' | 'o' | 'O' | 'c') {
+            text.push('%');
+            continue;
+        }
+
+        let Some(argument) = arguments.next() else {
+            // Like in browsers, substitutions without argument are kept.
+            text.push('%');
+            continue;
+        };
+        characters.next();
+
+        match specifier {
+            's' if argument.is_string() => text.push_str(&argument.as_string()?.to_string()),
+            's' => text.push_str(&inspect(ctx, argument, 1, &mut Vec::new())?),
+            'd' | 'i' => {
+                let number = argument.as_number()?;
+                text.push_str(&number_to_string(ctx, number.trunc())?);
+            }
+            'f' => text.push_str(&number_to_string(ctx, argument.as_number()?)?),
+            'o' | 'O' => text.push_str(&inspect(ctx, argument, 0, &mut Vec::new())?),
+            // CSS isn't supported, but its argument is consumed.
+            _ => {}
+        }
+    }
+
+    Ok(text)
+}
+
+/// Formats `number` like JavaScript.
+fn number_to_string(ctx: &JSContext, number: f64) -> Result<String, JSException> {
+    Ok(JSValue::new_number(ctx, number).as_string()?.to_string())
+}
+
+/// The depth after which nested objects are abbreviated, like in Node.js.
+const MAX_DEPTH: usize = 2;
+
+/// The number of items of an array, or properties of an object, after
+/// which the rest are only counted, like in Node.js.
+const MAX_ITEMS: u32 = 100;
+
+/// Formats `value` like the `util.inspect()` function of Node.js, e.g.
+/// `{ name: 'value', items: [ 1, 2 ] }`.
+///
+/// `seen` holds the objects being inspected, to detect cycles.
+fn inspect(
+    ctx: &JSContext,
+    value: &JSValue<'_>,
+    depth: usize,
+    seen: &mut Vec<sys::JSValueRef>,
+) -> Result<String, JSException> {
+    Ok(match value.get_type() {
+        JSType::Undefined => String::from("undefined"),
+        JSType::Null => String::from("null"),
+        JSType::Boolean | JSType::Number => value.as_string()?.to_string(),
+        JSType::BigInt => format!("{}n", value.as_string()?),
+        JSType::String => quote(&value.as_string()?.to_string()),
+        JSType::Symbol => call_global(ctx, "String", value)?,
+        JSType::Object => {
+            if seen.contains(&value.raw) {
+                return Ok(String::from("[Circular]"));
+            }
+
+            seen.push(value.raw);
+            let text = inspect_object(ctx, &value.as_object()?, depth, seen);
+            seen.pop();
+
+            text?
+        }
+    })
+}
+
+/// Formats `object`, see [`inspect`].
+fn inspect_object(
+    ctx: &JSContext,
+    object: &JSObject<'_>,
+    depth: usize,
+    seen: &mut Vec<sys::JSValueRef>,
+) -> Result<String, JSException> {
+    if object.is_function() {
+        let name = object.get_property("name");
+        let name = if name.is_string() {
+            name.as_string()?.to_string()
+        } else {
+            String::new()
+        };
+
+        return Ok(if name.is_empty() {
+            String::from("[Function (anonymous)]")
+        } else {
+            format!("[Function: {name}]")
+        });
+    }
+
+    if object.is_date() {
+        return Ok(if object.as_number()?.is_nan() {
+            String::from("Invalid Date")
+        } else {
+            call_method(object, "toISOString")?
+        });
+    }
+
+    let error_constructor = ctx.global_object()?.get_property("Error").as_object()?;
+    if object.is_instance_of(&error_constructor)? {
+        return call_method(object, "toString");
+    }
+
+    if object.is_array() {
+        if depth > MAX_DEPTH {
+            return Ok(String::from("[Array]"));
+        }
+
+        let length = object.get_property("length").as_number()? as u32;
+        let mut items = (0..length.min(MAX_ITEMS))
+            .map(|index| inspect_property(ctx, object, &index.to_string(), depth, seen))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if length > MAX_ITEMS {
+            items.push(more(length - MAX_ITEMS, "item"));
+        }
+
+        return Ok(if items.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[ {} ]", items.join(", "))
+        });
+    }
+
+    // Like Node.js, name the instances of classes.
+    let constructor = object.get_property("constructor");
+    let class_name = if constructor.is_object() {
+        let name = constructor.as_object()?.get_property("name");
+
+        if name.is_string() {
+            name.as_string()?.to_string()
+        } else {
+            String::new()
+        }
+    } else {
+        String::new()
+    };
+    let prefix = if class_name.is_empty() || class_name == "Object" {
+        String::new()
+    } else {
+        format!("{class_name} ")
+    };
+
+    if depth > MAX_DEPTH {
+        return Ok(format!(
+            "[{}]",
+            if prefix.is_empty() {
+                "Object"
+            } else {
+                &class_name
+            }
+        ));
+    }
+
+    let mut names = object.property_names();
+    let mut entries = names
+        .by_ref()
+        .take(MAX_ITEMS as usize)
+        .map(|name| {
+            let name = name.to_string();
+            let value = inspect_property(ctx, object, &name, depth, seen)?;
+
+            Ok(format!("{}: {value}", property_key(&name)))
+        })
+        .collect::<Result<Vec<_>, JSException>>()?;
+
+    let rest = names.count();
+    if rest > 0 {
+        entries.push(more(u32::try_from(rest).unwrap_or(u32::MAX), "property"));
+    }
+
+    Ok(if entries.is_empty() {
+        format!("{prefix}{{}}")
+    } else {
+        format!("{prefix}{{ {} }}", entries.join(", "))
+    })
+}
+
+/// Formats the property `name` of `object`, found at `depth`, without
+/// invoking its accessors: like in Node.js, they are shown as `[Getter]`,
+/// `[Setter]` or `[Getter/Setter]`.
+fn inspect_property(
+    ctx: &JSContext,
+    object: &JSObject<'_>,
+    name: &str,
+    depth: usize,
+    seen: &mut Vec<sys::JSValueRef>,
+) -> Result<String, JSException> {
+    let Some(descriptor) = property_descriptor(ctx, object, name)? else {
+        return inspect(ctx, &object.get_property(name), depth + 1, seen);
+    };
+
+    let has_getter = !descriptor.get_property("get").is_undefined();
+    let has_setter = !descriptor.get_property("set").is_undefined();
+
+    Ok(match (has_getter, has_setter) {
+        (true, true) => String::from("[Getter/Setter]"),
+        (true, false) => String::from("[Getter]"),
+        (false, true) => String::from("[Setter]"),
+        (false, false) => inspect(ctx, &descriptor.get_property("value"), depth + 1, seen)?,
+    })
+}
+
+/// Returns the descriptor of the property `name` of `object`, or of its
+/// prototypes, like `Object.getOwnPropertyDescriptor()`.
+fn property_descriptor<'ctx>(
+    ctx: &'ctx JSContext,
+    object: &JSObject<'ctx>,
+    name: &str,
+) -> Result<Option<JSObject<'ctx>>, JSException> {
+    let object_constructor = ctx.global_object()?.get_property("Object").as_object()?;
+    let get_own_property_descriptor = object_constructor
+        .get_property("getOwnPropertyDescriptor")
+        .as_object()?;
+    let get_prototype_of = object_constructor
+        .get_property("getPrototypeOf")
+        .as_object()?;
+
+    let name = JSValue::new_string(ctx, name);
+    let mut current = JSValue::from(object);
+
+    while current.is_object() {
+        let descriptor =
+            get_own_property_descriptor.call_as_function(None, &[current.clone(), name.clone()])?;
+
+        if descriptor.is_object() {
+            return Ok(Some(descriptor.as_object()?));
+        }
+
+        current = get_prototype_of.call_as_function(None, &[current])?;
+    }
+
+    Ok(None)
+}
+
+/// Formats the number of items, or properties, left out, e.g.
+/// `... 2 more items`.
+fn more(count: u32, noun: &str) -> String {
+    if count == 1 {
+        format!("... 1 more {noun}")
+    } else if let Some(stem) = noun.strip_suffix('y') {
+        format!("... {count} more {stem}ies")
+    } else {
+        format!("... {count} more {noun}s")
+    }
+}
+
+/// Calls the method `name` of `object`, without arguments, and converts
+/// its result to a string.
+fn call_method(object: &JSObject<'_>, name: &str) -> Result<String, JSException> {
+    Ok(object
+        .get_property(name)
+        .as_object()?
+        .call_as_function(Some(object), &[])?
+        .as_string()?
+        .to_string())
+}
+
+/// Calls the global function `name` with `value`, and converts its result
+/// to a string.
+fn call_global(ctx: &JSContext, name: &str, value: &JSValue<'_>) -> Result<String, JSException> {
+    Ok(ctx
+        .global_object()?
+        .get_property(name)
+        .as_object()?
+        .call_as_function(None, std::slice::from_ref(value))?
+        .as_string()?
+        .to_string())
+}
+
+/// Quotes `string` with single quotes.
+fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('\'');
+
+    for character in string.chars() {
+        match character {
+            '\'' => quoted.push_str("\\'"),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            character => quoted.push(character),
+        }
+    }
+
+    quoted.push('\'');
+    quoted
+}
+
+/// Quotes the property `name` unless it's an identifier.
+fn property_key(name: &str) -> String {
+    let mut characters = name.chars();
+    let is_identifier = characters
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_' || first == '$')
+        && characters
+            .all(|character| character.is_alphanumeric() || character == '_' || character == '$');
+
+    if is_identifier {
+        name.to_owned()
+    } else {
+        quote(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{install, CaptureSink, Level, Message};
+    use crate::{evaluate_script, JSContext};
+
+    /// Runs `script` with a console, and returns the messages.
+    fn console(script: &str) -> Vec<Message> {
+        let ctx = JSContext::default();
+        let sink = CaptureSink::default();
+        install(&ctx, sink.clone()).unwrap();
+
+        evaluate_script(&ctx, script, None, "test.js", 1).unwrap();
+
+        sink.take()
+    }
+
+    fn texts(messages: &[Message]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn levels() {
+        let messages = console(
+            "console.log('log');
+             console.info('info');
+             console.debug('debug');
+             console.warn('warn');
+             console.error('error');",
+        );
+
+        let levels = messages
+            .iter()
+            .map(|message| message.level)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            [
+                Level::Info,
+                Level::Info,
+                Level::Debug,
+                Level::Warn,
+                Level::Error
+            ]
+        );
+        assert_eq!(texts(&messages), ["log", "info", "debug", "warn", "error"]);
+    }
+
+    #[test]
+    fn substitutions() {
+        let messages = console(
+            "console.log('%s is %d years old', 'Bob', 42.5);
+             console.log('%i%% of %f', 99.9, 1.5);
+             console.log('%o and %s', { a: [1] }, 'x', 'extra');
+             console.log('%cstyled', 'color: red');
+             console.log('%s and %s', 'one');
+             console.log(42, 'is', true);",
+        );
+
+        assert_eq!(
+            texts(&messages),
+            [
+                "Bob is 42 years old",
+                "99% of 1.5",
+                "{ a: [ 1 ] } and x extra",
+                "styled",
+                "one and %s",
+                "42 is true",
+            ]
+        );
+    }
+
+    #[test]
+    fn inspection() {
+        let messages = console(
+            "class Point { constructor() { this.x = 1; this.y = 2; } }
+             const cycle = { name: 'cycle' };
+             cycle.self = cycle;
+             console.log(
+                 undefined, null, -1.5, 10n, Symbol('tag'), [],
+                 { 'a-b': \"it's\", nested: { deeper: { deepest: { hidden: true } } } },
+             );
+             console.log(new Point(), cycle, [function named() {}, () => {}]);
+             console.log(new TypeError('oops'), new Date(0), new Date(NaN));",
+        );
+
+        assert_eq!(
+            texts(&messages),
+            [
+                "undefined null -1.5 10n Symbol(tag) [] \
+                 { 'a-b': 'it\\'s', nested: { deeper: { deepest: [Object] } } }",
+                "Point { x: 1, y: 2 } { name: 'cycle', self: [Circular] } \
+                 [ [Function: named], [Function (anonymous)] ]",
+                "TypeError: oops 1970-01-01T00:00:00.000Z Invalid Date",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_arrays_and_objects_are_abbreviated() {
+        let messages = console(
+            "console.log(new Array(2 ** 32 - 1));
+             console.log(Object.fromEntries(Array.from({ length: 102 }, (_, i) => ['p' + i, i])));
+             console.log([...Array(101).keys()].map(() => 0));",
+        );
+
+        let undefineds = vec!["undefined"; 100].join(", ");
+        let properties = (0..100)
+            .map(|index| format!("p{index}: {index}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let zeros = vec!["0"; 100].join(", ");
+
+        assert_eq!(
+            texts(&messages),
+            [
+                format!("[ {undefineds}, ... 4294967195 more items ]"),
+                format!("{{ {properties}, ... 2 more properties }}"),
+                format!("[ {zeros}, ... 1 more item ]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn accessors_are_not_invoked() {
+        let messages = console(
+            "const accessors = {
+                 get a() { throw new Error('invoked'); },
+                 set b(value) {},
+                 get c() { return 1; },
+                 set c(value) {},
+                 d: 4,
+             };
+             const array = [1];
+             Object.defineProperty(array, 1, { get() { throw 0; }, enumerable: true });
+             const prototype = {};
+             Object.defineProperty(prototype, 'inherited', { get() { throw 0; }, enumerable: true });
+             console.log(accessors, array, Object.create(prototype));",
+        );
+
+        assert_eq!(
+            texts(&messages),
+            ["{ a: [Getter], b: [Setter], c: [Getter/Setter], d: 4 } \
+              [ 1, [Getter] ] { inherited: [Getter] }"]
+        );
+    }
+
+    #[test]
+    fn assertions_and_counters() {
+        let messages = console(
+            "console.assert(true, 'not written');
+             console.assert(false);
+             console.assert(0, 'failed with %d', 42);
+             console.count();
+             console.count('items');
+             console.count();
+             console.countReset();
+             console.count();
+             console.countReset('missing');",
+        );
+
+        assert_eq!(
+            texts(&messages),
+            [
+                "Assertion failed",
+                "Assertion failed: failed with 42",
+                "default: 1",
+                "items: 1",
+                "default: 2",
+                "default: 1",
+                "Count for 'missing' does not exist",
+            ]
+        );
+        assert_eq!(messages[0].level, Level::Error);
+        assert_eq!(messages[6].level, Level::Warn);
+    }
+
+    #[test]
+    fn timers() {
+        let messages = console(
+            "console.time('load');
+             console.timeLog('load', 'step', 1);
+             console.timeEnd('load');
+             console.timeEnd('load');",
+        );
+
+        assert!(messages[0].text.starts_with("load: "));
+        assert!(messages[0].text.ends_with("ms step 1"));
+        assert!(messages[1].text.starts_with("load: "));
+        assert!(messages[1].text.ends_with("ms"));
+        assert_eq!(messages[2].text, "Timer 'load' does not exist");
+    }
+
+    #[test]
+    fn groups() {
+        let messages = console(
+            "console.group('outer');
+             console.log('one');
+             console.groupCollapsed();
+             console.log('two\\nlines');
+             console.groupEnd();
+             console.groupEnd();
+             console.groupEnd();
+             console.log('three');",
+        );
+
+        let rendered = messages.iter().map(Message::to_string).collect::<Vec<_>>();
+        assert_eq!(rendered, ["outer", "  one", "    two\n    lines", "three"]);
+    }
+
+    #[test]
+    fn trace() {
+        let messages = console("function inner() { console.trace('here') }\ninner();");
+
+        assert_eq!(messages[0].level, Level::Trace);
+        let mut lines = messages[0].text.lines();
+        assert_eq!(lines.next(), Some("Trace: here"));
+        assert!(lines.any(|line| line.starts_with("    at inner@test.js:1:")));
+    }
+
+    #[test]
+    fn closure_sink() {
+        let ctx = JSContext::default();
+        install(&ctx, |message: &Message| assert_eq!(message.text, "hello")).unwrap();
+
+        evaluate_script(&ctx, "console.log('hello')", None, "test.js", 1).unwrap();
+    }
+}
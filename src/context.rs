@@ -7,7 +7,18 @@
 use sys::JSContextGetGlobalObject;
 
 use crate::{sys, JSClass, JSContext, JSContextGroup, JSException, JSObject, JSString, JSValue};
-use std::ptr;
+use std::{
+    future::Future,
+    mem::ManuallyDrop,
+    ops::Deref,
+    pin::Pin,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
 
 impl JSContext {
     /// Create a new [`Self`] from its raw pointer directly.
@@ -19,6 +30,30 @@ impl JSContext {
         Self { raw }
     }
 
+    /// Retains the global context of `ctx`, which is released when the
+    /// returned context is dropped.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `ctx` is valid.
+    pub(crate) unsafe fn retain(ctx: sys::JSContextRef) -> Self {
+        Self::from_raw(sys::JSGlobalContextRetain(sys::JSContextGetGlobalContext(
+            ctx,
+        )))
+    }
+
+    /// Borrows the global context of `ctx`, e.g. received by a callback,
+    /// without retaining it.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `ctx` is valid, and outlives the returned context.
+    pub(crate) unsafe fn borrow_raw(ctx: sys::JSContextRef) -> BorrowedContext {
+        BorrowedContext(ManuallyDrop::new(Self::from_raw(
+            sys::JSContextGetGlobalContext(ctx),
+        )))
+    }
+
     /// Creates a global JavaScript execution context and populates it
     /// with all the built-in JavaScript objects, such as `Object`,
     /// `Function`, `String`, and `Array`.
@@ -44,7 +79,7 @@ impl JSContext {
     ///
     /// * `global_object_class`: The class to use when creating the global
     ///   object.
-    pub fn new_with_class(global_object_class: &JSClass) -> Self {
+    pub fn new_with_class(global_object_class: &JSClass<'_>) -> Self {
         unsafe { Self::from_raw(sys::JSGlobalContextCreate(global_object_class.raw)) }
     }
 
@@ -107,7 +142,7 @@ impl JSContext {
     ///
     /// assert!(ctx.global_object().is_ok());
     /// ```
-    pub fn global_object(&self) -> Result<JSObject, JSException> {
+    pub fn global_object(&self) -> Result<JSObject<'_>, JSException> {
         let global_object = unsafe { JSContextGetGlobalObject(self.raw) };
 
         if global_object.is_null() {
@@ -116,6 +151,88 @@ impl JSContext {
             Ok(unsafe { JSObject::from_raw(self.raw, global_object) })
         }
     }
+
+    /// Runs the pending microtasks of the context, like the reactions of
+    /// the settled promises.
+    ///
+    /// JavaScriptCore runs them when the outermost call into the context
+    /// returns, e.g. at the end of [`evaluate_script`](crate::evaluate_script),
+    /// so this is only needed when jobs have been queued by other means.
+    ///
+    /// The C API has no function to run the microtasks: this relies on
+    /// JavaScriptCore draining them when the outermost API call releases
+    /// the lock of the virtual machine, which isn't documented. Thus,
+    /// nothing is run when called from a callback, as the lock is held by
+    /// the script calling it: the microtasks wait for the script to return,
+    /// like in browsers.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+    /// ctx.global_object().unwrap().set_property("promise", promise.into()).unwrap();
+    /// evaluate_script(&ctx, "promise.then(value => answer = value)", None, "test.js", 1)
+    ///     .unwrap();
+    ///
+    /// resolver.resolve(&JSValue::new_number(&ctx, 42.)).unwrap();
+    /// ctx.run_microtasks();
+    ///
+    /// let answer = evaluate_script(&ctx, "answer", None, "test.js", 1).unwrap();
+    /// assert_eq!(answer.as_number().unwrap(), 42.);
+    /// ```
+    pub fn run_microtasks(&self) {
+        // Any call entering and leaving the virtual machine will do.
+        unsafe { sys::JSValueMakeUndefined(self.raw) };
+    }
+
+    /// Runs the pending microtasks, and polls `future` until it's ready, or
+    /// until it doesn't make progress anymore.
+    ///
+    /// The future is polled again each time it's woken, so a single-threaded
+    /// loop can drive the futures awaiting JavaScript promises, like
+    /// [`JSValue::into_future`], and the promise jobs resolving them,
+    /// together. `Poll::Pending` is returned once the future is waiting for
+    /// something else than the microtasks, e.g. I/O or a timer.
+    ///
+    /// ```
+    /// # use std::{pin::pin, task::Poll};
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let promise = evaluate_script(&ctx, "Promise.resolve(40).then(x => x + 2)", None, "test.js", 1)
+    ///     .unwrap();
+    ///
+    /// let Poll::Ready(answer) = ctx.poll_future(pin!(promise.into_future())) else {
+    ///     panic!("the promise is pending");
+    /// };
+    /// assert_eq!(answer.unwrap().as_number().unwrap(), 42.);
+    /// ```
+    pub fn poll_future<F: Future + ?Sized>(&self, mut future: Pin<&mut F>) -> Poll<F::Output> {
+        /// A waker recording that it has been woken.
+        struct Flag(AtomicBool);
+
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let flag = Arc::new(Flag(AtomicBool::new(true)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut context = Context::from_waker(&waker);
+
+        while flag.0.swap(false, Ordering::Acquire) {
+            self.run_microtasks();
+
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return Poll::Ready(output);
+            }
+
+            // The microtasks queued while polling may wake the future.
+            self.run_microtasks();
+        }
+
+        Poll::Pending
+    }
 }
 
 impl Default for JSContext {
@@ -133,6 +250,18 @@ impl Default for JSContext {
     }
 }
 
+/// A context borrowed with [`JSContext::borrow_raw`], which isn't released
+/// when dropped.
+pub(crate) struct BorrowedContext(ManuallyDrop<JSContext>);
+
+impl Deref for BorrowedContext {
+    type Target = JSContext;
+
+    fn deref(&self) -> &JSContext {
+        &self.0
+    }
+}
+
 impl Drop for JSContext {
     fn drop(&mut self) {
         unsafe { sys::JSGlobalContextRelease(self.raw) }
@@ -141,7 +270,7 @@ impl Drop for JSContext {
 
 #[cfg(test)]
 mod tests {
-    use crate::JSContext;
+    use crate::{evaluate_script, JSContext, JSValue};
 
     #[test]
     fn context_group() {
@@ -167,4 +296,37 @@ mod tests {
         let some_property = global_object.get_property("Array");
         assert!(!some_property.is_undefined());
     }
+
+    #[test]
+    fn microtasks_wait_for_the_script_in_callbacks() {
+        let ctx = JSContext::new();
+        let run_microtasks =
+            JSValue::new_function_from_closure(&ctx, "runMicrotasks", |ctx, _this, _arguments| {
+                ctx.run_microtasks();
+
+                Ok(JSValue::new_undefined(ctx))
+            });
+        ctx.global_object()
+            .unwrap()
+            .set_property("runMicrotasks", run_microtasks)
+            .unwrap();
+
+        let log = |script| {
+            evaluate_script(&ctx, script, None, "test.js", 1)
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            log("var log = [];
+                 Promise.resolve().then(() => log.push('microtask'));
+                 runMicrotasks();
+                 log.push('script');
+                 log.join()"),
+            "script"
+        );
+        assert_eq!(log("log.join()"), "script,microtask");
+    }
 }
@@ -36,7 +36,7 @@ impl JSContextGroup {
     ///
     /// * `global_object_class`: The class to use when creating the global
     ///   object.
-    pub fn new_context_with_class(&self, global_object_class: &JSClass) -> JSContext {
+    pub fn new_context_with_class(&self, global_object_class: &JSClass<'_>) -> JSContext {
         unsafe {
             JSContext::from_raw(sys::JSGlobalContextCreateInGroup(
                 self.raw,
@@ -0,0 +1,1105 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
+    ptr,
+    time::SystemTime,
+};
+
+use crate::{sys, JSContext, JSException, JSObject, JSString, JSType, JSValue, MAX_SAFE_INTEGER};
+
+/// Conversion of a Rust value into a [`JSValue`].
+///
+/// This trait is implemented for the primitive types (`bool`, integers,
+/// floats, strings), for [`JSString`], [`JSValue`] and [`JSObject`], and
+/// for the usual containers: `Option<T>`, `Vec<T>`, slices, arrays,
+/// tuples and maps with string keys. `()` is converted to `undefined`,
+/// `None` to `null`, and a `SystemTime` to a `Date`. With the `chrono`
+/// and `time` features, `chrono::DateTime<Utc>` and
+/// `time::OffsetDateTime` are converted to a `Date` too.
+///
+/// It can be derived for structs and enums with `#[derive(ToJSValue)]`,
+/// see [`macro@crate::ToJSValue`] to learn more.
+///
+/// ```
+/// # use javascriptcore::{JSContext, ToJSValue};
+/// let ctx = JSContext::default();
+///
+/// let value = vec![Some(1), None, Some(3)].to_js_value(&ctx).unwrap();
+/// assert_eq!(value.to_json_string(0).unwrap(), "[1,null,3]");
+/// ```
+///
+/// Integers that cannot be represented exactly by a JavaScript number
+/// (i.e. beyond `Number.MAX_SAFE_INTEGER`) are rejected with a
+/// `RangeError`:
+///
+/// ```
+/// # use javascriptcore::{JSContext, ToJSValue};
+/// let ctx = JSContext::default();
+///
+/// assert!(u64::MAX.to_js_value(&ctx).is_err());
+/// ```
+///
+/// # See also
+///
+/// - [`FromJSValue`]
+pub trait ToJSValue<'ctx> {
+    /// Converts `self` into a [`JSValue`] living in `ctx`.
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException>;
+}
+
+/// Conversion of a [`JSValue`] into a Rust value.
+///
+/// Conversions are strict: no JavaScript coercion happens, a string is
+/// not a number, and a number is not a boolean. When the value doesn't
+/// have the expected type, a `TypeError` naming the expected and actual
+/// [`JSType`] is returned. Numbers that don't fit in the targeted
+/// integer type, and finite numbers beyond the range of `f32`, are
+/// rejected with a `RangeError`.
+///
+/// `undefined` and `null` are both converted to `None`.
+///
+/// It can be derived for structs and enums with `#[derive(FromJSValue)]`,
+/// see [`macro@crate::FromJSValue`] to learn more.
+///
+/// ```
+/// # use javascriptcore::{evaluate_script, FromJSValue, JSContext};
+/// let ctx = JSContext::default();
+///
+/// let value = evaluate_script(&ctx, "[[1, 'one'], [2, 'two']]", None, "test.js", 1).unwrap();
+/// let pairs = Vec::<(u8, String)>::from_js_value(&value).unwrap();
+/// assert_eq!(pairs, vec![(1, "one".to_owned()), (2, "two".to_owned())]);
+///
+/// let value = evaluate_script(&ctx, "'1'", None, "test.js", 1).unwrap();
+/// let error = u8::from_js_value(&value).unwrap_err();
+/// assert_eq!(error.name().unwrap(), "TypeError");
+/// ```
+///
+/// # See also
+///
+/// - [`ToJSValue`]
+pub trait FromJSValue<'ctx>: Sized {
+    /// Converts `value` into `Self`.
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException>;
+}
+
+/// Builds a `TypeError` stating that `value` doesn't have the `expected` type.
+pub(crate) fn type_error(value: &JSValue, expected: &str) -> JSException {
+    JSException::new_builtin(
+        value.ctx,
+        "TypeError",
+        &format!("expected {expected}, got `{:?}`", value.get_type()),
+    )
+}
+
+/// Checks that `value` has the type `expected`.
+fn expect_type(value: &JSValue, expected: JSType) -> Result<(), JSException> {
+    if value.get_type() == expected {
+        Ok(())
+    } else {
+        Err(type_error(value, &format!("`{expected:?}`")))
+    }
+}
+
+/// Checks that `value` is an object, and returns it.
+pub(crate) fn expect_object<'ctx>(value: &JSValue<'ctx>) -> Result<JSObject<'ctx>, JSException> {
+    expect_type(value, JSType::Object)?;
+
+    Ok(unsafe { JSObject::from_raw(value.ctx, value.raw as sys::JSObjectRef) })
+}
+
+/// Checks that `value` is an array, and returns it along with its length.
+pub(crate) fn expect_array<'ctx>(
+    value: &JSValue<'ctx>,
+) -> Result<(JSObject<'ctx>, u32), JSException> {
+    if !value.is_array() {
+        return Err(type_error(value, "an array"));
+    }
+
+    let array = expect_object(value)?;
+    let length = u32::from_js_value(&array.get_property("length"))?;
+
+    Ok((array, length))
+}
+
+/// Checks that `value` is an array of exactly `expected` elements, and
+/// returns it.
+pub(crate) fn expect_array_of_length<'ctx>(
+    value: &JSValue<'ctx>,
+    expected: u32,
+) -> Result<JSObject<'ctx>, JSException> {
+    let (array, length) = expect_array(value)?;
+
+    if length != expected {
+        return Err(JSException::new_builtin(
+            value.ctx,
+            "TypeError",
+            &format!("expected an array of length {expected}, got an array of length {length}"),
+        ));
+    }
+
+    Ok(array)
+}
+
+/// Converts `value` to a number, without treating `NaN` as an error.
+fn expect_number(value: &JSValue) -> Result<f64, JSException> {
+    expect_type(value, JSType::Number)?;
+
+    let mut exception: sys::JSValueRef = ptr::null_mut();
+    let number = unsafe { sys::JSValueToNumber(value.ctx, value.raw, &mut exception) };
+
+    if exception.is_null() {
+        Ok(number)
+    } else {
+        Err(unsafe { JSValue::from_raw(value.ctx, exception) }.into())
+    }
+}
+
+/// Prefixes the message of a conversion error with some context, like the
+/// index of the element that failed to be converted.
+///
+/// Only built-in errors are rewritten, other exceptions are returned as is.
+pub(crate) fn prefix_exception(exception: JSException, prefix: &str) -> JSException {
+    const BUILTINS: [&str; 4] = ["Error", "RangeError", "TypeError", "SyntaxError"];
+
+    let value = exception.underlying_value();
+
+    if !value.is_object() {
+        return exception;
+    }
+
+    let Ok(name) = exception.name() else {
+        return exception;
+    };
+    let name = name.to_string();
+
+    if !BUILTINS.contains(&name.as_str()) {
+        return exception;
+    }
+
+    let message = value
+        .as_object()
+        .and_then(|object| object.get_property("message").as_string())
+        .map(|message| message.to_string())
+        .unwrap_or_default();
+
+    JSException::new_builtin(value.ctx, &name, &format!("{prefix}: {message}"))
+}
+
+impl<'ctx> ToJSValue<'ctx> for JSValue<'ctx> {
+    fn to_js_value(&self, _ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(self.clone())
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for JSValue<'ctx> {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        Ok(value.clone())
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for JSObject<'ctx> {
+    fn to_js_value(&self, _ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(self.into())
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for JSObject<'ctx> {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        expect_object(value)
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for () {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(JSValue::new_undefined(ctx))
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for () {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        expect_type(value, JSType::Undefined)
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for bool {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(JSValue::new_boolean(ctx, *self))
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for bool {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        expect_type(value, JSType::Boolean)?;
+
+        Ok(value.as_boolean())
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for f64 {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(JSValue::new_number(ctx, *self))
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for f64 {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        expect_number(value)
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for f32 {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(JSValue::new_number(ctx, (*self).into()))
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        let number = expect_number(value)?;
+
+        if number.is_finite() && number.abs() > f64::from(f32::MAX) {
+            return Err(range_error(value, number, "f32"));
+        }
+
+        Ok(number as f32)
+    }
+}
+
+macro_rules! impl_for_small_integers {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<'ctx> ToJSValue<'ctx> for $ty {
+                fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+                    Ok(JSValue::new_number(ctx, (*self).into()))
+                }
+            }
+
+            impl<'ctx> FromJSValue<'ctx> for $ty {
+                fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+                    integer_from_js_value(value, <$ty>::MIN.into(), <$ty>::MAX.into(), stringify!($ty))
+                        .map(|number| number as $ty)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_for_large_integers {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<'ctx> ToJSValue<'ctx> for $ty {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+                    let number = *self as f64;
+
+                    if number.abs() > MAX_SAFE_INTEGER as f64 {
+                        return Err(JSException::new_builtin(
+                            ctx.raw,
+                            "RangeError",
+                            &format!("integer {self} cannot be represented exactly by a JavaScript number"),
+                        ));
+                    }
+
+                    Ok(JSValue::new_number(ctx, number))
+                }
+            }
+
+            impl<'ctx> FromJSValue<'ctx> for $ty {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+                    integer_from_js_value(
+                        value,
+                        (<$ty>::MIN as f64).max(-MAX_SAFE_INTEGER as f64),
+                        (<$ty>::MAX as f64).min(MAX_SAFE_INTEGER as f64),
+                        stringify!($ty),
+                    )
+                    .map(|number| number as $ty)
+                }
+            }
+        )*
+    };
+}
+
+/// Converts `value` into an integral number in the `min..=max` range.
+fn integer_from_js_value(
+    value: &JSValue,
+    min: f64,
+    max: f64,
+    ty: &str,
+) -> Result<f64, JSException> {
+    let number = expect_number(value)?;
+
+    if number.fract() != 0.0 || !(min..=max).contains(&number) {
+        return Err(range_error(value, number, ty));
+    }
+
+    Ok(number)
+}
+
+/// Builds a `RangeError` stating that the `number` of `value` cannot be
+/// converted to `ty`.
+fn range_error(value: &JSValue, number: f64, ty: &str) -> JSException {
+    JSException::new_builtin(
+        value.ctx,
+        "RangeError",
+        &format!("number {number} cannot be converted to `{ty}`"),
+    )
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+mod integers {
+    use super::*;
+
+    impl_for_small_integers!(i8, i16, i32, u8, u16, u32);
+    impl_for_large_integers!(i64, i128, isize, u64, u128, usize);
+}
+
+impl<'ctx> ToJSValue<'ctx> for str {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(JSValue::new_string(ctx, self))
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for String {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        self.as_str().to_js_value(ctx)
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for String {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        JSString::from_js_value(value).map(|string| string.to_string())
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for JSString {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        Ok(unsafe { JSValue::from_raw(ctx.raw, sys::JSValueMakeString(ctx.raw, self.raw)) })
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for JSString {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        expect_type(value, JSType::String)?;
+
+        value.as_string()
+    }
+}
+
+impl<'ctx> ToJSValue<'ctx> for SystemTime {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        JSValue::new_date(ctx, *self)
+    }
+}
+
+impl<'ctx> FromJSValue<'ctx> for SystemTime {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        value.as_date()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'ctx> ToJSValue<'ctx> for chrono::DateTime<chrono::Utc> {
+    #[allow(clippy::cast_precision_loss)]
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        JSValue::new_date_from_timestamp(ctx, self.timestamp_millis() as f64)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'ctx> FromJSValue<'ctx> for chrono::DateTime<chrono::Utc> {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        let millis = value.as_timestamp()?;
+
+        Self::from_timestamp_millis(millis as i64).ok_or_else(|| {
+            JSException::new_builtin(
+                value.ctx,
+                "RangeError",
+                &format!("{millis}ms since the Unix epoch is out of the range of a `DateTime`"),
+            )
+        })
+    }
+}
+
+#[cfg(feature = "time")]
+impl<'ctx> ToJSValue<'ctx> for time::OffsetDateTime {
+    #[allow(clippy::cast_precision_loss)]
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        let millis = self.unix_timestamp_nanos().div_euclid(1_000_000);
+
+        JSValue::new_date_from_timestamp(ctx, millis as f64)
+    }
+}
+
+#[cfg(feature = "time")]
+impl<'ctx> FromJSValue<'ctx> for time::OffsetDateTime {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        let millis = value.as_timestamp()?;
+
+        Self::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+            .map_err(|error| JSException::new_builtin(value.ctx, "RangeError", &error.to_string()))
+    }
+}
+
+impl<'ctx, T: ToJSValue<'ctx> + ?Sized> ToJSValue<'ctx> for &T {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        (**self).to_js_value(ctx)
+    }
+}
+
+impl<'ctx, T: ToJSValue<'ctx>> ToJSValue<'ctx> for Option<T> {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        match self {
+            Some(value) => value.to_js_value(ctx),
+            None => Ok(JSValue::new_null(ctx)),
+        }
+    }
+}
+
+impl<'ctx, T: FromJSValue<'ctx>> FromJSValue<'ctx> for Option<T> {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        if value.is_undefined() || value.is_null() {
+            Ok(None)
+        } else {
+            T::from_js_value(value).map(Some)
+        }
+    }
+}
+
+impl<'ctx, T: ToJSValue<'ctx>> ToJSValue<'ctx> for [T] {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        let array = JSValue::new_array(ctx, &[])?.as_object()?;
+
+        for (index, item) in (0..).zip(self) {
+            array
+                .set_property_at_index(index, item.to_js_value(ctx)?)
+                .map_err(|exception| prefix_exception(exception, &format!("element {index}")))?;
+        }
+
+        Ok(array.into())
+    }
+}
+
+impl<'ctx, T: ToJSValue<'ctx>, const N: usize> ToJSValue<'ctx> for [T; N] {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        self.as_slice().to_js_value(ctx)
+    }
+}
+
+impl<'ctx, T: ToJSValue<'ctx>> ToJSValue<'ctx> for Vec<T> {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        self.as_slice().to_js_value(ctx)
+    }
+}
+
+impl<'ctx, T: FromJSValue<'ctx>> FromJSValue<'ctx> for Vec<T> {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        let (array, length) = expect_array(value)?;
+
+        (0..length)
+            .map(|index| {
+                T::from_js_value(&array.get_property_at_index(index))
+                    .map_err(|exception| prefix_exception(exception, &format!("element {index}")))
+            })
+            .collect()
+    }
+}
+
+/// Builds an object from `(key, value)` pairs.
+fn object_from_entries<'a, 'ctx, K, V, I>(
+    ctx: &'ctx JSContext,
+    entries: I,
+) -> Result<JSValue<'ctx>, JSException>
+where
+    K: AsRef<str> + 'a,
+    V: ToJSValue<'ctx> + 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    let object = JSObject::new(ctx);
+
+    for (key, value) in entries {
+        let key = key.as_ref();
+
+        object
+            .set_property(key, value.to_js_value(ctx)?)
+            .map_err(|exception| prefix_exception(exception, &format!("property `{key}`")))?;
+    }
+
+    Ok(object.into())
+}
+
+/// Reads the enumerable properties of an object as `(key, value)` pairs.
+fn entries_from_object<'ctx, V, C>(value: &JSValue<'ctx>) -> Result<C, JSException>
+where
+    V: FromJSValue<'ctx>,
+    C: FromIterator<(String, V)>,
+{
+    let object = expect_object(value)?;
+
+    object
+        .property_names()
+        .map(|name| {
+            let name = name.to_string();
+
+            V::from_js_value(&object.get_property(name.as_str()))
+                .map(|value| (name.clone(), value))
+                .map_err(|exception| prefix_exception(exception, &format!("property `{name}`")))
+        })
+        .collect()
+}
+
+impl<'ctx, V: ToJSValue<'ctx>, S: BuildHasher> ToJSValue<'ctx> for HashMap<String, V, S> {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        object_from_entries(ctx, self.iter())
+    }
+}
+
+impl<'ctx, V: FromJSValue<'ctx>, S: BuildHasher + Default> FromJSValue<'ctx>
+    for HashMap<String, V, S>
+{
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        entries_from_object(value)
+    }
+}
+
+impl<'ctx, V: ToJSValue<'ctx>> ToJSValue<'ctx> for BTreeMap<String, V> {
+    fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+        object_from_entries(ctx, self.iter())
+    }
+}
+
+impl<'ctx, V: FromJSValue<'ctx>> FromJSValue<'ctx> for BTreeMap<String, V> {
+    fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+        entries_from_object(value)
+    }
+}
+
+macro_rules! impl_for_tuples {
+    ($( ( $( $name:ident : $index:tt ),+ ) ),* $(,)?) => {
+        $(
+            impl<'ctx, $( $name: ToJSValue<'ctx> ),+> ToJSValue<'ctx> for ( $( $name, )+ ) {
+                fn to_js_value(&self, ctx: &'ctx JSContext) -> Result<JSValue<'ctx>, JSException> {
+                    JSValue::new_array(ctx, &[ $( self.$index.to_js_value(ctx)? ),+ ])
+                }
+            }
+
+            impl<'ctx, $( $name: FromJSValue<'ctx> ),+> FromJSValue<'ctx> for ( $( $name, )+ ) {
+                fn from_js_value(value: &JSValue<'ctx>) -> Result<Self, JSException> {
+                    let array = expect_array_of_length(value, [ $( $index ),+ ].len() as u32)?;
+
+                    Ok(( $(
+                        $name::from_js_value(&array.get_property_at_index($index)).map_err(
+                            |exception| prefix_exception(exception, concat!("element ", $index)),
+                        )?,
+                    )+ ))
+                }
+            }
+        )*
+    };
+}
+
+impl_for_tuples!(
+    (A: 0),
+    (A: 0, B: 1),
+    (A: 0, B: 1, C: 2),
+    (A: 0, B: 1, C: 2, D: 3),
+    (A: 0, B: 1, C: 2, D: 3, E: 4),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7),
+);
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, SystemTime},
+    };
+
+    use super::{FromJSValue, ToJSValue};
+    use crate::{evaluate_script, JSContext, JSException, JSString, JSValue};
+
+    fn eval<'ctx>(ctx: &'ctx JSContext, script: &str) -> JSValue<'ctx> {
+        evaluate_script(ctx, script, None, "test.js", 1).unwrap()
+    }
+
+    fn message(exception: &JSException) -> String {
+        exception
+            .underlying_value()
+            .as_object()
+            .unwrap()
+            .get_property("message")
+            .as_string()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn primitives() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        assert!(bool::from_js_value(&true.to_js_value(&ctx)?)?);
+        assert_eq!(f64::from_js_value(&1.5f64.to_js_value(&ctx)?)?, 1.5);
+        assert_eq!(f32::from_js_value(&1.5f32.to_js_value(&ctx)?)?, 1.5);
+        assert_eq!(i8::from_js_value(&(-3i8).to_js_value(&ctx)?)?, -3);
+        assert_eq!(u64::from_js_value(&42u64.to_js_value(&ctx)?)?, 42);
+        assert_eq!(String::from_js_value(&"abc".to_js_value(&ctx)?)?, "abc");
+        assert_eq!(
+            JSString::from_js_value(&JSString::from("abc").to_js_value(&ctx)?)?,
+            "abc"
+        );
+        assert!(().to_js_value(&ctx)?.is_undefined());
+        <()>::from_js_value(&JSValue::new_undefined(&ctx))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let ctx = JSContext::default();
+
+        let error = f64::from_js_value(&eval(&ctx, "'1'")).unwrap_err();
+        assert_eq!(error.name().unwrap(), "TypeError");
+        assert_eq!(message(&error), "expected `Number`, got `String`");
+
+        let error = bool::from_js_value(&eval(&ctx, "0")).unwrap_err();
+        assert_eq!(message(&error), "expected `Boolean`, got `Number`");
+
+        let error = Vec::<u8>::from_js_value(&eval(&ctx, "({})")).unwrap_err();
+        assert_eq!(message(&error), "expected an array, got `Object`");
+    }
+
+    #[test]
+    fn f32_range() {
+        let ctx = JSContext::default();
+
+        assert_eq!(f32::from_js_value(&eval(&ctx, "0.1")).unwrap(), 0.1);
+        assert_eq!(
+            f32::from_js_value(&f32::MAX.to_js_value(&ctx).unwrap()).unwrap(),
+            f32::MAX
+        );
+        assert_eq!(
+            f32::from_js_value(&eval(&ctx, "-Infinity")).unwrap(),
+            f32::NEG_INFINITY
+        );
+        assert!(f32::from_js_value(&eval(&ctx, "NaN")).unwrap().is_nan());
+
+        let error = f32::from_js_value(&eval(&ctx, "1e39")).unwrap_err();
+        assert_eq!(error.name().unwrap(), "RangeError");
+        assert_eq!(
+            message(&error),
+            "number 1000000000000000000000000000000000000000 cannot be converted to `f32`"
+        );
+        assert!(f32::from_js_value(&eval(&ctx, "-Number.MAX_VALUE")).is_err());
+    }
+
+    #[test]
+    fn integer_ranges() {
+        let ctx = JSContext::default();
+
+        assert_eq!(u8::from_js_value(&eval(&ctx, "255")).unwrap(), 255);
+
+        let error = u8::from_js_value(&eval(&ctx, "256")).unwrap_err();
+        assert_eq!(error.name().unwrap(), "RangeError");
+
+        assert!(u8::from_js_value(&eval(&ctx, "-1")).is_err());
+        assert!(i32::from_js_value(&eval(&ctx, "1.5")).is_err());
+        assert!(i64::from_js_value(&eval(&ctx, "NaN")).is_err());
+        assert!(u64::from_js_value(&eval(&ctx, "2 ** 53")).is_err());
+        assert!(f64::from_js_value(&eval(&ctx, "NaN")).unwrap().is_nan());
+
+        assert!((1u64 << 53).to_js_value(&ctx).is_err());
+        assert!(i128::MIN.to_js_value(&ctx).is_err());
+    }
+
+    #[test]
+    fn options() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        assert_eq!(
+            Option::<f64>::from_js_value(&eval(&ctx, "undefined"))?,
+            None
+        );
+        assert_eq!(Option::<f64>::from_js_value(&eval(&ctx, "null"))?, None);
+        assert_eq!(Option::<f64>::from_js_value(&eval(&ctx, "1"))?, Some(1.));
+        assert!(None::<f64>.to_js_value(&ctx)?.is_null());
+
+        Ok(())
+    }
+
+    #[test]
+    fn containers() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        let value = vec![vec![1, 2], vec![3]].to_js_value(&ctx)?;
+        assert_eq!(value.to_json_string(0)?, "[[1,2],[3]]");
+        assert_eq!(
+            Vec::<Vec<i32>>::from_js_value(&value)?,
+            vec![vec![1, 2], vec![3]]
+        );
+
+        let map = HashMap::from([("a".to_owned(), true)]);
+        let value = map.to_js_value(&ctx)?;
+        assert_eq!(value.to_json_string(0)?, r#"{"a":true}"#);
+        assert_eq!(HashMap::<String, bool>::from_js_value(&value)?, map);
+
+        let value = (1u8, "two", [3.5]).to_js_value(&ctx)?;
+        assert_eq!(value.to_json_string(0)?, r#"[1,"two",[3.5]]"#);
+        assert_eq!(
+            <(u8, String, Vec<f64>)>::from_js_value(&value)?,
+            (1, "two".to_owned(), vec![3.5])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_errors_name_their_location() {
+        let ctx = JSContext::default();
+
+        let error = Vec::<u8>::from_js_value(&eval(&ctx, "[1, 2, 'x']")).unwrap_err();
+        assert_eq!(
+            message(&error),
+            "element 2: expected `Number`, got `String`"
+        );
+
+        let error = HashMap::<String, Vec<bool>>::from_js_value(&eval(&ctx, "({ a: [true, 1] })"))
+            .unwrap_err();
+        assert_eq!(
+            message(&error),
+            "property `a`: element 1: expected `Boolean`, got `Number`"
+        );
+
+        let error = <(u8, u8)>::from_js_value(&eval(&ctx, "[1]")).unwrap_err();
+        assert_eq!(
+            message(&error),
+            "expected an array of length 2, got an array of length 1"
+        );
+    }
+
+    #[test]
+    fn dates() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let value = time.to_js_value(&ctx)?;
+        assert!(value.is_date());
+        assert_eq!(SystemTime::from_js_value(&value)?, time);
+
+        let error = SystemTime::from_js_value(&eval(&ctx, "new Date('nope')")).unwrap_err();
+        assert_eq!(message(&error), "invalid date: its time value is `NaN`");
+
+        let error = SystemTime::from_js_value(&eval(&ctx, "1700000000123")).unwrap_err();
+        assert_eq!(message(&error), "expected a `Date`, got `Number`");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_dates() -> Result<(), JSException> {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let ctx = JSContext::default();
+
+        let time = Utc.with_ymd_and_hms(1969, 7, 20, 20, 17, 40).unwrap();
+        let value = time.to_js_value(&ctx)?;
+        assert_eq!(value.to_json_string(0)?, "\"1969-07-20T20:17:40.000Z\"");
+        assert_eq!(DateTime::<Utc>::from_js_value(&value)?, time);
+
+        let value = eval(&ctx, "new Date(Date.UTC(2000, 0, 1, 0, 0, 0, 5))");
+        assert_eq!(
+            DateTime::<Utc>::from_js_value(&value)?,
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::milliseconds(5)
+        );
+        assert!(DateTime::<Utc>::from_js_value(&eval(&ctx, "new Date(NaN)")).is_err());
+
+        // Sub-millisecond times are rounded towards the past.
+        let epoch = DateTime::<Utc>::UNIX_EPOCH;
+        let value = (epoch - chrono::Duration::microseconds(1_500)).to_js_value(&ctx)?;
+        assert_eq!(
+            DateTime::<Utc>::from_js_value(&value)?,
+            epoch - chrono::Duration::milliseconds(2)
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_dates() -> Result<(), JSException> {
+        use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+        let ctx = JSContext::default();
+
+        let time = Date::from_calendar_date(2024, Month::February, 29)
+            .and_then(|date| date.with_hms_milli(12, 0, 0, 250))
+            .and_then(|date| Ok(date.assume_offset(UtcOffset::from_hms(2, 0, 0)?)))
+            .unwrap();
+        let value = time.to_js_value(&ctx)?;
+        assert_eq!(value.to_json_string(0)?, "\"2024-02-29T10:00:00.250Z\"");
+        assert_eq!(OffsetDateTime::from_js_value(&value)?, time);
+
+        // Sub-millisecond times are rounded towards the past.
+        let epoch = OffsetDateTime::UNIX_EPOCH;
+        let value = (epoch - time::Duration::microseconds(1_500)).to_js_value(&ctx)?;
+        assert_eq!(
+            OffsetDateTime::from_js_value(&value)?,
+            epoch - time::Duration::milliseconds(2)
+        );
+
+        // `time` only supports years from -9999 to 9999 by default.
+        let value = eval(&ctx, "new Date(8.64e15)");
+        assert!(OffsetDateTime::from_js_value(&value).is_err());
+
+        Ok(())
+    }
+
+    mod derive {
+        use super::{eval, message};
+        use crate as javascriptcore;
+        use crate::{FromJSValue, JSContext, JSException, ToJSValue};
+
+        fn answer() -> u32 {
+            42
+        }
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        #[js(rename_all = "camelCase")]
+        struct Settings {
+            user_name: String,
+            #[js(rename = "max")]
+            maximum: Option<u8>,
+            #[js(skip)]
+            cache: Vec<u8>,
+            #[js(default)]
+            tags: Vec<String>,
+            #[js(default = "answer")]
+            r#type: u32,
+        }
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        struct Wrapper<T>(T);
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        struct Pair(u8, bool);
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        enum External {
+            Unit,
+            Newtype(u8),
+            Tuple(u8, u8),
+            Struct { a: bool },
+        }
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        #[js(tag = "kind", rename_all = "snake_case")]
+        enum Internal {
+            Empty,
+            Circle { radius: f64 },
+            Wrapped(Wrapper<Settings>),
+        }
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        #[js(tag = "t", content = "c")]
+        enum Adjacent {
+            Unit,
+            Tuple(u8, u8),
+            #[js(rename = "s", rename_all = "SCREAMING_SNAKE_CASE")]
+            Struct {
+                some_field: bool,
+            },
+        }
+
+        #[derive(Debug, PartialEq, ToJSValue, FromJSValue)]
+        #[js(untagged)]
+        enum Untagged {
+            Nothing,
+            Number(f64),
+            Text(String),
+            Point { x: u8, y: u8 },
+        }
+
+        fn round_trip<'ctx, T>(
+            ctx: &'ctx JSContext,
+            value: T,
+            json: &str,
+        ) -> Result<(), JSException>
+        where
+            T: ToJSValue<'ctx> + FromJSValue<'ctx> + PartialEq + std::fmt::Debug,
+        {
+            let js_value = value.to_js_value(ctx)?;
+            assert_eq!(js_value.to_json_string(0)?, json);
+            assert_eq!(T::from_js_value(&js_value)?, value);
+
+            Ok(())
+        }
+
+        #[test]
+        fn structs() -> Result<(), JSException> {
+            let ctx = JSContext::default();
+
+            let settings = Settings {
+                user_name: "alice".to_owned(),
+                maximum: Some(3),
+                cache: vec![1, 2],
+                tags: vec!["a".to_owned()],
+                r#type: 7,
+            };
+            let value = settings.to_js_value(&ctx)?;
+            assert_eq!(
+                value.to_json_string(0)?,
+                r#"{"userName":"alice","max":3,"tags":["a"],"type":7}"#
+            );
+            assert_eq!(
+                Settings::from_js_value(&value)?,
+                Settings {
+                    cache: Vec::new(),
+                    ..settings
+                }
+            );
+
+            let settings = Settings::from_js_value(&eval(&ctx, "({ userName: 'bob' })"))?;
+            assert_eq!(
+                settings,
+                Settings {
+                    user_name: "bob".to_owned(),
+                    maximum: None,
+                    cache: Vec::new(),
+                    tags: Vec::new(),
+                    r#type: 42,
+                }
+            );
+
+            round_trip(&ctx, Wrapper(1.5), "1.5")?;
+            round_trip(&ctx, Pair(1, true), "[1,true]")?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn struct_errors() {
+            let ctx = JSContext::default();
+
+            let error = Settings::from_js_value(&eval(&ctx, "({ userName: 1 })")).unwrap_err();
+            assert_eq!(error.name().unwrap(), "TypeError");
+            assert_eq!(
+                message(&error),
+                "property `userName`: expected `String`, got `Number`"
+            );
+
+            let error = Settings::from_js_value(&eval(&ctx, "({})")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "property `userName`: expected `String`, got `Undefined`"
+            );
+
+            let error = Pair::from_js_value(&eval(&ctx, "[1]")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "expected an array of length 2, got an array of length 1"
+            );
+        }
+
+        #[test]
+        fn externally_tagged_enums() -> Result<(), JSException> {
+            let ctx = JSContext::default();
+
+            round_trip(&ctx, External::Unit, r#""Unit""#)?;
+            round_trip(&ctx, External::Newtype(1), r#"{"Newtype":1}"#)?;
+            round_trip(&ctx, External::Tuple(1, 2), r#"{"Tuple":[1,2]}"#)?;
+            round_trip(
+                &ctx,
+                External::Struct { a: true },
+                r#"{"Struct":{"a":true}}"#,
+            )?;
+
+            let error = External::from_js_value(&eval(&ctx, "'Other'")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "unknown variant `Other`, expected one of `Unit`, `Newtype`, `Tuple`, `Struct`"
+            );
+
+            let error = External::from_js_value(&eval(&ctx, "({ Struct: { a: 1 } })")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "property `Struct`: property `a`: expected `Boolean`, got `Number`"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn internally_tagged_enums() -> Result<(), JSException> {
+            let ctx = JSContext::default();
+
+            round_trip(&ctx, Internal::Empty, r#"{"kind":"empty"}"#)?;
+            round_trip(
+                &ctx,
+                Internal::Circle { radius: 1.5 },
+                r#"{"kind":"circle","radius":1.5}"#,
+            )?;
+            round_trip(
+                &ctx,
+                Internal::Wrapped(Wrapper(Settings {
+                    user_name: "carol".to_owned(),
+                    maximum: None,
+                    cache: Vec::new(),
+                    tags: Vec::new(),
+                    r#type: 1,
+                })),
+                r#"{"userName":"carol","max":null,"tags":[],"type":1,"kind":"wrapped"}"#,
+            )?;
+
+            let error = Internal::from_js_value(&eval(&ctx, "({ kind: 'square' })")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "unknown variant `square`, expected one of `empty`, `circle`, `wrapped`"
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn adjacently_tagged_enums() -> Result<(), JSException> {
+            let ctx = JSContext::default();
+
+            round_trip(&ctx, Adjacent::Unit, r#"{"t":"Unit"}"#)?;
+            round_trip(&ctx, Adjacent::Tuple(1, 2), r#"{"t":"Tuple","c":[1,2]}"#)?;
+            round_trip(
+                &ctx,
+                Adjacent::Struct { some_field: true },
+                r#"{"t":"s","c":{"SOME_FIELD":true}}"#,
+            )?;
+
+            Ok(())
+        }
+
+        #[test]
+        fn untagged_enums() -> Result<(), JSException> {
+            let ctx = JSContext::default();
+
+            round_trip(&ctx, Untagged::Nothing, "null")?;
+            round_trip(&ctx, Untagged::Number(1.5), "1.5")?;
+            round_trip(&ctx, Untagged::Text("abc".to_owned()), r#""abc""#)?;
+            round_trip(&ctx, Untagged::Point { x: 1, y: 2 }, r#"{"x":1,"y":2}"#)?;
+
+            let error = Untagged::from_js_value(&eval(&ctx, "true")).unwrap_err();
+            assert_eq!(
+                message(&error),
+                "data did not match any variant of untagged enum `Untagged`"
+            );
+
+            Ok(())
+        }
+    }
+}
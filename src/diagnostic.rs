@@ -0,0 +1,360 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{error, fmt};
+
+use crate::{IntoJSException, JSContext, JSException};
+
+/// A syntax error in a script, with its location and the offending line of
+/// source.
+///
+/// It's returned by [`check_script_syntax`](crate::check_script_syntax),
+/// and held by the exception thrown by
+/// [`evaluate_script`](crate::evaluate_script), see
+/// [`JSException::diagnostic()`](crate::JSException::diagnostic).
+///
+/// The alternate mode (`{:#}`) prints the location and the offending line
+/// as well, with carets under the column, or under the whole line if the
+/// column isn't known, like JavaScriptCore's syntax errors:
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let diagnostic =
+///     check_script_syntax(&ctx, "let answer = 42;\nlet = ;", "test.js", 1).unwrap_err();
+///
+/// assert_eq!(diagnostic.source_url.as_deref(), Some("test.js"));
+/// assert_eq!(diagnostic.line, 2);
+/// assert_eq!(diagnostic.source_line.as_deref(), Some("let = ;"));
+///
+/// assert_eq!(
+///     format!("{diagnostic:#}"),
+///     format!(
+///         "SyntaxError: {}\n --> test.js:2\n  |\n2 | let = ;\n  | ^^^^^^^",
+///         diagnostic.message,
+///     ),
+/// );
+/// ```
+///
+/// With the `miette` feature, it implements `miette::Diagnostic`, so that it
+/// can be rendered with the offending line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptDiagnostic {
+    /// The message of the error, e.g. `Unexpected keyword 'let'`.
+    pub message: String,
+    /// The URL of the script, if any.
+    pub source_url: Option<String>,
+    /// The line, one-based, counted from the starting line number of the
+    /// script.
+    pub line: u32,
+    /// The column, one-based, if known.
+    pub column: Option<u32>,
+    /// The offending line of source, if it's in the script.
+    pub source_line: Option<String>,
+}
+
+impl ScriptDiagnostic {
+    /// Creates the diagnostic of `exception` if it's a `SyntaxError`, e.g.
+    /// thrown by [`evaluate_script`](crate::evaluate_script) when
+    /// evaluating `script` with `starting_line_number`.
+    ///
+    /// The exceptions thrown by `evaluate_script` because the script cannot
+    /// be parsed already hold their diagnostic, see
+    /// [`JSException::diagnostic()`](crate::JSException::diagnostic).
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let script = "let answer = (42;";
+    ///
+    /// let exception = evaluate_script(&ctx, script, None, "test.js", 10).unwrap_err();
+    /// let diagnostic = ScriptDiagnostic::from_exception(&exception, script, 10).unwrap();
+    /// assert_eq!(diagnostic.line, 10);
+    /// assert_eq!(diagnostic.source_line.as_deref(), Some(script));
+    ///
+    /// let exception = evaluate_script(&ctx, "oops()", None, "test.js", 1).unwrap_err();
+    /// assert!(ScriptDiagnostic::from_exception(&exception, "oops()", 1).is_none());
+    /// ```
+    pub fn from_exception(
+        exception: &JSException,
+        script: &str,
+        starting_line_number: i32,
+    ) -> Option<Self> {
+        if exception.name().ok()? == "SyntaxError" {
+            Some(Self::new(exception, script, starting_line_number))
+        } else {
+            None
+        }
+    }
+
+    /// Creates the diagnostic of `exception`, whatever its type.
+    pub(crate) fn new(exception: &JSException, script: &str, starting_line_number: i32) -> Self {
+        // Like JavaScriptCore, clamp invalid starting line numbers to `1`.
+        let starting_line = u32::try_from(starting_line_number).unwrap_or(1).max(1);
+        let location = exception.raw_location();
+        let line = location.line.unwrap_or(starting_line);
+        let source_line = line
+            .checked_sub(starting_line)
+            .and_then(|index| script.lines().nth(index as usize))
+            .map(str::to_owned);
+        let message = exception
+            .message()
+            .or_else(|_| exception.underlying_value().as_string())
+            .map(|message| message.to_string())
+            .unwrap_or_default();
+
+        Self {
+            message,
+            source_url: location.source_url,
+            line,
+            column: location.column,
+            source_line,
+        }
+    }
+
+    /// Returns the byte range of the offending part of the source line: the
+    /// character at the column, or the line without its indentation.
+    fn highlight(&self) -> Option<(usize, usize)> {
+        let source_line = self.source_line.as_deref()?;
+
+        match self.column.and_then(|column| column.checked_sub(1)) {
+            Some(index) => {
+                let (start, character) = source_line
+                    .char_indices()
+                    .nth(index as usize)
+                    .unwrap_or((source_line.len(), ' '));
+
+                Some((start, character.len_utf8()))
+            }
+            None => {
+                let trimmed = source_line.trim();
+                let start = source_line.len() - source_line.trim_start().len();
+
+                Some((start, trimmed.len().max(1)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScriptDiagnostic {
+    /// Formats the diagnostic as `SyntaxError: message`.
+    ///
+    /// The alternate mode (`{:#}`) adds the location and the offending line:
+    ///
+    /// ```text
+    /// SyntaxError: Unexpected token ';'
+    ///  --> test.js:2
+    ///   |
+    /// 2 | let = ;
+    ///   | ^^^^^^^
+    /// ```
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "SyntaxError: {}", self.message)?;
+
+        if !formatter.alternate() {
+            return Ok(());
+        }
+
+        let line = self.line.to_string();
+        let margin = " ".repeat(line.len());
+
+        write!(formatter, "\n{margin}--> ")?;
+        if let Some(source_url) = &self.source_url {
+            formatter.write_str(source_url)?;
+        }
+        write!(formatter, ":{line}")?;
+        if let Some(column) = self.column {
+            write!(formatter, ":{column}")?;
+        }
+
+        if let (Some(source_line), Some((start, length))) = (&self.source_line, self.highlight()) {
+            // Keep the tabs, so that the caret is aligned with the source.
+            let indentation = source_line[..start]
+                .chars()
+                .map(|character| if character == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let end = (start + length).min(source_line.len());
+            let carets = "^".repeat(source_line[start..end].chars().count().max(1));
+
+            write!(formatter, "\n{margin} |")?;
+            write!(formatter, "\n{line} | {source_line}")?;
+            write!(formatter, "\n{margin} | {indentation}{carets}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for ScriptDiagnostic {}
+
+impl IntoJSException for ScriptDiagnostic {
+    /// Converts the diagnostic into a `SyntaxError` holding its message.
+    fn into_js_exception(self, ctx: &JSContext) -> JSException {
+        JSException::new_syntax_error(ctx, self.message)
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for ScriptDiagnostic {
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.source_line.as_ref()?;
+
+        Some(self)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let span = self.highlight()?;
+
+        Some(Box::new(std::iter::once(
+            miette::LabeledSpan::new_primary_with_span(None, span),
+        )))
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::SourceCode for ScriptDiagnostic {
+    /// Reads the span from the offending line, numbered as in the script.
+    fn read_span<'a>(
+        &'a self,
+        span: &miette::SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn miette::SpanContents<'a> + 'a>, miette::MietteError> {
+        let source_line = self
+            .source_line
+            .as_deref()
+            .ok_or(miette::MietteError::OutOfBounds)?;
+        let contents = source_line.read_span(span, context_lines_before, context_lines_after)?;
+        let line = contents.line() + self.line as usize - 1;
+
+        Ok(Box::new(match &self.source_url {
+            Some(source_url) => miette::MietteSpanContents::new_named(
+                source_url.clone(),
+                contents.data(),
+                *contents.span(),
+                line,
+                contents.column(),
+                contents.line_count(),
+            ),
+            None => miette::MietteSpanContents::new(
+                contents.data(),
+                *contents.span(),
+                line,
+                contents.column(),
+                contents.line_count(),
+            ),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScriptDiagnostic;
+    use crate::{check_script_syntax, evaluate_script, JSContext};
+
+    #[test]
+    fn syntax_error_diagnostic() {
+        let ctx = JSContext::default();
+
+        let diagnostic =
+            check_script_syntax(&ctx, "let answer = 42;\n  let = ;\nanswer;", "test.js", 10)
+                .unwrap_err();
+
+        assert!(!diagnostic.message.is_empty());
+        assert_eq!(diagnostic.source_url.as_deref(), Some("test.js"));
+        assert_eq!(diagnostic.line, 11);
+        assert_eq!(diagnostic.source_line.as_deref(), Some("  let = ;"));
+
+        let rendered = format!("{diagnostic:#}");
+        let mut lines = rendered.lines();
+        assert_eq!(
+            lines.next(),
+            Some(format!("SyntaxError: {}", diagnostic.message).as_str())
+        );
+        assert!(lines.next().unwrap().starts_with("  --> test.js:11"));
+        assert_eq!(lines.next(), Some("   |"));
+        assert_eq!(lines.next(), Some("11 |   let = ;"));
+        assert!(lines.next().unwrap().starts_with("   |   ^"));
+    }
+
+    #[test]
+    fn caret_under_column() {
+        let diagnostic = ScriptDiagnostic {
+            message: String::from("Unexpected token ';'"),
+            source_url: None,
+            line: 3,
+            column: Some(8),
+            source_line: Some(String::from("\tlet = ;")),
+        };
+
+        assert_eq!(diagnostic.to_string(), "SyntaxError: Unexpected token ';'");
+        assert_eq!(
+            format!("{diagnostic:#}"),
+            "SyntaxError: Unexpected token ';'\n --> :3:8\n  |\n3 | \tlet = ;\n  | \t      ^"
+        );
+    }
+
+    #[test]
+    fn only_syntax_errors_have_diagnostics() {
+        let ctx = JSContext::default();
+
+        let script = "if (true) {";
+        let exception = evaluate_script(&ctx, script, None, "test.js", 1).unwrap_err();
+        let diagnostic = ScriptDiagnostic::from_exception(&exception, script, 1).unwrap();
+        assert_eq!(diagnostic.line, 1);
+        assert_eq!(diagnostic.source_line.as_deref(), Some(script));
+
+        let exception =
+            evaluate_script(&ctx, "throw new Error('oops')", None, "test.js", 1).unwrap_err();
+        assert!(ScriptDiagnostic::from_exception(&exception, "", 1).is_none());
+    }
+
+    #[test]
+    fn evaluate_script_attaches_diagnostics() {
+        let ctx = JSContext::default();
+
+        let script = "let answer = 42;\nif (true) {";
+        let exception = evaluate_script(&ctx, script, None, "test.js", 5).unwrap_err();
+        assert_eq!(
+            exception.diagnostic(),
+            ScriptDiagnostic::from_exception(&exception, script, 5).as_ref()
+        );
+        assert_eq!(exception.diagnostic().unwrap().line, 6);
+
+        // Runtime errors don't have a diagnostic, even `SyntaxError`s.
+        let exception = evaluate_script(&ctx, "JSON.parse('{')", None, "test.js", 1).unwrap_err();
+        assert!(exception.diagnostic().is_none());
+        let exception =
+            evaluate_script(&ctx, "throw new Error('oops')", None, "test.js", 1).unwrap_err();
+        assert!(exception.diagnostic().is_none());
+    }
+
+    #[cfg(feature = "miette")]
+    #[test]
+    fn miette_diagnostic() {
+        use miette::Diagnostic;
+
+        let diagnostic = ScriptDiagnostic {
+            message: String::from("Unexpected token ';'"),
+            source_url: Some(String::from("test.js")),
+            line: 3,
+            column: Some(5),
+            source_line: Some(String::from("let = ;")),
+        };
+
+        let label = diagnostic.labels().unwrap().next().unwrap();
+        assert_eq!((label.offset(), label.len()), (4, 1));
+
+        let contents = diagnostic
+            .source_code()
+            .unwrap()
+            .read_span(label.inner(), 0, 0)
+            .unwrap();
+        assert_eq!(contents.name(), Some("test.js"));
+        assert_eq!(contents.line(), 2);
+        assert_eq!(contents.data(), b"let = ;");
+    }
+}
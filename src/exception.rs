@@ -4,42 +4,881 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{error, fmt};
+use std::{error, fmt, ptr};
 
-use crate::{sys, JSException, JSString, JSValue};
+use crate::{
+    sys, FromJSValue, JSContext, JSException, JSObject, JSString, JSValue, ScriptDiagnostic,
+    StackFrame,
+};
 
 impl JSException {
+    /// Create an exception holding a new instance of the built-in error
+    /// constructor named `constructor`, e.g. `TypeError` or `RangeError`.
+    ///
+    /// The error is built from the intrinsic constructors, which scripts
+    /// can't replace. Unknown names make an `Error`.
+    pub(crate) fn new_builtin(ctx: sys::JSContextRef, constructor: &str, message: &str) -> Self {
+        Self::new_builtin_inner(ctx, constructor, JSValue::new_string_inner(ctx, message))
+    }
+
+    fn new_builtin_inner(ctx: sys::JSContextRef, constructor: &str, message: JSValue<'_>) -> Self {
+        let mut exception = ptr::null();
+        let error = unsafe { sys::JSObjectMakeError(ctx, 1, &message.raw, &mut exception) };
+
+        if error.is_null() {
+            return if exception.is_null() {
+                message.into()
+            } else {
+                unsafe { JSValue::from_raw(ctx, exception) }.into()
+            };
+        }
+
+        if let Some(prototype) = unsafe { intrinsic_error_prototype(ctx, constructor) } {
+            unsafe { sys::JSObjectSetPrototype(ctx, error, prototype) };
+        }
+
+        unsafe { JSValue::from_raw(ctx, error) }.into()
+    }
+
+    /// Creates an exception holding a new `Error`, whose `message` is
+    /// `message`.
+    ///
+    /// Contrary to a string, the error has a `name`, a `message` and a
+    /// `stack` when it's caught by a script:
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// #[function_callback]
+    /// fn fail(
+    ///     ctx: &JSContext,
+    ///     _function: Option<&JSObject>,
+    ///     _this_object: Option<&JSObject>,
+    ///     _arguments: &[JSValue],
+    /// ) -> Result<JSValue, JSException> {
+    ///     Err(JSException::new_error(ctx, "oops"))
+    /// }
+    ///
+    /// let ctx = JSContext::default();
+    /// let fail = JSValue::new_function(&ctx, "fail", Some(fail));
+    /// ctx.global_object().unwrap().set_property("fail", fail).unwrap();
+    ///
+    /// let caught = evaluate_script(
+    ///     &ctx,
+    ///     "try { fail() } catch (error) { `${error instanceof Error} ${error.message}` }",
+    ///     None,
+    ///     "test.js",
+    ///     1,
+    /// ).unwrap();
+    /// assert_eq!(caught.as_string().unwrap(), "true oops");
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSObject::new_error()`]
+    pub fn new_error<S: Into<JSString>>(ctx: &JSContext, message: S) -> Self {
+        match JSObject::new_error(ctx, message) {
+            Ok(error) => JSValue::from(error).into(),
+            Err(exception) => exception,
+        }
+    }
+
+    /// Creates an exception holding a new `Error` mirroring a Rust error.
+    ///
+    /// The `message` of the JavaScript error is the top-level error, and its
+    /// `cause` chain mirrors [`Error::source`](error::Error::source): each
+    /// source becomes an `Error` which is the `cause` of the previous one.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// #[derive(Debug, thiserror::Error)]
+    /// #[error("cannot load the settings")]
+    /// struct SettingsError(#[source] std::num::ParseIntError);
+    ///
+    /// let ctx = JSContext::default();
+    /// let error = SettingsError("x".parse::<u8>().unwrap_err());
+    /// let exception = JSException::from_error(&ctx, &error);
+    ///
+    /// assert_eq!(exception.message().unwrap(), "cannot load the settings");
+    ///
+    /// let cause = exception.cause().unwrap().as_object().unwrap();
+    /// assert_eq!(cause.get_property("message").as_string().unwrap(), "invalid digit found in string");
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`ResultExt::into_js()`]
+    pub fn from_error(ctx: &JSContext, error: &dyn error::Error) -> Self {
+        let mut chain = vec![error];
+
+        while let Some(source) = chain.last().and_then(|error| error.source()) {
+            chain.push(source);
+        }
+
+        // Build the chain from its root, so that each error can be the cause
+        // of the previous one.
+        let mut exception: Option<Self> = None;
+
+        for error in chain.into_iter().rev() {
+            let error = Self::new_error(ctx, error.to_string());
+
+            exception = Some(match exception {
+                Some(cause) => error.with_cause(&cause.underlying_value()),
+                None => error,
+            });
+        }
+
+        exception.expect("the chain holds at least the top-level error")
+    }
+
+    /// Creates an exception holding a new `TypeError`, whose `message` is
+    /// `message`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let exception = JSException::new_type_error(&ctx, "expected a number");
+    ///
+    /// assert_eq!(exception.name().unwrap(), "TypeError");
+    /// assert_eq!(exception.message().unwrap(), "expected a number");
+    /// ```
+    pub fn new_type_error<S: Into<JSString>>(ctx: &JSContext, message: S) -> Self {
+        Self::new_builtin_inner(ctx.raw, "TypeError", JSValue::new_string(ctx, message))
+    }
+
+    /// Creates an exception holding a new `RangeError`, whose `message` is
+    /// `message`.
+    pub fn new_range_error<S: Into<JSString>>(ctx: &JSContext, message: S) -> Self {
+        Self::new_builtin_inner(ctx.raw, "RangeError", JSValue::new_string(ctx, message))
+    }
+
+    /// Creates an exception holding a new `SyntaxError`, whose `message` is
+    /// `message`.
+    pub fn new_syntax_error<S: Into<JSString>>(ctx: &JSContext, message: S) -> Self {
+        Self::new_builtin_inner(ctx.raw, "SyntaxError", JSValue::new_string(ctx, message))
+    }
+
+    /// Creates an exception holding a new `ReferenceError`, whose `message`
+    /// is `message`.
+    pub fn new_reference_error<S: Into<JSString>>(ctx: &JSContext, message: S) -> Self {
+        Self::new_builtin_inner(ctx.raw, "ReferenceError", JSValue::new_string(ctx, message))
+    }
+
+    /// Sets the `cause` of the error held by this exception, i.e. the value
+    /// that caused it, like `new Error(message, { cause })` does.
+    ///
+    /// Nothing is set if the exception doesn't hold an object, or if `cause`
+    /// belongs to another [`JSContextGroup`](crate::JSContextGroup).
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let cause = evaluate_script(&ctx, "JSON.parse('{')", None, "test.js", 1).unwrap_err();
+    ///
+    /// let exception =
+    ///     JSException::new_error(&ctx, "invalid settings").with_cause(&cause.underlying_value());
+    /// assert_eq!(exception.cause().unwrap(), cause.underlying_value());
+    /// ```
+    pub fn with_cause(self, cause: &JSValue<'_>) -> Self {
+        if self.underlying_value().is_object() && cause.same_group(self.ctx) {
+            let name = JSString::from("cause");
+            let mut exception: sys::JSValueRef = ptr::null_mut();
+
+            // Like the property defined by the `Error` constructor, `cause`
+            // isn't enumerable. A frozen error keeps its cause, if any.
+            unsafe {
+                sys::JSObjectSetProperty(
+                    self.ctx,
+                    self.value as sys::JSObjectRef,
+                    name.raw,
+                    cause.raw,
+                    sys::kJSPropertyAttributeDontEnum,
+                    &mut exception,
+                );
+            }
+        }
+
+        self
+    }
+
+    /// Returns the diagnostic of the syntax error of the script, if this
+    /// exception has been thrown by [`evaluate_script`](crate::evaluate_script)
+    /// because the script cannot be parsed.
+    ///
+    /// The syntax errors thrown while the script runs, e.g. by `JSON.parse()`,
+    /// don't have a diagnostic.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let exception = evaluate_script(&ctx, "let answer = (42;", None, "test.js", 10).unwrap_err();
+    /// let diagnostic = exception.diagnostic().unwrap();
+    /// assert_eq!(diagnostic.line, 10);
+    /// assert_eq!(diagnostic.source_line.as_deref(), Some("let answer = (42;"));
+    ///
+    /// let exception = evaluate_script(&ctx, "JSON.parse('{')", None, "test.js", 1).unwrap_err();
+    /// assert_eq!(exception.name().unwrap(), "SyntaxError");
+    /// assert!(exception.diagnostic().is_none());
+    /// ```
+    pub fn diagnostic(&self) -> Option<&ScriptDiagnostic> {
+        self.diagnostic.as_deref()
+    }
+
     /// Return the underlying value backing the exception.
-    pub const fn underlying_value(&self) -> &JSValue {
-        &self.value
+    ///
+    /// The value cannot outlive the exception, which keeps its context
+    /// alive.
+    pub fn underlying_value(&self) -> JSValue<'_> {
+        unsafe { JSValue::from_raw(self.ctx, self.value) }
     }
 
     /// Return the name of the exception. This is the value of the `name`
     /// property on the exception object.
     pub fn name(&self) -> Result<JSString, JSException> {
-        self.value.as_object()?.get_property("name").as_string()
+        self.underlying_value()
+            .as_object()?
+            .get_property("name")
+            .as_string()
+    }
+
+    /// Return the message of the exception. This is the value of the
+    /// `message` property on the exception object.
+    pub fn message(&self) -> Result<JSString, JSException> {
+        self.underlying_value()
+            .as_object()?
+            .get_property("message")
+            .as_string()
+    }
+
+    /// Return the `cause` of the exception, if any.
+    ///
+    /// # See also
+    ///
+    /// - [`JSException::with_cause()`]
+    pub fn cause(&self) -> Option<JSValue<'_>> {
+        let error = self.error()?;
+
+        if error.has_property("cause") {
+            Some(error.get_property("cause"))
+        } else {
+            None
+        }
+    }
+
+    /// Return the stack trace of the exception, if any. This is the value
+    /// of the `stack` property on the exception object, as captured when
+    /// the error has been created.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let exception = evaluate_script(
+    ///     &ctx,
+    ///     "function fail() { throw new Error('oops') }\nfail()",
+    ///     None,
+    ///     "test.js",
+    ///     1,
+    /// ).unwrap_err();
+    ///
+    /// assert!(exception.stack().unwrap().to_string().starts_with("fail@"));
+    /// assert_eq!(exception.line(), Some(1));
+    /// assert_eq!(exception.source_url().unwrap(), "test.js");
+    /// ```
+    pub fn stack(&self) -> Option<JSString> {
+        self.string_property("stack")
+    }
+
+    /// Return the frames of the stack trace of the exception, from the
+    /// innermost one, parsed from [`JSException::stack()`].
+    ///
+    /// The frames are empty if the exception doesn't have a stack trace,
+    /// e.g. when a string has been thrown.
+    ///
+    /// # See also
+    ///
+    /// - [`StackFrame`]
+    /// - [`JSContext::register_source_map()`], with the `sourcemap` feature
+    pub fn stack_frames(&self) -> Vec<StackFrame> {
+        #[allow(unused_mut)]
+        let mut frames = self
+            .stack()
+            .map(|stack| StackFrame::parse_stack(&stack.to_string()))
+            .unwrap_or_default();
+
+        #[cfg(feature = "sourcemap")]
+        for frame in &mut frames {
+            crate::source_map::remap(self.ctx, frame);
+        }
+
+        frames
+    }
+
+    /// Return the line, one-based, at which the exception has been
+    /// thrown, if known.
+    pub fn line(&self) -> Option<u32> {
+        self.location().line
+    }
+
+    /// Return the column, one-based, at which the exception has been
+    /// thrown, if known.
+    pub fn column(&self) -> Option<u32> {
+        self.location().column
+    }
+
+    /// Return the URL of the script in which the exception has been
+    /// thrown, if known, as given to [`evaluate_script`](crate::evaluate_script).
+    pub fn source_url(&self) -> Option<JSString> {
+        self.location().source_url.map(JSString::from)
+    }
+
+    /// Return the location at which the exception has been thrown,
+    /// remapped to the original source if a source map has been
+    /// registered for its script.
+    fn location(&self) -> StackFrame {
+        #[allow(unused_mut)]
+        let mut location = self.raw_location();
+
+        #[cfg(feature = "sourcemap")]
+        crate::source_map::remap(self.ctx, &mut location);
+
+        location
+    }
+
+    /// Return the location at which the exception has been thrown, in the
+    /// evaluated script.
+    pub(crate) fn raw_location(&self) -> StackFrame {
+        StackFrame {
+            function: None,
+            source_url: self
+                .string_property("sourceURL")
+                .map(|source_url| source_url.to_string()),
+            line: self.integer_property("line"),
+            column: self.integer_property("column"),
+        }
+    }
+
+    /// Return the exception object, if the exception holds an object.
+    fn error(&self) -> Option<JSObject<'_>> {
+        let value = self.underlying_value();
+
+        if value.is_object() {
+            Some(unsafe { JSObject::from_raw(self.ctx, self.value as sys::JSObjectRef) })
+        } else {
+            None
+        }
+    }
+
+    fn string_property(&self, name: &str) -> Option<JSString> {
+        let value = self.error()?.get_property(name);
+
+        if value.is_string() {
+            value.as_string().ok()
+        } else {
+            None
+        }
+    }
+
+    fn integer_property(&self, name: &str) -> Option<u32> {
+        let value = self.error()?.get_property(name);
+
+        if value.is_number() {
+            u32::from_js_value(&value).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Conversion of an error into a [`JSException`], as used by the functions
+/// declared with [`crate::js_function`] that return a `Result`.
+///
+/// Strings and boxed errors are converted into JavaScript `Error` instances
+/// holding their message, and the sources of boxed errors become the `cause`
+/// chain, see [`JSException::from_error`]. To return your own error type,
+/// implement this trait for it:
+///
+/// ```
+/// # use javascriptcore::{IntoJSException, JSContext, JSException};
+/// struct MyError {
+///     code: u32,
+/// }
+///
+/// impl IntoJSException for MyError {
+///     fn into_js_exception(self, ctx: &JSContext) -> JSException {
+///         format!("failed with code {}", self.code).into_js_exception(ctx)
+///     }
+/// }
+/// ```
+pub trait IntoJSException {
+    /// Converts `self` into an exception living in `ctx`.
+    fn into_js_exception(self, ctx: &JSContext) -> JSException;
+}
+
+impl IntoJSException for JSException {
+    fn into_js_exception(self, _ctx: &JSContext) -> JSException {
+        self
+    }
+}
+
+impl IntoJSException for &str {
+    fn into_js_exception(self, ctx: &JSContext) -> JSException {
+        JSException::new_builtin(ctx.raw, "Error", self)
+    }
+}
+
+impl IntoJSException for String {
+    fn into_js_exception(self, ctx: &JSContext) -> JSException {
+        self.as_str().into_js_exception(ctx)
+    }
+}
+
+impl IntoJSException for Box<dyn error::Error> {
+    fn into_js_exception(self, ctx: &JSContext) -> JSException {
+        JSException::from_error(ctx, &*self)
+    }
+}
+
+impl IntoJSException for Box<dyn error::Error + Send + Sync> {
+    fn into_js_exception(self, ctx: &JSContext) -> JSException {
+        JSException::from_error(ctx, &*self)
+    }
+}
+
+/// Conversion of the error of a `Result` into a [`JSException`], so that
+/// callbacks can use `?` on the errors of other crates.
+///
+/// It's implemented for any error that can be converted into a
+/// `Box<dyn Error + Send + Sync>`, like the errors implementing
+/// [`std::error::Error`], `anyhow::Error`, or strings. The error is
+/// converted with [`JSException::from_error`], keeping its sources as the
+/// `cause` chain.
+///
+/// ```
+/// # use javascriptcore::*;
+/// #[function_callback]
+/// fn parse(
+///     ctx: &JSContext,
+///     _function: Option<&JSObject>,
+///     _this_object: Option<&JSObject>,
+///     arguments: &[JSValue],
+/// ) -> Result<JSValue, JSException> {
+///     let string = arguments[0].as_string()?.to_string();
+///     let number = string.parse::<u8>().into_js(ctx)?;
+///
+///     Ok(JSValue::new_number(ctx, number.into()))
+/// }
+///
+/// let ctx = JSContext::default();
+/// let parse = JSValue::new_function(&ctx, "parse", Some(parse));
+/// ctx.global_object().unwrap().set_property("parse", parse).unwrap();
+///
+/// let exception = evaluate_script(&ctx, "parse('x')", None, "test.js", 1).unwrap_err();
+/// assert_eq!(exception.message().unwrap(), "invalid digit found in string");
+/// ```
+pub trait ResultExt<T> {
+    /// Converts the error, if any, into an exception living in `ctx`.
+    fn into_js(self, ctx: &JSContext) -> Result<T, JSException>;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    fn into_js(self, ctx: &JSContext) -> Result<T, JSException> {
+        self.map_err(|error| JSException::from_error(ctx, &*error.into()))
     }
 }
 
 impl fmt::Display for JSException {
+    /// Formats the exception as a string.
+    ///
+    /// The alternate mode (`{:#}`) prints the exception as `name: message`
+    /// instead, followed by its stack trace, like a Rust backtrace:
+    ///
+    /// ```text
+    /// Error: oops
+    ///    0: fail
+    ///              at test.js:1:35
+    ///    1: global code
+    ///              at test.js:2:5
+    /// ```
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.underlying_value().as_string() {
-            Ok(string) => write!(formatter, "JSException (interpreted as string): {string}"),
-            Err(_) => write!(formatter, "{self:?}"),
+        if !formatter.alternate() {
+            return match self.underlying_value().as_string() {
+                Ok(string) => write!(formatter, "JSException (interpreted as string): {string}"),
+                Err(_) => write!(formatter, "{self:?}"),
+            };
+        }
+
+        match (self.name(), self.message()) {
+            (Ok(name), Ok(message)) if self.error().is_some() => {
+                write!(formatter, "{name}: {message}")?;
+            }
+            _ => match self.underlying_value().as_string() {
+                Ok(string) => write!(formatter, "{string}")?,
+                Err(_) => write!(formatter, "{self:?}")?,
+            },
+        }
+
+        for (index, frame) in self.stack_frames().iter().enumerate() {
+            let function = frame.function.as_deref().unwrap_or("<anonymous>");
+            write!(formatter, "\n{index:>4}: {function}")?;
+
+            match (&frame.source_url, frame.line, frame.column) {
+                (Some(url), Some(line), Some(column)) => {
+                    write!(formatter, "\n             at {url}:{line}:{column}")?;
+                }
+                (Some(url), _, _) => write!(formatter, "\n             at {url}")?,
+                _ => {}
+            }
         }
+
+        Ok(())
     }
 }
 
 impl error::Error for JSException {}
 
-impl From<JSValue> for JSException {
-    fn from(value: JSValue) -> Self {
-        Self { value }
+impl From<JSValue<'_>> for JSException {
+    fn from(value: JSValue<'_>) -> Self {
+        unsafe {
+            let ctx = sys::JSGlobalContextRetain(sys::JSContextGetGlobalContext(value.ctx));
+            sys::JSValueProtect(ctx, value.raw);
+
+            Self {
+                ctx,
+                value: value.raw,
+                diagnostic: None,
+            }
+        }
     }
 }
 
 impl From<JSException> for sys::JSValueRef {
-    fn from(value: JSException) -> Self {
-        value.value.raw
+    /// Returns the raw value of the exception, e.g. to throw it from a
+    /// callback. The value is no longer protected: it must be handed to
+    /// JavaScriptCore right away.
+    fn from(exception: JSException) -> Self {
+        exception.value
+    }
+}
+
+impl Drop for JSException {
+    fn drop(&mut self) {
+        unsafe {
+            sys::JSValueUnprotect(self.ctx, self.value);
+            sys::JSGlobalContextRelease(self.ctx);
+        }
+    }
+}
+
+/// Returns the intrinsic prototype of the built-in error constructor named
+/// `constructor`, other than `Error`.
+///
+/// The global constructors may have been replaced by scripts, so the engine
+/// is made to throw one of its own instances instead, without running any
+/// script code.
+///
+/// # Safety
+///
+/// Ensure `ctx` is valid.
+unsafe fn intrinsic_error_prototype(
+    ctx: sys::JSContextRef,
+    constructor: &str,
+) -> Option<sys::JSValueRef> {
+    let mut exception = ptr::null();
+
+    match constructor {
+        "TypeError" => {
+            sys::JSValueToObject(ctx, sys::JSValueMakeNull(ctx), &mut exception);
+        }
+        "RangeError" => {
+            let array = sys::JSObjectMakeArray(ctx, 0, ptr::null(), ptr::null_mut());
+
+            if !array.is_null() {
+                sys::JSObjectSetProperty(
+                    ctx,
+                    array,
+                    JSString::from("length").raw,
+                    sys::JSValueMakeNumber(ctx, -1.),
+                    sys::kJSPropertyAttributeNone,
+                    &mut exception,
+                );
+            }
+        }
+        "SyntaxError" => {
+            let pattern = JSValue::new_string_inner(ctx, "(");
+            sys::JSObjectMakeRegExp(ctx, 1, &pattern.raw, &mut exception);
+        }
+        "ReferenceError" => {
+            // Reading a `let` binding before its declaration.
+            let function = sys::JSObjectMakeFunction(
+                ctx,
+                ptr::null_mut(),
+                0,
+                ptr::null(),
+                JSString::from("x; let x;").raw,
+                ptr::null_mut(),
+                1,
+                &mut exception,
+            );
+
+            if !function.is_null() {
+                sys::JSObjectCallAsFunction(
+                    ctx,
+                    function,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null(),
+                    &mut exception,
+                );
+            }
+        }
+        _ => return None,
+    }
+
+    if exception.is_null() || !sys::JSValueIsObject(ctx, exception) {
+        return None;
+    }
+
+    Some(sys::JSObjectGetPrototype(
+        ctx,
+        exception as sys::JSObjectRef,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error, fmt};
+
+    use crate::{
+        evaluate_script, FromJSValue, IntoJSException, JSContext, JSException, JSValue, ResultExt,
+    };
+
+    #[test]
+    fn typed_errors() {
+        let ctx = JSContext::default();
+
+        for (exception, name) in [
+            (JSException::new_error(&ctx, "oops"), "Error"),
+            (JSException::new_type_error(&ctx, "oops"), "TypeError"),
+            (JSException::new_range_error(&ctx, "oops"), "RangeError"),
+            (JSException::new_syntax_error(&ctx, "oops"), "SyntaxError"),
+            (
+                JSException::new_reference_error(&ctx, "oops"),
+                "ReferenceError",
+            ),
+        ] {
+            assert_eq!(exception.name().unwrap(), name);
+            assert_eq!(exception.message().unwrap(), "oops");
+            assert!(exception.stack().is_some());
+            assert!(exception.cause().is_none());
+        }
+    }
+
+    #[test]
+    fn typed_errors_ignore_replaced_constructors() {
+        let ctx = JSContext::default();
+        evaluate_script(
+            &ctx,
+            "var called = false;
+             var intrinsics = { Error, TypeError, RangeError, SyntaxError, ReferenceError };
+             for (const name in intrinsics) {
+                 globalThis[name] = function () { called = true; throw 'hijacked'; };
+             }",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        let global_object = ctx.global_object().unwrap();
+
+        for (exception, name) in [
+            (JSException::new_error(&ctx, "oops"), "Error"),
+            (JSException::new_type_error(&ctx, "oops"), "TypeError"),
+            (JSException::new_range_error(&ctx, "oops"), "RangeError"),
+            (JSException::new_syntax_error(&ctx, "oops"), "SyntaxError"),
+            (
+                JSException::new_reference_error(&ctx, "oops"),
+                "ReferenceError",
+            ),
+            (
+                u8::from_js_value(&JSValue::new_number(&ctx, 256.)).unwrap_err(),
+                "RangeError",
+            ),
+        ] {
+            assert_eq!(exception.name().unwrap(), name);
+            global_object
+                .set_property("error", exception.underlying_value())
+                .unwrap();
+
+            let is_intrinsic = evaluate_script(
+                &ctx,
+                format!(
+                    "Object.getPrototypeOf(error) === intrinsics.{name}.prototype \
+                     && error instanceof intrinsics.Error && !called"
+                ),
+                None,
+                "test.js",
+                1,
+            )
+            .unwrap();
+            assert!(is_intrinsic.as_boolean(), "{name}");
+        }
+    }
+
+    #[derive(Debug)]
+    struct ChainedError(&'static str, Option<Box<ChainedError>>);
+
+    impl fmt::Display for ChainedError {
+        fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str(self.0)
+        }
+    }
+
+    impl error::Error for ChainedError {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            self.1.as_deref().map(|source| source as _)
+        }
+    }
+
+    #[test]
+    fn error_chain_becomes_causes() {
+        let ctx = JSContext::default();
+        let error = ChainedError(
+            "cannot start",
+            Some(Box::new(ChainedError(
+                "cannot read the settings",
+                Some(Box::new(ChainedError("permission denied", None))),
+            ))),
+        );
+
+        let exception = Err::<(), _>(error).into_js(&ctx).unwrap_err();
+        let global = ctx.global_object().unwrap();
+        global
+            .set_property("error", exception.underlying_value().clone())
+            .unwrap();
+
+        let messages = evaluate_script(
+            &ctx,
+            "const messages = []; \
+             for (let e = error; e; e = e.cause) messages.push(e.message); \
+             messages.join(': ')",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            messages.as_string().unwrap(),
+            "cannot start: cannot read the settings: permission denied"
+        );
+    }
+
+    #[test]
+    fn boxed_errors_keep_their_causes() {
+        let ctx = JSContext::default();
+        let error: Box<dyn error::Error> = Box::new(ChainedError(
+            "outer",
+            Some(Box::new(ChainedError("inner", None))),
+        ));
+
+        let exception = error.into_js_exception(&ctx);
+        assert_eq!(exception.message().unwrap(), "outer");
+
+        let cause = exception.cause().unwrap().as_object().unwrap();
+        assert_eq!(cause.get_property("message").as_string().unwrap(), "inner");
+        assert!(!cause.has_property("cause"));
+    }
+
+    #[test]
+    fn error_location() {
+        let ctx = JSContext::default();
+
+        let exception = evaluate_script(
+            &ctx,
+            "let a = 1;\n  undefinedFunction();",
+            None,
+            "https://example.com/script.js",
+            10,
+        )
+        .unwrap_err();
+
+        assert_eq!(exception.name().unwrap(), "ReferenceError");
+        assert_eq!(exception.line(), Some(11));
+        assert!(exception.column().is_some());
+        assert_eq!(
+            exception.source_url().unwrap(),
+            "https://example.com/script.js"
+        );
+    }
+
+    #[test]
+    fn stack_frames() {
+        let ctx = JSContext::default();
+
+        let exception = evaluate_script(
+            &ctx,
+            "function fail() {\n  throw new TypeError('oops');\n}\n(() => fail())();",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap_err();
+
+        let frames = exception.stack_frames();
+        let functions = frames
+            .iter()
+            .map(|frame| frame.function.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(functions, [Some("fail"), None, Some("global code")]);
+        assert_eq!(frames[0].source_url.as_deref(), Some("test.js"));
+        assert_eq!(frames[0].line, Some(2));
+        assert_eq!(frames[2].line, Some(4));
+
+        let backtrace = format!("{exception:#}");
+        let mut lines = backtrace.lines();
+        assert_eq!(lines.next(), Some("TypeError: oops"));
+        assert_eq!(lines.next(), Some("   0: fail"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("             at test.js:2:"));
+        assert_eq!(lines.next(), Some("   1: <anonymous>"));
+    }
+
+    #[test]
+    fn thrown_primitives_have_no_details() {
+        let ctx = JSContext::default();
+
+        let exception = evaluate_script(&ctx, "throw 'oops'", None, "test.js", 1).unwrap_err();
+
+        assert!(exception.message().is_err());
+        assert!(exception.stack().is_none());
+        assert!(exception.stack_frames().is_empty());
+        assert_eq!(format!("{exception:#}"), "oops");
+        assert!(exception.line().is_none());
+        assert!(exception.source_url().is_none());
+        assert!(exception
+            .with_cause(&JSValue::new_null(&ctx))
+            .cause()
+            .is_none());
+    }
+
+    #[test]
+    fn cause_is_not_enumerable() {
+        let ctx = JSContext::default();
+
+        let exception =
+            JSException::new_type_error(&ctx, "oops").with_cause(&JSValue::new_number(&ctx, 42.));
+        assert_eq!(exception.cause().unwrap().as_number().unwrap(), 42.);
+
+        let global = ctx.global_object().unwrap();
+        global
+            .set_property("error", exception.underlying_value().clone())
+            .unwrap();
+        let keys = evaluate_script(&ctx, "Object.keys(error).join()", None, "test.js", 1).unwrap();
+        assert_eq!(keys.as_string().unwrap(), "");
     }
 }
@@ -8,6 +8,55 @@
 //!
 //! Evaluate JavaScript programs from within an app, and support
 //! JavaScript scripting of your app.
+//!
+//! # Lifetimes
+//!
+//! [`JSValue`], [`JSObject`], [`JSTypedArray`] and [`JSClass`] borrow the
+//! [`JSContext`] they have been created in, through a `'ctx` lifetime.
+//! The compiler rejects a value outliving its context:
+//!
+//! ```compile_fail
+//! # use javascriptcore::*;
+//! let ctx = JSContext::default();
+//! let value = JSValue::new_number(&ctx, 42.);
+//!
+//! drop(ctx);
+//! value.as_number();
+//! ```
+//!
+//! A [`JSException`] doesn't borrow its context, so that it can be
+//! propagated with `?` out of the function owning the context.
+//!
+//! Passing a value to a context of another [`JSContextGroup`] compiles,
+//! but fails at runtime with a `TypeError`.
+//!
+//! ## Migrating from unbound values
+//!
+//! Most code compiles as is, as lifetimes are inferred. A function creating
+//! values must tie them to the context it receives:
+//!
+//! ```
+//! # use javascriptcore::*;
+//! fn answer<'ctx>(ctx: &'ctx JSContext) -> JSValue<'ctx> {
+//!     JSValue::new_number(ctx, 42.)
+//! }
+//! ```
+//!
+//! The lifetime can be elided when the function receives the context only
+//! (`fn answer(ctx: &JSContext) -> JSValue<'_>`), but not when it receives
+//! other references, e.g. values.
+//!
+//! Functions annotated with the callback macros, like
+//! [`function_callback`] or [`js_function`], or exposed by [`js_class`],
+//! don't need to be changed: the elided lifetimes of `&JSContext`,
+//! `JSValue`, `JSObject`, `JSTypedArray` and `JSClass` in their signature
+//! are bound to a `'ctx` lifetime parameter, added if needed.
+//!
+//! [`ToJSValue`] and [`FromJSValue`] take the lifetime as a parameter.
+//! Their implementations become `impl<'ctx> ToJSValue<'ctx> for T`, and a
+//! bound on a conversion which isn't tied to a given context becomes
+//! `T: for<'ctx> FromJSValue<'ctx>`. The derive macros reuse a `'ctx`
+//! lifetime parameter of the type, e.g. to hold a `JSValue<'ctx>`.
 
 #![warn(clippy::doc_markdown, missing_docs)]
 #![deny(
@@ -17,36 +66,78 @@
     unused_qualifications
 )]
 
-use std::ffi::CString;
+use std::{ffi::CString, marker::PhantomData};
 
-pub use javascriptcore_macros::{constructor_callback, function_callback};
+pub use javascriptcore_macros::{
+    constructor_callback, convert_to_type_callback, delete_property_callback, finalize_callback,
+    function_callback, get_property_callback, get_property_names_callback, has_instance_callback,
+    has_property_callback, initialize_callback, js_class, js_function, set_property_callback,
+    FromJSValue, ToJSValue,
+};
 #[doc(hidden)]
 pub use javascriptcore_sys as sys;
 
 mod base;
 mod class;
+mod closure;
+#[cfg(feature = "console")]
+pub mod console;
 mod context;
 mod contextgroup;
+mod convert;
+mod diagnostic;
 mod exception;
+#[doc(hidden)]
+pub mod macro_support;
 mod object;
+mod panic;
+mod persistent;
+mod promise;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "sourcemap")]
+mod source_map;
+mod spawn;
+mod stack;
+#[cfg(feature = "sourcemap")]
+mod state;
 mod string;
+#[cfg(feature = "timers")]
+pub mod timers;
 mod typed_array;
 mod value;
+#[cfg(feature = "weak")]
+mod weak;
 
 pub use crate::sys::{JSType, JSTypedArrayType};
 pub use crate::{
     base::{check_script_syntax, evaluate_script, garbage_collect},
-    class::JSClassBuilder,
+    class::{JSClassBuilder, JSPropertyNameAccumulator},
+    convert::{FromJSValue, ToJSValue},
+    diagnostic::ScriptDiagnostic,
+    exception::{IntoJSException, ResultExt},
+    panic::{panic_strategy, set_panic_strategy, PanicStrategy},
+    persistent::Persistable,
+    spawn::Spawner,
+    stack::StackFrame,
 };
 
+/// The largest integer that can be represented exactly by a JavaScript
+/// number, i.e. `Number.MAX_SAFE_INTEGER`.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
 /// A JavaScript class.
 ///
 /// The best way to create a class is by using [`JSClass::builder`].
-pub struct JSClass {
+pub struct JSClass<'ctx> {
     ctx: sys::JSContextRef,
     raw: sys::JSClassRef,
+    /// The subclass of the objects holding private data, see
+    /// [`JSClass::new_object_with_data`].
+    data_class: sys::JSClassRef,
     #[allow(unused)]
     name: CString,
+    context: PhantomData<&'ctx JSContext>,
 }
 
 /// A JavaScript execution context.
@@ -59,8 +150,8 @@ pub struct JSContext {
 /// A group that associates JavaScript contexts with one another.
 ///
 /// Contexts in the same group may share and exchange JavaScript
-/// objects. Sharing and/or exchanging JavaScript objects between
-/// contexts in different groups will produce undefined behavior.
+/// objects. Exchanging JavaScript values between contexts in different
+/// groups is rejected with a `TypeError`, see [`JSValue`].
 /// When objects from the same context group are used in multiple
 /// threads, explicit synchronization is required.
 pub struct JSContextGroup {
@@ -68,9 +159,18 @@ pub struct JSContextGroup {
 }
 
 /// A wrapper for a [`JSValue`] that contains an exception.
+///
+/// Contrary to the values, an exception doesn't borrow its context: it
+/// retains the context and protects its value until it's dropped. Thus, an
+/// exception can be returned from the function owning the context, e.g.
+/// with the `?` operator.
 #[derive(Debug)]
 pub struct JSException {
-    value: JSValue,
+    ctx: sys::JSGlobalContextRef,
+    value: sys::JSValueRef,
+    /// The diagnostic of a syntax error of the evaluated script, see
+    /// [`JSException::diagnostic`].
+    diagnostic: Option<Box<ScriptDiagnostic>>,
 }
 
 /// A JavaScript object.
@@ -78,9 +178,82 @@ pub struct JSException {
 /// An `JSObject` is a [`JSValue`]. This is implemented by having
 /// `JSObject` implement the `Deref` trait so that anything that
 /// expects a `JSValue` can receive a `JSObject` as well.
-pub struct JSObject {
+///
+/// Like a [`JSValue`], an object cannot outlive its context.
+pub struct JSObject<'ctx> {
     raw: sys::JSObjectRef,
-    value: JSValue,
+    value: JSValue<'ctx>,
+}
+
+/// A JavaScript promise, settled from Rust.
+///
+/// [`JSPromise::new`] creates a pending promise along with its
+/// [`JSPromiseResolver`], so that a host function can return the promise
+/// right away, and settle it later. A `JSPromise` is a [`JSObject`], through
+/// the `Deref` trait.
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+/// ctx.global_object().unwrap().set_property("promise", promise.into()).unwrap();
+/// evaluate_script(&ctx, "promise.then(value => answer = value)", None, "test.js", 1).unwrap();
+///
+/// resolver.resolve(&JSValue::new_number(&ctx, 42.)).unwrap();
+///
+/// let answer = evaluate_script(&ctx, "answer", None, "test.js", 1).unwrap();
+/// assert_eq!(answer.as_number().unwrap(), 42.);
+/// ```
+pub struct JSPromise<'ctx> {
+    object: JSObject<'ctx>,
+}
+
+/// The functions settling a [`JSPromise`].
+///
+/// Like a [`Persistent`], a resolver doesn't borrow its context: it retains
+/// the context, and protects the `resolve` and `reject` functions of the
+/// promise until it's dropped. Thus, it can be moved into a callback or
+/// stored until the result is known.
+///
+/// A resolver settles its promise once, as [`JSPromiseResolver::resolve`]
+/// and [`JSPromiseResolver::reject`] consume it. If it's dropped without
+/// settling the promise, the promise is rejected with an `Error`, so that
+/// the scripts awaiting it don't hang.
+pub struct JSPromiseResolver {
+    resolve: Persistent<JSObject<'static>>,
+    reject: Persistent<JSObject<'static>>,
+    settled: bool,
+}
+
+/// A handle keeping a JavaScript value alive while Rust holds it.
+///
+/// Contrary to a [`JSValue`], a `Persistent` doesn't borrow its context: it
+/// retains the context and protects its value from garbage collection until
+/// it's dropped. Thus, it can be stored anywhere, e.g. in a struct, a `Vec`
+/// or a `HashMap`, without balancing [`JSValue::protect`] and
+/// [`JSValue::unprotect`] by hand. Cloning it protects the value again.
+///
+/// A `Persistent` holds any value, and a `Persistent<JSObject<'static>>`
+/// holds an object. It's created with [`JSValue::to_persistent`],
+/// [`JSObject::to_persistent`], or from a [`JSException`]:
+///
+/// ```
+/// # use javascriptcore::*;
+/// let persistent = {
+///     let ctx = JSContext::default();
+///     let value = evaluate_script(&ctx, "({ answer: 42 })", None, "test.js", 1).unwrap();
+///
+///     value.to_persistent()
+/// };
+///
+/// // The context is still alive.
+/// let answer = persistent.get().as_object().unwrap().get_property("answer");
+/// assert_eq!(answer.as_number().unwrap(), 42.);
+/// ```
+pub struct Persistent<T: Persistable = JSValue<'static>> {
+    ctx: sys::JSGlobalContextRef,
+    raw: sys::JSValueRef,
+    kind: PhantomData<T>,
 }
 
 /// A UTF16 character buffer.
@@ -129,9 +302,33 @@ pub struct JSString {
 /// buffer views. Learn more by [reading the documentation][doc].
 ///
 /// [doc]: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/TypedArray#behavior_when_viewing_a_resizable_buffer
-pub struct JSTypedArray {
+pub struct JSTypedArray<'ctx> {
     raw: sys::JSObjectRef,
     ctx: sys::JSContextRef,
+    context: PhantomData<&'ctx JSContext>,
+}
+
+/// A weak reference to a JavaScript object.
+///
+/// Contrary to a [`Persistent`], a `JSWeak` doesn't prevent its object from
+/// being garbage collected: [`JSWeak::upgrade`] returns `None` once it has
+/// been. It retains the context of the object though, like a `Persistent`.
+///
+/// This is built on the private API of JavaScriptCore, and requires the
+/// `weak` feature.
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let object = JSObject::new(&ctx).to_persistent();
+/// let weak = object.downgrade();
+///
+/// assert!(weak.upgrade().is_some());
+/// ```
+#[cfg(feature = "weak")]
+pub struct JSWeak {
+    ctx: sys::JSGlobalContextRef,
+    raw: sys::JSWeakRef,
 }
 
 /// A JavaScript value.
@@ -149,6 +346,7 @@ pub struct JSTypedArray {
 /// * [`JSValue::new_boolean()`]
 /// * [`JSValue::new_number()`]
 /// * [`JSValue::new_string()`]
+/// * [`JSValue::new_date()`]
 /// * [`JSValue::new_typed_array_with_bytes()`]
 /// * [`JSValue::new_function()`]
 /// * [`JSValue::new_from_json()`]
@@ -161,12 +359,59 @@ pub struct JSTypedArray {
 /// # Retrieving Rust values
 ///
 /// * [`JSValue::as_boolean()`]
+/// * [`JSValue::as_date()`]
 /// * [`JSValue::as_number()`]
 /// * [`JSValue::as_object()`]
 /// * [`JSValue::as_string()`]
 /// * [`JSValue::as_typed_array()`]
-#[derive(Debug)]
-pub struct JSValue {
+///
+/// # Lifetime
+///
+/// A value borrows the [`JSContext`] it has been created in, so that it
+/// cannot be used once the context has been dropped:
+///
+/// ```compile_fail
+/// # use javascriptcore::*;
+/// let value = {
+///     let ctx = JSContext::default();
+///     JSValue::new_number(&ctx, 42.)
+/// };
+/// ```
+///
+/// The compiler doesn't prevent passing a value to a context of another
+/// [`JSContextGroup`], whose heap doesn't know the value. This is checked
+/// when the value is used, e.g. set as a property, passed as an argument or
+/// returned by a function, which fails with a `TypeError`:
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let other_ctx = JSContext::default();
+///
+/// let value = JSValue::new_string(&other_ctx, "elsewhere");
+/// let exception = ctx.global_object().unwrap().set_property("value", value).unwrap_err();
+/// assert_eq!(exception.name().unwrap(), "TypeError");
+/// ```
+///
+/// # Cloning
+///
+/// A clone of a `JSValue` refers to the same JavaScript value: objects
+/// aren't copied, and a clone isn't protected from garbage collection any
+/// more than the original. Use a [`Persistent`] to keep a value alive.
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let object = evaluate_script(&ctx, "({ answer: 42 })", None, "test.js", 1).unwrap();
+/// let clone = object.clone();
+///
+/// clone.as_object().unwrap().set_property("answer", JSValue::new_number(&ctx, 43.)).unwrap();
+/// assert_eq!(object.as_object().unwrap().get_property("answer").as_number().unwrap(), 43.);
+/// assert_eq!(object, clone);
+/// ```
+#[derive(Clone, Debug)]
+pub struct JSValue<'ctx> {
     raw: sys::JSValueRef,
     ctx: sys::JSContextRef,
+    context: PhantomData<&'ctx JSContext>,
 }
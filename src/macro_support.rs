@@ -0,0 +1,389 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support code for the items generated by the `javascriptcore-macros`
+//! crate. Nothing in here is part of the public API.
+
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    ptr,
+    sync::OnceLock,
+};
+
+use crate::{
+    class::{self, StaticClass},
+    convert::{self, prefix_exception},
+    sys, FromJSValue, IntoJSException, JSClass, JSContext, JSException, JSObject, JSString,
+    JSValue, ToJSValue,
+};
+
+/// Checks that `value` is an object, and returns it.
+pub fn expect_object<'ctx>(value: &JSValue<'ctx>) -> Result<JSObject<'ctx>, JSException> {
+    convert::expect_object(value)
+}
+
+/// Checks that `value` is an array of exactly `length` elements, and
+/// returns it.
+pub fn expect_array_of_length<'ctx>(
+    value: &JSValue<'ctx>,
+    length: u32,
+) -> Result<JSObject<'ctx>, JSException> {
+    convert::expect_array_of_length(value, length)
+}
+
+/// Converts `value` and stores it in the property `key` of `object`.
+pub fn set_property<'ctx, T: ToJSValue<'ctx> + ?Sized>(
+    ctx: &'ctx JSContext,
+    object: &JSObject<'ctx>,
+    key: &str,
+    value: &T,
+) -> Result<(), JSException> {
+    value
+        .to_js_value(ctx)
+        .and_then(|value| object.set_property(key, value))
+        .map_err(|exception| prefix_exception(exception, &format!("property `{key}`")))
+}
+
+/// Reads and converts the property `key` of `object`.
+pub fn get_property<'ctx, T: FromJSValue<'ctx>>(
+    object: &JSObject<'ctx>,
+    key: &str,
+) -> Result<T, JSException> {
+    in_property(key, || T::from_js_value(&object.get_property(key)))
+}
+
+/// Reads and converts the property `key` of `object`, or returns `None` if
+/// the property is `undefined`.
+pub fn get_optional_property<'ctx, T: FromJSValue<'ctx>>(
+    object: &JSObject<'ctx>,
+    key: &str,
+) -> Result<Option<T>, JSException> {
+    let value = object.get_property(key);
+
+    if value.is_undefined() {
+        Ok(None)
+    } else {
+        in_property(key, || T::from_js_value(&value)).map(Some)
+    }
+}
+
+/// Reads and converts the element at `index` of `array`.
+pub fn get_element<'ctx, T: FromJSValue<'ctx>>(
+    array: &JSObject<'ctx>,
+    index: u32,
+) -> Result<T, JSException> {
+    T::from_js_value(&array.get_property_at_index(index))
+        .map_err(|exception| prefix_exception(exception, &format!("element {index}")))
+}
+
+/// Runs `conversion`, prefixing its error with the property `key`.
+pub fn in_property<T>(
+    key: &str,
+    conversion: impl FnOnce() -> Result<T, JSException>,
+) -> Result<T, JSException> {
+    conversion().map_err(|exception| prefix_exception(exception, &format!("property `{key}`")))
+}
+
+/// Runs `conversion`, discarding its error, as used by untagged enums to
+/// try each variant in turn.
+pub fn attempt<T>(conversion: impl FnOnce() -> Result<T, JSException>) -> Option<T> {
+    conversion().ok()
+}
+
+/// Returns the name and the value of the single property of `object`, as
+/// used by externally tagged enums.
+pub fn single_property<'ctx>(
+    object: &JSObject<'ctx>,
+) -> Result<(String, JSValue<'ctx>), JSException> {
+    let mut names = object.property_names();
+
+    match (names.next(), names.next()) {
+        (Some(name), None) => {
+            let name = name.to_string();
+            let value = object.get_property(name.as_str());
+
+            Ok((name, value))
+        }
+        _ => Err(type_error(
+            object,
+            "expected a string or an object with a single property",
+        )),
+    }
+}
+
+/// Builds a `TypeError` for a variant `name` that doesn't exist.
+pub fn unknown_variant(value: &JSValue, name: &str, expected: &[&str]) -> JSException {
+    let expected = expected
+        .iter()
+        .map(|variant| format!("`{variant}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    type_error(
+        value,
+        &format!("unknown variant `{name}`, expected one of {expected}"),
+    )
+}
+
+/// Builds a `TypeError` with the given `message`.
+pub fn type_error(value: &JSValue, message: &str) -> JSException {
+    JSException::new_builtin(value.ctx, "TypeError", message)
+}
+
+/// Checks that a function received between `required` and `maximum`
+/// arguments, `maximum` being `None` for functions with a rest parameter.
+pub fn check_arity(
+    ctx: &JSContext,
+    count: usize,
+    required: usize,
+    maximum: Option<usize>,
+) -> Result<(), JSException> {
+    let plural = |count: usize| if count == 1 { "argument" } else { "arguments" };
+
+    let expected = match maximum {
+        Some(maximum) if (required..=maximum).contains(&count) => return Ok(()),
+        None if count >= required => return Ok(()),
+        Some(maximum) if maximum == required => format!("{required} {}", plural(required)),
+        Some(maximum) => format!("between {required} and {maximum} arguments"),
+        None => format!("at least {required} {}", plural(required)),
+    };
+
+    Err(JSException::new_builtin(
+        ctx.raw,
+        "TypeError",
+        &format!("expected {expected}, got {count}"),
+    ))
+}
+
+/// Converts the argument at `index`, or `undefined` if it's missing.
+pub fn argument<'ctx, T: FromJSValue<'ctx>>(
+    ctx: &'ctx JSContext,
+    arguments: &[JSValue<'ctx>],
+    index: usize,
+) -> Result<T, JSException> {
+    let result = match arguments.get(index) {
+        Some(argument) => T::from_js_value(argument),
+        None => T::from_js_value(&JSValue::new_undefined(ctx)),
+    };
+
+    result.map_err(|exception| prefix_exception(exception, &format!("argument {index}")))
+}
+
+/// Returns the arguments from `index`.
+pub fn rest_arguments<'a, 'ctx>(
+    arguments: &'a [JSValue<'ctx>],
+    index: usize,
+) -> &'a [JSValue<'ctx>] {
+    arguments.get(index..).unwrap_or_default()
+}
+
+/// Converts the value returned by a function.
+pub fn return_value<'ctx, T: ToJSValue<'ctx>>(
+    ctx: &'ctx JSContext,
+    value: T,
+) -> Result<JSValue<'ctx>, JSException> {
+    value.to_js_value(ctx)
+}
+
+/// Converts the result returned by a function.
+pub fn return_result<'ctx, T: ToJSValue<'ctx>, E: IntoJSException>(
+    ctx: &'ctx JSContext,
+    result: Result<T, E>,
+) -> Result<JSValue<'ctx>, JSException> {
+    result
+        .map_err(|error| error.into_js_exception(ctx))
+        .and_then(|value| value.to_js_value(ctx))
+}
+
+/// Runs a callback, handling a panic according to
+/// [`crate::panic_strategy()`].
+pub fn catch_panic<T>(
+    ctx: &JSContext,
+    callback: impl FnOnce() -> Result<T, JSException>,
+) -> Result<T, JSException> {
+    crate::panic::catch_panic(ctx, callback)
+}
+
+/// Runs a callback where no exception can be thrown, e.g. a finalizer.
+pub fn catch_panic_without_exception(callback: impl FnOnce()) {
+    crate::panic::catch_panic_without_exception(callback)
+}
+
+/// Creates a [`JSString`] from a string borrowed by a callback.
+///
+/// # Safety
+///
+/// Ensure `raw` is valid.
+pub unsafe fn string_from_raw(raw: sys::JSStringRef) -> JSString {
+    JSString {
+        raw: sys::JSStringRetain(raw),
+    }
+}
+
+/// Converts the result of a callback returning an optional value, `None`
+/// meaning that the request must be forwarded, e.g. to the parent class.
+///
+/// # Safety
+///
+/// Ensure `exception` is valid, or null.
+pub unsafe fn return_optional_value(
+    result: Result<Option<JSValue<'_>>, JSException>,
+    exception: *mut sys::JSValueRef,
+) -> sys::JSValueRef {
+    match result {
+        Ok(value) => value.map_or(ptr::null(), |value| value.raw),
+        Err(error) => {
+            if !exception.is_null() {
+                *exception = error.into();
+            }
+
+            ptr::null()
+        }
+    }
+}
+
+/// Converts the result of a callback returning a boolean.
+///
+/// # Safety
+///
+/// Ensure `exception` is valid, or null.
+pub unsafe fn return_bool(
+    result: Result<bool, JSException>,
+    exception: *mut sys::JSValueRef,
+) -> bool {
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            if !exception.is_null() {
+                *exception = error.into();
+            }
+
+            false
+        }
+    }
+}
+
+/// Builds an exception holding a new builtin error, e.g. a `TypeError`.
+pub fn builtin_error(ctx: &JSContext, name: &str, message: &str) -> JSException {
+    JSException::new_builtin(ctx.raw, name, message)
+}
+
+/// Converts the error of `result`, as returned by a constructor.
+pub fn into_result<T, E: IntoJSException>(
+    ctx: &JSContext,
+    result: Result<T, E>,
+) -> Result<T, JSException> {
+    result.map_err(|error| error.into_js_exception(ctx))
+}
+
+/// A class created once for the whole process, as used by `#[js_class]`.
+pub struct ClassCell(OnceLock<StaticClass>);
+
+impl ClassCell {
+    /// Creates an empty cell.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    /// Returns the class of the objects holding private data, see
+    /// [`JSClass::new_object_with_data`], building it with `build` the
+    /// first time.
+    pub fn get_or_try_init<'ctx>(
+        &self,
+        build: impl FnOnce() -> Result<JSClass<'ctx>, JSException>,
+    ) -> Result<sys::JSClassRef, JSException> {
+        if let Some(class) = self.0.get() {
+            return Ok(class.0);
+        }
+
+        let class = build()?;
+        // The class outlives `JSClass`, which releases it on drop. It
+        // retains its parent class.
+        let raw = unsafe { sys::JSClassRetain(class.data_class) };
+
+        // Another thread may have been faster.
+        if let Err(StaticClass(raw)) = self.0.set(StaticClass(raw)) {
+            unsafe { sys::JSClassRelease(raw) };
+        }
+
+        Ok(self.0.get().map_or(raw, |class| class.0))
+    }
+}
+
+/// Creates an object of `class`, holding `data`.
+///
+/// # Safety
+///
+/// Ensure `class` is valid, and has been returned by [`ClassCell`].
+pub unsafe fn new_object_with_data<T: 'static>(
+    ctx: &JSContext,
+    class: sys::JSClassRef,
+    data: T,
+) -> JSObject<'_> {
+    class::new_object_with_data(ctx.raw, class, data)
+}
+
+/// Creates a constructor of the objects of `class`.
+///
+/// # Safety
+///
+/// Ensure `class` is valid.
+pub unsafe fn new_constructor(
+    ctx: &JSContext,
+    class: sys::JSClassRef,
+    constructor: sys::JSObjectCallAsConstructorCallback,
+) -> JSObject<'_> {
+    JSObject::from_raw(
+        ctx.raw,
+        sys::JSObjectMakeConstructor(ctx.raw, class, constructor),
+    )
+}
+
+/// Returns the data of `this`, which must be an instance of the class
+/// `class_name` holding a `RefCell<T>`.
+pub fn this_data<'a, T: 'static>(
+    ctx: &JSContext,
+    this: Option<&'a JSObject<'_>>,
+    class_name: &str,
+) -> Result<&'a RefCell<T>, JSException> {
+    this.and_then(|this| this.private_data::<RefCell<T>>())
+        .ok_or_else(|| {
+            builtin_error(
+                ctx,
+                "TypeError",
+                &format!("`this` is not an instance of `{class_name}`"),
+            )
+        })
+}
+
+/// Borrows the data of an instance of the class `class_name`.
+pub fn borrow<'a, T>(
+    ctx: &JSContext,
+    data: &'a RefCell<T>,
+    class_name: &str,
+) -> Result<Ref<'a, T>, JSException> {
+    data.try_borrow()
+        .map_err(|_| already_borrowed(ctx, class_name))
+}
+
+/// Mutably borrows the data of an instance of the class `class_name`.
+pub fn borrow_mut<'a, T>(
+    ctx: &JSContext,
+    data: &'a RefCell<T>,
+    class_name: &str,
+) -> Result<RefMut<'a, T>, JSException> {
+    data.try_borrow_mut()
+        .map_err(|_| already_borrowed(ctx, class_name))
+}
+
+fn already_borrowed(ctx: &JSContext, class_name: &str) -> JSException {
+    builtin_error(
+        ctx,
+        "Error",
+        &format!("this instance of `{class_name}` is already in use"),
+    )
+}
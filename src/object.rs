@@ -4,11 +4,12 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::{sys, JSException, JSObject, JSString, JSValue};
+use crate::{class, sys, JSContext, JSException, JSObject, JSString, JSValue, Persistent};
+use std::cell::{RefCell, RefMut};
 use std::ops::Deref;
 use std::ptr;
 
-impl JSObject {
+impl<'ctx> JSObject<'ctx> {
     /// Create a new [`Self`] from its raw pointer directly.
     ///
     /// # Safety
@@ -21,6 +22,61 @@ impl JSObject {
         }
     }
 
+    /// Creates an empty JavaScript object, as if by evaluating `{}`.
+    ///
+    /// * `ctx`: The execution context to use.
+    ///
+    /// ```
+    /// # use javascriptcore::{JSContext, JSObject, JSValue};
+    /// let ctx = JSContext::default();
+    /// let object = JSObject::new(&ctx);
+    /// object.set_property("id", JSValue::new_number(&ctx, 123.)).unwrap();
+    ///
+    /// assert!(object.has_property("id"));
+    /// ```
+    pub fn new(ctx: &'ctx JSContext) -> Self {
+        unsafe {
+            Self::from_raw(
+                ctx.raw,
+                sys::JSObjectMake(ctx.raw, ptr::null_mut(), ptr::null_mut()),
+            )
+        }
+    }
+
+    /// Creates a JavaScript `Error` object, as if by invoking
+    /// `new Error(message)`.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `message`: A value that can be converted into a [`JSString`] to use
+    ///   as the `message` of the error.
+    ///
+    /// ```
+    /// # use javascriptcore::{JSContext, JSObject};
+    /// let ctx = JSContext::default();
+    /// let error = JSObject::new_error(&ctx, "oops").unwrap();
+    ///
+    /// assert_eq!(error.get_property("name").as_string().unwrap(), "Error");
+    /// assert_eq!(error.get_property("message").as_string().unwrap(), "oops");
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSException::new_error()`]
+    pub fn new_error<S: Into<JSString>>(
+        ctx: &'ctx JSContext,
+        message: S,
+    ) -> Result<Self, JSException> {
+        let message = JSValue::new_string(ctx, message);
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let result = unsafe { sys::JSObjectMakeError(ctx.raw, 1, &message.raw, &mut exception) };
+
+        if result.is_null() {
+            Err(unsafe { JSValue::from_raw(ctx.raw, exception) }.into())
+        } else {
+            Ok(unsafe { Self::from_raw(ctx.raw, result) })
+        }
+    }
+
     /// Gets an iterator over the names of an object's enumerable properties.
     ///
     /// ```
@@ -90,7 +146,7 @@ impl JSObject {
     /// * [`JSObject::has_property()`]
     /// * [`JSObject::set_property()`]
     /// * [`JSObject::set_property_at_index()`]
-    pub fn get_property<S>(&self, name: S) -> JSValue
+    pub fn get_property<S>(&self, name: S) -> JSValue<'ctx>
     where
         S: Into<JSString>,
     {
@@ -149,7 +205,7 @@ impl JSObject {
     /// * [`JSObject::has_property()`]
     /// * [`JSObject::set_property()`]
     /// * [`JSObject::set_property_at_index()`]
-    pub fn get_property_at_index(&self, index: u32) -> JSValue {
+    pub fn get_property_at_index(&self, index: u32) -> JSValue<'ctx> {
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let value = unsafe {
             sys::JSObjectGetPropertyAtIndex(self.value.ctx, self.raw, index, &mut exception)
@@ -187,13 +243,14 @@ impl JSObject {
     /// * [`JSObject::get_property_at_index()`]
     /// * [`JSObject::has_property()`]
     /// * [`JSObject::set_property_at_index()`]
-    pub fn set_property<S>(&self, name: S, value: JSValue) -> Result<(), JSException>
+    pub fn set_property<S>(&self, name: S, value: JSValue<'ctx>) -> Result<(), JSException>
     where
         S: Into<JSString>,
     {
         let name: JSString = name.into();
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let context = self.value.ctx;
+        value.check_context(context)?;
 
         unsafe {
             sys::JSObjectSetProperty(
@@ -240,9 +297,14 @@ impl JSObject {
     /// * [`JSObject::get_property_at_index()`]
     /// * [`JSObject::has_property()`]
     /// * [`JSObject::set_property()`]
-    pub fn set_property_at_index(&self, index: u32, value: JSValue) -> Result<(), JSException> {
+    pub fn set_property_at_index(
+        &self,
+        index: u32,
+        value: JSValue<'ctx>,
+    ) -> Result<(), JSException> {
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let context = self.value.ctx;
+        value.check_context(context)?;
 
         unsafe {
             sys::JSObjectSetPropertyAtIndex(context, self.raw, index, value.raw, &mut exception);
@@ -295,13 +357,13 @@ impl JSObject {
     ///
     /// - [`JSObject::call_as_function()`]
     /// - [`JSObject::is_constructor()`]
-    pub fn call_as_constructor(&self, arguments: &[JSValue]) -> Result<JSValue, JSException> {
-        let arguments = arguments
-            .iter()
-            .map(|argument| argument.raw)
-            .collect::<Vec<_>>();
-        let mut exception: sys::JSValueRef = ptr::null_mut();
+    pub fn call_as_constructor(
+        &self,
+        arguments: &[JSValue<'ctx>],
+    ) -> Result<JSValue<'ctx>, JSException> {
         let context = self.value.ctx;
+        let arguments = raw_arguments(context, arguments)?;
+        let mut exception: sys::JSValueRef = ptr::null_mut();
 
         let result = unsafe {
             sys::JSObjectCallAsConstructor(
@@ -318,11 +380,11 @@ impl JSObject {
         }
 
         if result.is_null() {
-            return Err(JSValue::new_string_inner(
+            return Err(JSException::new_builtin(
                 context,
+                "TypeError",
                 "Cannot call this object as a constructor: it is not a valid constructor",
-            )
-            .into());
+            ));
         }
 
         Ok(unsafe { JSValue::from_raw(context, result) })
@@ -376,15 +438,16 @@ impl JSObject {
     /// - [`JSObject::is_function()`]
     pub fn call_as_function(
         &self,
-        this: Option<&JSObject>,
-        arguments: &[JSValue],
-    ) -> Result<JSValue, JSException> {
-        let arguments = arguments
-            .iter()
-            .map(|argument| argument.raw)
-            .collect::<Vec<_>>();
-        let mut exception: sys::JSValueRef = ptr::null_mut();
+        this: Option<&JSObject<'ctx>>,
+        arguments: &[JSValue<'ctx>],
+    ) -> Result<JSValue<'ctx>, JSException> {
         let context = self.value.ctx;
+        let arguments = raw_arguments(context, arguments)?;
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+
+        if let Some(this) = this {
+            this.check_context(context)?;
+        }
 
         let result = unsafe {
             sys::JSObjectCallAsFunction(
@@ -402,38 +465,110 @@ impl JSObject {
         }
 
         if result.is_null() {
-            return Err(JSValue::new_string_inner(
+            return Err(JSException::new_builtin(
                 context,
+                "TypeError",
                 "Cannot call this object as a function: it is not a valid function",
-            )
-            .into());
+            ));
         }
 
         Ok(unsafe { JSValue::from_raw(context, result) })
     }
+
+    /// Returns the data held by this object, if it has been created by
+    /// [`JSClass::new_object_with_data`](crate::JSClass::new_object_with_data) with a value of type `T`.
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let class = JSClass::builder(&ctx, "Parser").unwrap().build().unwrap();
+    /// let object = class.new_object_with_data(String::from("source"));
+    ///
+    /// assert_eq!(object.private_data::<String>().unwrap(), "source");
+    /// assert!(object.private_data::<u32>().is_none());
+    /// assert!(JSObject::new(&ctx).private_data::<String>().is_none());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSClass::new_object_with_data()`](crate::JSClass::new_object_with_data)
+    /// - [`JSObject::private_data_mut()`]
+    pub fn private_data<T: 'static>(&self) -> Option<&T> {
+        unsafe { class::private_data(self.raw) }?.downcast_ref()
+    }
+
+    /// Mutably borrows the data held by this object, if it has been created
+    /// by [`JSClass::new_object_with_data`](crate::JSClass::new_object_with_data) with a value of type
+    /// [`RefCell<T>`].
+    ///
+    /// Returns `None` if the data isn't a `RefCell<T>`, or if it's already
+    /// borrowed.
+    ///
+    /// ```rust
+    /// # use std::cell::RefCell;
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let class = JSClass::builder(&ctx, "Counter").unwrap().build().unwrap();
+    /// let object = class.new_object_with_data(RefCell::new(0u32));
+    ///
+    /// *object.private_data_mut::<u32>().unwrap() += 1;
+    ///
+    /// assert_eq!(*object.private_data::<RefCell<u32>>().unwrap().borrow(), 1);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSClass::new_object_with_data()`](crate::JSClass::new_object_with_data)
+    /// - [`JSObject::private_data()`]
+    pub fn private_data_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        self.private_data::<RefCell<T>>()?.try_borrow_mut().ok()
+    }
+
+    /// Returns a handle protecting this object from garbage collection
+    /// until it's dropped, and which doesn't borrow the context.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// struct Listener {
+    ///     callback: Persistent<JSObject<'static>>,
+    /// }
+    ///
+    /// let ctx = JSContext::default();
+    /// let listener = Listener {
+    ///     callback: JSObject::new(&ctx).to_persistent(),
+    /// };
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::to_persistent()`]
+    /// - [`Persistent`]
+    pub fn to_persistent(&self) -> Persistent<JSObject<'static>> {
+        unsafe { Persistent::from_raw(self.value.ctx, self.value.raw) }
+    }
 }
 
 /// A `JSObject` can be dereferenced to return the underlying `JSValue`.
 ///
 /// This lets a `JSObject` instance be used where a `JSValue` instance is
 /// expected.
-impl Deref for JSObject {
-    type Target = JSValue;
+impl<'ctx> Deref for JSObject<'ctx> {
+    type Target = JSValue<'ctx>;
 
-    fn deref(&self) -> &JSValue {
+    fn deref(&self) -> &JSValue<'ctx> {
         &self.value
     }
 }
 
-impl From<&JSObject> for JSValue {
-    fn from(object: &JSObject) -> Self {
+impl<'ctx> From<&JSObject<'ctx>> for JSValue<'ctx> {
+    fn from(object: &JSObject<'ctx>) -> Self {
         // SAFETY: `ctx` and `raw` is valid, it's safe to use them.
         unsafe { JSValue::from_raw(object.value.ctx, object.value.raw) }
     }
 }
 
-impl From<JSObject> for JSValue {
-    fn from(object: JSObject) -> Self {
+impl<'ctx> From<JSObject<'ctx>> for JSValue<'ctx> {
+    fn from(object: JSObject<'ctx>) -> Self {
         (&object).into()
     }
 }
@@ -473,9 +608,26 @@ impl Drop for JSObjectPropertyNameIter {
     }
 }
 
+/// Returns the raw values of `arguments`, if they can be used in `ctx`.
+fn raw_arguments(
+    ctx: sys::JSContextRef,
+    arguments: &[JSValue<'_>],
+) -> Result<Vec<sys::JSValueRef>, JSException> {
+    arguments
+        .iter()
+        .map(|argument| argument.check_context(ctx).map(|()| argument.raw))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{JSContext, JSException, JSValue};
+    use std::{
+        cell::{Cell, RefCell},
+        ptr,
+        rc::Rc,
+    };
+
+    use crate::{garbage_collect, sys, JSClass, JSContext, JSException, JSObject, JSValue};
 
     #[test]
     fn can_has_property() {
@@ -599,4 +751,97 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn can_get_private_data() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+        let class = JSClass::builder(&ctx, "Foo")?.build()?;
+
+        let object = class.new_object_with_data(42u32);
+        assert_eq!(object.private_data::<u32>(), Some(&42));
+        assert!(object.private_data::<i32>().is_none());
+
+        // Objects without data.
+        assert!(class.new_object().private_data::<u32>().is_none());
+        assert!(JSObject::new(&ctx).private_data::<u32>().is_none());
+
+        // Objects of a class inheriting from another one.
+        let derived_class = JSClass::builder(&ctx, "Bar")?
+            .parent_class(&class)
+            .build()?;
+        let object = derived_class.new_object_with_data(String::from("bar"));
+        assert_eq!(object.private_data::<String>().unwrap(), "bar");
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_mutate_private_data() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+        let class = JSClass::builder(&ctx, "Foo")?.build()?;
+
+        let object = class.new_object_with_data(RefCell::new(vec![1, 2]));
+        object.private_data_mut::<Vec<i32>>().unwrap().push(3);
+
+        {
+            let _borrowed = object.private_data::<RefCell<Vec<i32>>>().unwrap().borrow();
+
+            // Already borrowed.
+            assert!(object.private_data_mut::<Vec<i32>>().is_none());
+        }
+
+        assert_eq!(*object.private_data_mut::<Vec<i32>>().unwrap(), [1, 2, 3]);
+
+        // The data isn't a `RefCell`.
+        let object = class.new_object_with_data(1u8);
+        assert!(object.private_data_mut::<u8>().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn private_data_is_dropped() -> Result<(), JSException> {
+        struct Guard(Rc<Cell<bool>>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(false));
+
+        {
+            let ctx = JSContext::default();
+            let class = JSClass::builder(&ctx, "Foo")?.build()?;
+            let _object = class.new_object_with_data(Guard(dropped.clone()));
+
+            assert!(!dropped.get());
+            garbage_collect(&ctx);
+        }
+
+        // Releasing the context finalizes its objects.
+        assert!(dropped.get());
+
+        Ok(())
+    }
+
+    #[test]
+    fn private_pointers_set_by_hand_are_left_alone() -> Result<(), JSException> {
+        static PRIVATE: u32 = 42;
+
+        {
+            let ctx = JSContext::default();
+            let class = JSClass::builder(&ctx, "Foo")?.build()?;
+            let object = class.new_object();
+
+            assert!(unsafe {
+                sys::JSObjectSetPrivate(object.raw, ptr::from_ref(&PRIVATE).cast_mut().cast())
+            });
+            assert!(object.private_data::<u32>().is_none());
+        }
+
+        // Releasing the context doesn't free the pointer.
+        Ok(())
+    }
 }
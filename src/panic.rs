@@ -0,0 +1,186 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    process,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{JSContext, JSException};
+
+/// What to do when Rust code called from JavaScript panics.
+///
+/// A panic must never unwind across the FFI boundary into JavaScriptCore.
+/// The callbacks generated by [`crate::function_callback`],
+/// [`crate::constructor_callback`] and [`crate::js_function`] catch
+/// panics, and handle them according to the strategy set with
+/// [`set_panic_strategy`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PanicStrategy {
+    /// Convert the panic into a JavaScript `Error` carrying the panic
+    /// message, and throw it. This is the default.
+    #[default]
+    ThrowException,
+    /// Print the panic message to the standard error, and abort the
+    /// process.
+    Abort,
+}
+
+static PANIC_STRATEGY: AtomicU8 = AtomicU8::new(PanicStrategy::ThrowException as u8);
+
+/// Sets how panics in Rust callbacks are handled, for the whole process.
+///
+/// ```
+/// # use javascriptcore::{panic_strategy, set_panic_strategy, PanicStrategy};
+/// set_panic_strategy(PanicStrategy::Abort);
+/// assert_eq!(panic_strategy(), PanicStrategy::Abort);
+/// ```
+///
+/// # See also
+///
+/// - [`panic_strategy()`]
+pub fn set_panic_strategy(strategy: PanicStrategy) {
+    PANIC_STRATEGY.store(strategy as u8, Ordering::Relaxed);
+}
+
+/// Returns how panics in Rust callbacks are handled.
+///
+/// # See also
+///
+/// - [`set_panic_strategy()`]
+pub fn panic_strategy() -> PanicStrategy {
+    match PANIC_STRATEGY.load(Ordering::Relaxed) {
+        strategy if strategy == PanicStrategy::Abort as u8 => PanicStrategy::Abort,
+        _ => PanicStrategy::ThrowException,
+    }
+}
+
+/// Extracts the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Runs `callback`, handling a panic according to [`panic_strategy()`].
+pub(crate) fn catch_panic<T>(
+    ctx: &JSContext,
+    callback: impl FnOnce() -> Result<T, JSException>,
+) -> Result<T, JSException> {
+    panic::catch_unwind(AssertUnwindSafe(callback)).unwrap_or_else(|payload| {
+        let message = panic_message(&*payload);
+
+        match panic_strategy() {
+            PanicStrategy::ThrowException => {
+                Err(JSException::new_builtin(ctx.raw, "Error", message))
+            }
+            PanicStrategy::Abort => {
+                eprintln!("a Rust callback called from JavaScript panicked: {message}");
+
+                process::abort()
+            }
+        }
+    })
+}
+
+/// Runs `callback` where no exception can be thrown, e.g. in a finalizer.
+///
+/// With [`PanicStrategy::ThrowException`], the panic is swallowed after
+/// having been reported by the panic hook.
+pub(crate) fn catch_panic_without_exception(callback: impl FnOnce()) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(callback)) {
+        if panic_strategy() == PanicStrategy::Abort {
+            eprintln!(
+                "a Rust callback called from JavaScript panicked: {}",
+                panic_message(&*payload)
+            );
+
+            process::abort()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{panic_strategy, set_panic_strategy, PanicStrategy};
+    use crate as javascriptcore;
+    use crate::{evaluate_script, function_callback, js_function, JSContext, JSException, JSValue};
+
+    #[test]
+    fn strategy() {
+        // Don't switch to `Abort` here: tests run concurrently, and would
+        // abort on the first panic.
+        assert_eq!(PanicStrategy::default(), PanicStrategy::ThrowException);
+        assert_eq!(panic_strategy(), PanicStrategy::ThrowException);
+
+        set_panic_strategy(PanicStrategy::ThrowException);
+        assert_eq!(panic_strategy(), PanicStrategy::ThrowException);
+    }
+
+    #[test]
+    fn panics_are_thrown() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        #[function_callback]
+        fn explode(
+            _ctx: &JSContext,
+            _function: Option<&JSObject>,
+            _this_object: Option<&JSObject>,
+            _arguments: &[JSValue],
+        ) -> Result<JSValue, JSException> {
+            panic!("boom");
+        }
+
+        #[js_function]
+        fn divide(a: u32, b: u32) -> u32 {
+            a.checked_div(b).expect("division by zero")
+        }
+
+        let global_object = ctx.global_object()?;
+        global_object.set_property(
+            "explode",
+            JSValue::new_function(&ctx, "explode", Some(explode)),
+        )?;
+        global_object.set_property(
+            "divide",
+            JSValue::new_function(&ctx, "divide", Some(divide)),
+        )?;
+
+        let error = evaluate_script(
+            &ctx,
+            "try { explode() } catch (e) { e instanceof Error ? e.message : 'not an error' }",
+            None,
+            "test.js",
+            1,
+        )?;
+        assert_eq!(error.as_string()?, "boom");
+
+        let error = evaluate_script(&ctx, "divide(1, 0)", None, "test.js", 1).unwrap_err();
+        assert_eq!(error.name()?, "Error");
+        assert_eq!(
+            error
+                .underlying_value()
+                .as_object()?
+                .get_property("message")
+                .as_string()?,
+            "division by zero"
+        );
+
+        // The context is still usable.
+        assert_eq!(
+            evaluate_script(&ctx, "divide(6, 3)", None, "test.js", 1)?.as_number()?,
+            2.
+        );
+
+        Ok(())
+    }
+}
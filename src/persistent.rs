@@ -0,0 +1,211 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{fmt, marker::PhantomData, mem::ManuallyDrop};
+
+use crate::{sys, JSException, JSObject, JSValue, Persistent};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The types of the values a [`Persistent`] can hold: [`JSValue`] and
+/// [`JSObject`].
+///
+/// The trait is implemented for their `'static` version only, by
+/// convention: a value held by a [`Persistent`] borrows the handle, not its
+/// context.
+pub trait Persistable: private::Sealed {
+    /// The type of the value borrowed from a [`Persistent`].
+    type Borrowed<'a>;
+
+    /// Wraps a raw value.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `ctx` and `raw` are valid, and outlive `'a`.
+    #[doc(hidden)]
+    unsafe fn borrow<'a>(ctx: sys::JSContextRef, raw: sys::JSValueRef) -> Self::Borrowed<'a>;
+}
+
+impl private::Sealed for JSValue<'static> {}
+
+impl Persistable for JSValue<'static> {
+    type Borrowed<'a> = JSValue<'a>;
+
+    unsafe fn borrow<'a>(ctx: sys::JSContextRef, raw: sys::JSValueRef) -> JSValue<'a> {
+        JSValue::from_raw(ctx, raw)
+    }
+}
+
+impl private::Sealed for JSObject<'static> {}
+
+impl Persistable for JSObject<'static> {
+    type Borrowed<'a> = JSObject<'a>;
+
+    unsafe fn borrow<'a>(ctx: sys::JSContextRef, raw: sys::JSValueRef) -> JSObject<'a> {
+        JSObject::from_raw(ctx, raw as sys::JSObjectRef)
+    }
+}
+
+impl<T: Persistable> Persistent<T> {
+    /// Protects `raw`, and retains the global context of `ctx`.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `ctx` and `raw` are valid, and that `raw` is a `T`.
+    pub(crate) unsafe fn from_raw(ctx: sys::JSContextRef, raw: sys::JSValueRef) -> Self {
+        let ctx = sys::JSGlobalContextRetain(sys::JSContextGetGlobalContext(ctx));
+        sys::JSValueProtect(ctx, raw);
+
+        Self {
+            ctx,
+            raw,
+            kind: PhantomData,
+        }
+    }
+
+    /// Returns the value, which cannot outlive this handle.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let persistent = JSValue::new_number(&ctx, 42.).to_persistent();
+    ///
+    /// assert_eq!(persistent.get().as_number().unwrap(), 42.);
+    /// ```
+    pub fn get(&self) -> T::Borrowed<'_> {
+        unsafe { T::borrow(self.ctx, self.raw) }
+    }
+}
+
+impl<T: Persistable> Clone for Persistent<T> {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_raw(self.ctx, self.raw) }
+    }
+}
+
+impl<T: Persistable> fmt::Debug for Persistent<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Persistent")
+            .field("ctx", &self.ctx)
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
+
+impl From<JSException> for Persistent {
+    /// Keeps the value of the exception, e.g. to rethrow it later.
+    fn from(mut exception: JSException) -> Self {
+        // Only the value is kept: the diagnostic, if any, is dropped.
+        drop(exception.diagnostic.take());
+
+        // The exception already retains the context and protects the value.
+        let exception = ManuallyDrop::new(exception);
+
+        Self {
+            ctx: exception.ctx,
+            raw: exception.value,
+            kind: PhantomData,
+        }
+    }
+}
+
+impl From<Persistent<JSObject<'static>>> for Persistent {
+    fn from(object: Persistent<JSObject<'static>>) -> Self {
+        let object = ManuallyDrop::new(object);
+
+        Self {
+            ctx: object.ctx,
+            raw: object.raw,
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<T: Persistable> Drop for Persistent<T> {
+    fn drop(&mut self) {
+        unsafe {
+            sys::JSValueUnprotect(self.ctx, self.raw);
+            sys::JSGlobalContextRelease(self.ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{evaluate_script, garbage_collect, JSContext, JSObject, JSValue, Persistent};
+
+    #[test]
+    fn persistent_value_survives_garbage_collection() {
+        let ctx = JSContext::default();
+
+        let persistent = {
+            let object = JSObject::new(&ctx);
+            object
+                .set_property("answer", JSValue::new_number(&ctx, 42.))
+                .unwrap();
+
+            object.to_persistent()
+        };
+
+        garbage_collect(&ctx);
+
+        let answer = persistent.get().get_property("answer");
+        assert_eq!(answer.as_number().unwrap(), 42.);
+    }
+
+    #[test]
+    fn persistent_value_outlives_context() {
+        let persistent = {
+            let ctx = JSContext::default();
+
+            JSValue::new_string(&ctx, "hello").to_persistent()
+        };
+
+        assert_eq!(persistent.get().as_string().unwrap(), "hello");
+    }
+
+    #[test]
+    fn persistent_values_can_be_stored() {
+        let ctx = JSContext::default();
+
+        let values = (0..3)
+            .map(|n| JSValue::new_number(&ctx, f64::from(n)).to_persistent())
+            .collect::<Vec<Persistent>>();
+        let mut names = HashMap::new();
+        names.insert("first", values[0].clone());
+
+        drop(values);
+        garbage_collect(&ctx);
+
+        assert_eq!(names["first"].get().as_number().unwrap(), 0.);
+    }
+
+    #[test]
+    fn persistent_from_exception() {
+        let ctx = JSContext::default();
+
+        let exception = evaluate_script(&ctx, "throw 'oops'", None, "test.js", 1).unwrap_err();
+        let persistent: Persistent = exception.into();
+
+        assert_eq!(persistent.get().as_string().unwrap(), "oops");
+    }
+
+    #[test]
+    fn persistent_object_into_value() {
+        let ctx = JSContext::default();
+
+        let object = JSObject::new(&ctx).to_persistent();
+        let value: Persistent = object.clone().into();
+
+        assert!(value.get().is_object());
+        assert_eq!(value.get(), *object.get());
+    }
+}
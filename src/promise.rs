@@ -0,0 +1,435 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    cell::RefCell,
+    fmt,
+    future::{self, Future},
+    ops::Deref,
+    ptr,
+    rc::Rc,
+    slice,
+    task::{Poll, Waker},
+};
+
+use crate::{
+    sys, JSContext, JSException, JSObject, JSPromise, JSPromiseResolver, JSValue, Persistent,
+};
+
+/// The message of the error rejecting the promises of dropped resolvers.
+const DROPPED_MESSAGE: &str = "the promise has been dropped without being settled";
+
+impl<'ctx> JSPromise<'ctx> {
+    /// Creates a pending promise, and the resolver settling it.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// #[function_callback]
+    /// fn fetch_answer(
+    ///     ctx: &JSContext,
+    ///     _function: Option<&JSObject>,
+    ///     _this_object: Option<&JSObject>,
+    ///     _arguments: &[JSValue],
+    /// ) -> Result<JSValue, JSException> {
+    ///     let (promise, resolver) = JSPromise::new(ctx)?;
+    ///
+    ///     // The resolver can be stored, and used once the answer is known.
+    ///     resolver.resolve(&JSValue::new_number(ctx, 42.))?;
+    ///
+    ///     Ok(promise.into())
+    /// }
+    ///
+    /// let ctx = JSContext::default();
+    /// let fetch_answer = JSValue::new_function(&ctx, "fetchAnswer", Some(fetch_answer));
+    /// ctx.global_object().unwrap().set_property("fetchAnswer", fetch_answer).unwrap();
+    ///
+    /// let promise = evaluate_script(&ctx, "fetchAnswer()", None, "test.js", 1).unwrap();
+    /// assert!(promise.as_object().unwrap().get_property("then").is_object());
+    /// ```
+    pub fn new(ctx: &'ctx JSContext) -> Result<(Self, JSPromiseResolver), JSException> {
+        let mut resolve: sys::JSObjectRef = ptr::null_mut();
+        let mut reject: sys::JSObjectRef = ptr::null_mut();
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+
+        let promise = unsafe {
+            sys::JSObjectMakeDeferredPromise(ctx.raw, &mut resolve, &mut reject, &mut exception)
+        };
+
+        if !exception.is_null() || promise.is_null() {
+            return Err(unsafe { JSValue::from_raw(ctx.raw, exception) }.into());
+        }
+
+        unsafe {
+            Ok((
+                Self {
+                    object: JSObject::from_raw(ctx.raw, promise),
+                },
+                JSPromiseResolver {
+                    resolve: Persistent::from_raw(ctx.raw, resolve),
+                    reject: Persistent::from_raw(ctx.raw, reject),
+                    settled: false,
+                },
+            ))
+        }
+    }
+}
+
+impl<'ctx> Deref for JSPromise<'ctx> {
+    type Target = JSObject<'ctx>;
+
+    fn deref(&self) -> &JSObject<'ctx> {
+        &self.object
+    }
+}
+
+impl<'ctx> From<JSPromise<'ctx>> for JSObject<'ctx> {
+    fn from(promise: JSPromise<'ctx>) -> Self {
+        promise.object
+    }
+}
+
+impl<'ctx> From<JSPromise<'ctx>> for JSValue<'ctx> {
+    fn from(promise: JSPromise<'ctx>) -> Self {
+        promise.object.into()
+    }
+}
+
+impl JSPromiseResolver {
+    /// Fulfills the promise with `value`, or follows it if it's a promise
+    /// itself.
+    ///
+    /// The value must belong to the context of the promise, or to a context
+    /// of the same [`JSContextGroup`](crate::JSContextGroup).
+    ///
+    /// # See also
+    ///
+    /// - [`JSPromiseResolver::reject()`]
+    pub fn resolve(mut self, value: &JSValue<'_>) -> Result<(), JSException> {
+        self.settled = true;
+        settle(&self.resolve, value)
+    }
+
+    /// Rejects the promise with `reason`, usually an `Error`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+    /// ctx.global_object().unwrap().set_property("promise", promise.into()).unwrap();
+    /// evaluate_script(&ctx, "promise.catch(error => reason = error.message)", None, "test.js", 1)
+    ///     .unwrap();
+    ///
+    /// let error = JSException::new_range_error(&ctx, "too far");
+    /// resolver.reject(&error.underlying_value()).unwrap();
+    ///
+    /// let reason = evaluate_script(&ctx, "reason", None, "test.js", 1).unwrap();
+    /// assert_eq!(reason.as_string().unwrap(), "too far");
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSPromiseResolver::resolve()`]
+    pub fn reject(mut self, reason: &JSValue<'_>) -> Result<(), JSException> {
+        self.settled = true;
+        settle(&self.reject, reason)
+    }
+}
+
+/// The settlement of a promise awaited by [`JSValue::into_future`].
+#[derive(Default)]
+struct Settlement {
+    result: Option<Result<Persistent, JSException>>,
+    waker: Option<Waker>,
+}
+
+impl<'ctx> JSValue<'ctx> {
+    /// Returns a future resolving to the value of this promise once it's
+    /// fulfilled, or to its reason as an exception if it's rejected.
+    ///
+    /// Like `await`, a value which isn't a promise resolves the future
+    /// right away, and any thenable is followed. The future is woken by the
+    /// microtasks settling the promise, which are run by JavaScriptCore
+    /// when a call into the context returns, or by
+    /// [`JSContext::run_microtasks`]. [`JSContext::poll_future`] drives the
+    /// future and the microtasks together:
+    ///
+    /// ```
+    /// # use std::{pin::pin, task::Poll};
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+    /// let mut future = pin!(JSValue::from(promise).into_future());
+    /// assert!(ctx.poll_future(future.as_mut()).is_pending());
+    ///
+    /// resolver.resolve(&JSValue::new_number(&ctx, 42.)).unwrap();
+    ///
+    /// let Poll::Ready(answer) = ctx.poll_future(future) else {
+    ///     panic!("the promise is pending");
+    /// };
+    /// assert_eq!(answer.unwrap().as_number().unwrap(), 42.);
+    /// ```
+    pub fn into_future(self) -> impl Future<Output = Result<JSValue<'ctx>, JSException>> + 'ctx {
+        let ctx = self.ctx;
+        let settlement = Rc::new(RefCell::new(Settlement::default()));
+
+        if let Err(exception) = self.then(&settlement) {
+            settlement.borrow_mut().result = Some(Err(exception));
+        }
+
+        future::poll_fn(move |context| {
+            let mut settlement = settlement.borrow_mut();
+
+            match settlement.result.take() {
+                Some(result) => Poll::Ready(
+                    result.map(|value| unsafe { JSValue::from_raw(ctx, value.get().raw) }),
+                ),
+                None => {
+                    settlement.waker = Some(context.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Calls `Promise.resolve(self).then()` with handlers recording the
+    /// settlement.
+    fn then(&self, settlement: &Rc<RefCell<Settlement>>) -> Result<(), JSException> {
+        let ctx = unsafe { JSContext::borrow_raw(self.ctx) };
+
+        let promise_constructor = ctx.global_object()?.get_property("Promise").as_object()?;
+        let promise = promise_constructor
+            .get_property("resolve")
+            .as_object()?
+            .call_as_function(Some(&promise_constructor), slice::from_ref(self))?
+            .as_object()?;
+
+        promise.get_property("then").as_object()?.call_as_function(
+            Some(&promise),
+            &[
+                settlement_handler(&ctx, settlement, true),
+                settlement_handler(&ctx, settlement, false),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Creates the handler recording that the promise has been fulfilled, or
+/// rejected, in `settlement`, and waking its future.
+fn settlement_handler<'ctx>(
+    ctx: &'ctx JSContext,
+    settlement: &Rc<RefCell<Settlement>>,
+    fulfilled: bool,
+) -> JSValue<'ctx> {
+    let settlement = Rc::clone(settlement);
+
+    JSValue::new_function_from_closure(ctx, "", move |ctx, _this, arguments| {
+        let value = arguments
+            .first()
+            .cloned()
+            .unwrap_or_else(|| JSValue::new_undefined(ctx));
+
+        let waker = {
+            let mut settlement = settlement.borrow_mut();
+            settlement.result = Some(if fulfilled {
+                Ok(value.to_persistent())
+            } else {
+                Err(value.into())
+            });
+
+            settlement.waker.take()
+        };
+
+        // The future may be polled right away.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        Ok(JSValue::new_undefined(ctx))
+    })
+}
+
+/// Calls `function`, the `resolve` or `reject` function of a promise.
+fn settle(
+    function: &Persistent<JSObject<'static>>,
+    value: &JSValue<'_>,
+) -> Result<(), JSException> {
+    function
+        .get()
+        .call_as_function(None, slice::from_ref(value))
+        .map(drop)
+}
+
+impl fmt::Debug for JSPromiseResolver {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("JSPromiseResolver")
+            .field("resolve", &self.resolve)
+            .field("reject", &self.reject)
+            .field("settled", &self.settled)
+            .finish()
+    }
+}
+
+impl Drop for JSPromiseResolver {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+
+        let error = JSException::new_builtin(self.reject.ctx, "Error", DROPPED_MESSAGE);
+
+        // There is nobody to report a failure to.
+        let _ = settle(&self.reject, &error.underlying_value());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{pin::pin, task::Poll};
+
+    use crate::{evaluate_script, JSContext, JSException, JSPromise, JSValue};
+
+    fn expose(ctx: &JSContext, promise: JSPromise<'_>) {
+        ctx.global_object()
+            .unwrap()
+            .set_property("promise", promise.into())
+            .unwrap();
+        evaluate_script(
+            ctx,
+            "var settled = 'pending';
+             promise.then(
+                 value => settled = `fulfilled: ${value}`,
+                 error => settled = `rejected: ${error.message}`,
+             );",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+    }
+
+    fn settled(ctx: &JSContext) -> String {
+        evaluate_script(ctx, "settled", None, "test.js", 1)
+            .unwrap()
+            .as_string()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn resolve_later() {
+        let ctx = JSContext::default();
+
+        let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+        assert!(promise.is_object());
+        expose(&ctx, promise);
+        assert_eq!(settled(&ctx), "pending");
+
+        resolver
+            .resolve(&JSValue::new_string(&ctx, "answer"))
+            .unwrap();
+        assert_eq!(settled(&ctx), "fulfilled: answer");
+    }
+
+    #[test]
+    fn reject_later() {
+        let ctx = JSContext::default();
+
+        let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+        expose(&ctx, promise);
+
+        let error = JSException::new_type_error(&ctx, "oops");
+        resolver.reject(&error.underlying_value()).unwrap();
+        assert_eq!(settled(&ctx), "rejected: oops");
+    }
+
+    #[test]
+    fn dropped_resolver_rejects() {
+        let ctx = JSContext::default();
+
+        let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+        expose(&ctx, promise);
+
+        drop(resolver);
+        assert_eq!(
+            settled(&ctx),
+            "rejected: the promise has been dropped without being settled"
+        );
+    }
+
+    #[test]
+    fn await_promises() {
+        let ctx = JSContext::default();
+
+        let promise = evaluate_script(
+            &ctx,
+            "Promise.resolve(40).then(x => x + 2)",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        let Poll::Ready(answer) = ctx.poll_future(pin!(promise.into_future())) else {
+            panic!("the promise is pending");
+        };
+        assert_eq!(answer.unwrap().as_number().unwrap(), 42.);
+
+        let promise = evaluate_script(
+            &ctx,
+            "(async () => { await null; throw new TypeError('oops'); })()",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        let Poll::Ready(exception) = ctx.poll_future(pin!(promise.into_future())) else {
+            panic!("the promise is pending");
+        };
+        assert_eq!(exception.unwrap_err().name().unwrap(), "TypeError");
+
+        // Values which aren't promises are ready right away.
+        let value = JSValue::new_string(&ctx, "answer");
+        let Poll::Ready(value) = ctx.poll_future(pin!(value.into_future())) else {
+            panic!("the value is pending");
+        };
+        assert_eq!(value.unwrap().as_string().unwrap(), "answer");
+    }
+
+    #[test]
+    fn await_pending_promise() {
+        let ctx = JSContext::default();
+
+        let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+        let mut future = pin!(JSValue::from(promise).into_future());
+        assert!(ctx.poll_future(future.as_mut()).is_pending());
+        assert!(ctx.poll_future(future.as_mut()).is_pending());
+
+        drop(resolver);
+        let Poll::Ready(exception) = ctx.poll_future(future) else {
+            panic!("the promise is pending");
+        };
+        assert_eq!(
+            exception.unwrap_err().message().unwrap(),
+            "the promise has been dropped without being settled"
+        );
+    }
+
+    #[test]
+    fn resolver_outlives_context() {
+        let resolver = {
+            let ctx = JSContext::default();
+            let (promise, resolver) = JSPromise::new(&ctx).unwrap();
+            expose(&ctx, promise);
+
+            resolver
+        };
+
+        // The resolver keeps the context alive.
+        drop(resolver);
+    }
+}
@@ -0,0 +1,1448 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serde support for [`JSValue`].
+//!
+//! This module is available when the `serde` feature is enabled. It
+//! converts Rust values to JavaScript values (and back) directly,
+//! without going through a JSON string.
+//!
+//! The data model follows the one used by `serde_json`:
+//!
+//! * `bool`, numbers and strings map to their JavaScript primitives,
+//! * `None`, `()` and unit structs map to `null`,
+//! * sequences and tuples map to arrays,
+//! * maps and structs map to plain objects. Map keys must be strings,
+//!   or integers, floats, booleans, characters or unit variants, which are
+//!   written as property names and parsed back,
+//! * enums are externally tagged: a unit variant is a string, and
+//!   other variants are objects with a single property named after the
+//!   variant.
+//!
+//! ```
+//! # use javascriptcore::{serde::{from_value, to_value}, JSContext};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     retries: u8,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let ctx = JSContext::default();
+//! let config = Config {
+//!     name: "primary".to_owned(),
+//!     retries: 3,
+//!     tags: vec!["a".to_owned(), "b".to_owned()],
+//! };
+//!
+//! let value = to_value(&ctx, &config).unwrap();
+//! assert_eq!(
+//!     value.to_json_string(0).unwrap(),
+//!     r#"{"name":"primary","retries":3,"tags":["a","b"]}"#,
+//! );
+//!
+//! let back: Config = from_value(&value).unwrap();
+//! assert_eq!(back, config);
+//! ```
+//!
+//! Errors carry the path to the offending value:
+//!
+//! ```
+//! # use javascriptcore::{serde::from_value, JSContext, JSValue};
+//! # use serde::Deserialize;
+//! #[derive(Debug, Deserialize)]
+//! struct Point {
+//!     x: f64,
+//!     y: f64,
+//! }
+//!
+//! let ctx = JSContext::default();
+//! let value = JSValue::new_from_json(&ctx, r#"[{"x": 1, "y": 2}, {"x": 3, "y": "4"}]"#).unwrap();
+//! let error = from_value::<Vec<Point>>(&value).unwrap_err();
+//!
+//! assert_eq!(error.path(), "[1].y");
+//! ```
+
+use std::{error, fmt};
+
+use ::serde::{de, ser, Deserialize, Serialize};
+
+use crate::{sys, JSContext, JSObject, JSType, JSValue, MAX_SAFE_INTEGER};
+
+/// Converts a Rust value into a [`JSValue`] living in `ctx`.
+///
+/// See the [module documentation](self) to learn how Rust types
+/// are mapped to JavaScript values.
+pub fn to_value<'ctx, T>(ctx: &'ctx JSContext, value: &T) -> Result<JSValue<'ctx>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer::new(ctx))
+}
+
+/// Converts a [`JSValue`] into a Rust value.
+///
+/// See the [module documentation](self) to learn how JavaScript
+/// values are mapped to Rust types.
+pub fn from_value<T>(value: &JSValue<'_>) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    T::deserialize(Deserializer::new(value.clone()))
+}
+
+/// An error raised while converting between Rust and JavaScript values.
+///
+/// The error remembers where it happened, see [`Error::path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+    path: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl Error {
+    fn new<M: fmt::Display>(message: M) -> Self {
+        Self {
+            message: message.to_string(),
+            path: Vec::new(),
+        }
+    }
+
+    /// The error message, without the path.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The path of the value that caused the error, like `users[2].name`.
+    ///
+    /// The path is empty if the error happened on the root value.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+
+        for segment in &self.path {
+            match segment {
+                PathSegment::Key(key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+
+                    path.push_str(key);
+                }
+                PathSegment::Index(index) => {
+                    path.push_str(&format!("[{index}]"));
+                }
+            }
+        }
+
+        path
+    }
+
+    fn at_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.path.insert(0, PathSegment::Key(key.into()));
+        self
+    }
+
+    fn at_index(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(formatter, "{}", self.message)
+        } else {
+            write!(formatter, "{} at `{}`", self.message, self.path())
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self::new(message)
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self::new(message)
+    }
+}
+
+/// A [`serde::Serializer`](ser::Serializer) producing [`JSValue`]s.
+#[derive(Clone, Copy)]
+pub struct Serializer<'a> {
+    ctx: &'a JSContext,
+}
+
+impl<'a> Serializer<'a> {
+    /// Creates a serializer building values in `ctx`.
+    pub fn new(ctx: &'a JSContext) -> Self {
+        Self { ctx }
+    }
+
+    fn new_array(&self) -> Result<JSObject<'a>, Error> {
+        JSValue::new_array(self.ctx, &[])
+            .and_then(|array| array.as_object())
+            .map_err(Error::new)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn new_integer<N>(self, number: N) -> Result<JSValue<'a>, Error>
+    where
+        N: TryInto<i64> + fmt::Display + Copy,
+    {
+        match number.try_into() {
+            Ok(n) if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&n) => {
+                Ok(JSValue::new_number(self.ctx, n as f64))
+            }
+            _ => Err(Error::new(format!(
+                "integer `{number}` cannot be represented exactly by a JavaScript number"
+            ))),
+        }
+    }
+
+    fn new_variant(&self, variant: &'static str, value: JSValue<'a>) -> Result<JSValue<'a>, Error> {
+        let object = JSObject::new(self.ctx);
+        object.set_property(variant, value).map_err(Error::new)?;
+
+        Ok(object.into())
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray<'a>;
+    type SerializeTuple = SerializeArray<'a>;
+    type SerializeTupleStruct = SerializeArray<'a>;
+    type SerializeTupleVariant = SerializeVariant<'a, SerializeArray<'a>>;
+    type SerializeMap = SerializeObject<'a>;
+    type SerializeStruct = SerializeObject<'a>;
+    type SerializeStructVariant = SerializeVariant<'a, SerializeObject<'a>>;
+
+    fn serialize_bool(self, v: bool) -> Result<JSValue<'a>, Error> {
+        Ok(JSValue::new_boolean(self.ctx, v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JSValue<'a>, Error> {
+        self.new_integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<JSValue<'a>, Error> {
+        self.new_integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JSValue<'a>, Error> {
+        self.new_integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<JSValue<'a>, Error> {
+        self.new_integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JSValue<'a>, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JSValue<'a>, Error> {
+        Ok(JSValue::new_number(self.ctx, v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<JSValue<'a>, Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JSValue<'a>, Error> {
+        Ok(JSValue::new_string(self.ctx, v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JSValue<'a>, Error> {
+        let array = self.new_array()?;
+
+        for (index, byte) in (0..).zip(v) {
+            array
+                .set_property_at_index(index, JSValue::new_number(self.ctx, (*byte).into()))
+                .map_err(Error::new)?;
+        }
+
+        Ok(array.into())
+    }
+
+    fn serialize_none(self) -> Result<JSValue<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<JSValue<'a>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JSValue<'a>, Error> {
+        Ok(JSValue::new_null(self.ctx))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JSValue<'a>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<JSValue<'a>, Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<JSValue<'a>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JSValue<'a>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = value
+            .serialize(Serializer::new(self.ctx))
+            .map_err(|error| error.at_key(variant))?;
+
+        self.new_variant(variant, value)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(SerializeArray {
+            ctx: self.ctx,
+            array: self.new_array()?,
+            index: 0,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(SerializeVariant {
+            serializer: self,
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(SerializeObject {
+            ctx: self.ctx,
+            object: JSObject::new(self.ctx),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(SerializeVariant {
+            serializer: self,
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Serializes sequences and tuples into a JavaScript array.
+#[doc(hidden)]
+pub struct SerializeArray<'a> {
+    ctx: &'a JSContext,
+    array: JSObject<'a>,
+    index: u32,
+}
+
+impl<'a> SerializeArray<'a> {
+    fn push<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let index = self.index;
+        let value = value
+            .serialize(Serializer::new(self.ctx))
+            .map_err(|error| error.at_index(index as usize))?;
+
+        self.array
+            .set_property_at_index(index, value)
+            .map_err(|error| Error::new(error).at_index(index as usize))?;
+        self.index += 1;
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for SerializeArray<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        Ok(self.array.into())
+    }
+}
+
+impl<'a> ser::SerializeTuple for SerializeArray<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        Ok(self.array.into())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SerializeArray<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        Ok(self.array.into())
+    }
+}
+
+/// Serializes maps and structs into a JavaScript object.
+#[doc(hidden)]
+pub struct SerializeObject<'a> {
+    ctx: &'a JSContext,
+    object: JSObject<'a>,
+    key: Option<String>,
+}
+
+impl<'a> SerializeObject<'a> {
+    fn insert<T>(&mut self, key: String, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = match value.serialize(Serializer::new(self.ctx)) {
+            Ok(value) => value,
+            Err(error) => return Err(error.at_key(key)),
+        };
+
+        if let Err(error) = self.object.set_property(key.as_str(), value) {
+            return Err(Error::new(error).at_key(key));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for SerializeObject<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(key.serialize(MapKeySerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::new("`serialize_value` called before `serialize_key`"))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        Ok(self.object.into())
+    }
+}
+
+impl<'a> ser::SerializeStruct for SerializeObject<'a> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        Ok(self.object.into())
+    }
+}
+
+/// Serializes enum variants holding data into an object with a single
+/// property named after the variant.
+#[doc(hidden)]
+pub struct SerializeVariant<'a, S> {
+    serializer: Serializer<'a>,
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'a> ser::SerializeTupleVariant for SerializeVariant<'a, SerializeArray<'a>> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner
+            .push(value)
+            .map_err(|error| error.at_key(self.variant))
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        self.serializer
+            .new_variant(self.variant, self.inner.array.into())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for SerializeVariant<'a, SerializeObject<'a>> {
+    type Ok = JSValue<'a>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner
+            .insert(key.to_owned(), value)
+            .map_err(|error| error.at_key(self.variant))
+    }
+
+    fn end(self) -> Result<JSValue<'a>, Error> {
+        self.serializer
+            .new_variant(self.variant, self.inner.object.into())
+    }
+}
+
+/// Serializes map keys into property names.
+struct MapKeySerializer;
+
+impl MapKeySerializer {
+    fn unsupported(kind: &str) -> Error {
+        Error::new(format!("{kind} cannot be used as an object property name"))
+    }
+}
+
+macro_rules! serialize_key_to_string {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String, Error> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    serialize_key_to_string!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    );
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(Self::unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(Self::unsupported("`None`"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(Self::unsupported("`()`"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<String, Error> {
+        Err(Self::unsupported(&format!("unit struct `{name}`")))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::unsupported(&format!("variant `{variant}`")))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Self::unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Self::unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Self::unsupported(&format!("tuple struct `{name}`")))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Self::unsupported(&format!("variant `{variant}`")))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Self::unsupported("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(Self::unsupported(&format!("struct `{name}`")))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Self::unsupported(&format!("variant `{variant}`")))
+    }
+}
+
+/// A [`serde::Deserializer`](de::Deserializer) reading from a [`JSValue`].
+pub struct Deserializer<'ctx> {
+    value: JSValue<'ctx>,
+}
+
+impl<'ctx> Deserializer<'ctx> {
+    /// Creates a deserializer reading `value`.
+    pub fn new(value: JSValue<'ctx>) -> Self {
+        Self { value }
+    }
+
+    /// Converts the value to a number without treating `NaN` as an error.
+    fn number(&self) -> f64 {
+        let mut exception: sys::JSValueRef = std::ptr::null_mut();
+
+        unsafe { sys::JSValueToNumber(self.value.ctx, self.value.raw, &mut exception) }
+    }
+
+    fn string(&self) -> Result<String, Error> {
+        self.value
+            .as_string()
+            .map(|string| string.to_string())
+            .map_err(Error::new)
+    }
+
+    fn object(&self) -> Result<JSObject<'ctx>, Error> {
+        self.value.as_object().map_err(Error::new)
+    }
+
+    /// Returns the `length` of an array-like object.
+    fn length(object: &JSObject<'ctx>) -> Result<u32, Error> {
+        let length = object
+            .get_property("length")
+            .as_number()
+            .map_err(Error::new)?;
+
+        if length.fract() != 0.0 || !(0.0..=f64::from(u32::MAX)).contains(&length) {
+            return Err(Error::new(format!("invalid array length `{length}`")));
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(length as u32)
+    }
+
+    fn invalid_type(&self, expected: &dyn de::Expected) -> Error {
+        let unexpected = match self.value.get_type() {
+            JSType::Undefined | JSType::Null => de::Unexpected::Unit,
+            JSType::Boolean => de::Unexpected::Bool(self.value.as_boolean()),
+            JSType::Number => de::Unexpected::Float(self.number()),
+            JSType::String => de::Unexpected::Other("string"),
+            JSType::Symbol => de::Unexpected::Other("symbol"),
+            JSType::BigInt => de::Unexpected::Other("bigint"),
+            JSType::Object if self.value.is_array() => de::Unexpected::Seq,
+            JSType::Object => de::Unexpected::Map,
+        };
+
+        de::Error::invalid_type(unexpected, expected)
+    }
+}
+
+impl<'de, 'ctx> de::Deserializer<'de> for Deserializer<'ctx> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.value.get_type() {
+            JSType::Undefined | JSType::Null => visitor.visit_unit(),
+            JSType::Boolean => visitor.visit_bool(self.value.as_boolean()),
+            JSType::Number => {
+                let number = self.number();
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+                if number.fract() != 0.0
+                    || number.abs() > MAX_SAFE_INTEGER as f64
+                    || (number == 0.0 && number.is_sign_negative())
+                {
+                    visitor.visit_f64(number)
+                } else if number < 0.0 {
+                    visitor.visit_i64(number as i64)
+                } else {
+                    #[allow(clippy::cast_sign_loss)]
+                    visitor.visit_u64(number as u64)
+                }
+            }
+            JSType::String => visitor.visit_string(self.string()?),
+            JSType::Object if self.value.is_array() || self.value.is_typed_array() => {
+                self.deserialize_seq(visitor)
+            }
+            JSType::Object => self.deserialize_map(visitor),
+            JSType::Symbol => Err(Error::new("symbols cannot be deserialized")),
+            JSType::BigInt => visitor.visit_i128(self.value.as_i128().map_err(Error::new)?),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.value.is_null() || self.value.is_undefined() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.value.is_typed_array() {
+            let bytes = self
+                .value
+                .as_typed_array()
+                .and_then(|array| array.to_vec())
+                .map_err(Error::new)?;
+
+            visitor.visit_byte_buf(bytes)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if !self.value.is_array() && !self.value.is_typed_array() {
+            return Err(self.invalid_type(&visitor));
+        }
+
+        let array = self.object()?;
+        let length = Self::length(&array)?;
+
+        visitor.visit_seq(ArrayAccess {
+            array,
+            length,
+            index: 0,
+        })
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if !self.value.is_object() {
+            return Err(self.invalid_type(&visitor));
+        }
+
+        let object = self.object()?;
+        let keys = object
+            .property_names()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        visitor.visit_map(ObjectAccess {
+            object,
+            keys,
+            key: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.value.is_string() {
+            return visitor.visit_enum(EnumAccess {
+                variant: self.string()?,
+                value: None,
+            });
+        }
+
+        if !self.value.is_object() {
+            return Err(self.invalid_type(&visitor));
+        }
+
+        let object = self.object()?;
+        let mut names = object.property_names();
+
+        match (names.next(), names.next()) {
+            (Some(variant), None) => {
+                let variant = variant.to_string();
+                let value = object.get_property(variant.as_str());
+
+                visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Map,
+                &"a string or an object with a single property",
+            )),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct identifier ignored_any
+    }
+}
+
+/// Visits the items of an array-like object.
+struct ArrayAccess<'ctx> {
+    array: JSObject<'ctx>,
+    length: u32,
+    index: u32,
+}
+
+impl<'de, 'ctx> de::SeqAccess<'de> for ArrayAccess<'ctx> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.index >= self.length {
+            return Ok(None);
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        seed.deserialize(Deserializer::new(self.array.get_property_at_index(index)))
+            .map(Some)
+            .map_err(|error| error.at_index(index as usize))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some((self.length - self.index) as usize)
+    }
+}
+
+/// Visits the enumerable properties of an object.
+struct ObjectAccess<'ctx> {
+    object: JSObject<'ctx>,
+    keys: std::vec::IntoIter<String>,
+    key: Option<String>,
+}
+
+impl<'de, 'ctx> de::MapAccess<'de> for ObjectAccess<'ctx> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        let Some(key) = self.keys.next() else {
+            return Ok(None);
+        };
+
+        let result = seed
+            .deserialize(MapKeyDeserializer { key: key.clone() })
+            .map(Some)
+            .map_err(|error| error.at_key(key.as_str()));
+        self.key = Some(key);
+
+        result
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::new("`next_value_seed` called before `next_key_seed`"))?;
+
+        seed.deserialize(Deserializer::new(self.object.get_property(key.as_str())))
+            .map_err(|error| error.at_key(key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.keys.len())
+    }
+}
+
+/// Deserializes property names into map keys, parsing the integers,
+/// floats, booleans and characters written by [`MapKeySerializer`].
+struct MapKeyDeserializer {
+    key: String,
+}
+
+macro_rules! deserialize_key_from_str {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                match self.key.parse::<$ty>() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(
+                        de::Unexpected::Str(&self.key),
+                        &visitor,
+                    )),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKeyDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_string(self.key)
+    }
+
+    deserialize_key_from_str!(
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    );
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::IntoDeserializer::<Error>::into_deserializer(self.key)
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+/// Visits an externally tagged enum.
+struct EnumAccess<'ctx> {
+    variant: String,
+    value: Option<JSValue<'ctx>>,
+}
+
+impl<'de, 'ctx> de::EnumAccess<'de> for EnumAccess<'ctx> {
+    type Error = Error;
+    type Variant = VariantAccess<'ctx>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantAccess<'ctx>), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(
+            self.variant.as_str(),
+        ))?;
+
+        Ok((
+            variant,
+            VariantAccess {
+                variant: self.variant,
+                value: self.value,
+            },
+        ))
+    }
+}
+
+/// Visits the content of an enum variant.
+struct VariantAccess<'ctx> {
+    variant: String,
+    value: Option<JSValue<'ctx>>,
+}
+
+impl<'ctx> VariantAccess<'ctx> {
+    fn deserializer(self, expected: &str) -> Result<(String, Deserializer<'ctx>), Error> {
+        match self.value {
+            Some(value) => Ok((self.variant, Deserializer::new(value))),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &expected,
+            )),
+        }
+    }
+}
+
+impl<'de, 'ctx> de::VariantAccess<'de> for VariantAccess<'ctx> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => <()>::deserialize(Deserializer::new(value))
+                .map_err(|error| error.at_key(self.variant)),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let (variant, deserializer) = self.deserializer("newtype variant")?;
+
+        seed.deserialize(deserializer)
+            .map_err(|error| error.at_key(variant))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let (variant, deserializer) = self.deserializer("tuple variant")?;
+
+        de::Deserializer::deserialize_tuple(deserializer, len, visitor)
+            .map_err(|error| error.at_key(variant))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let (variant, deserializer) = self.deserializer("struct variant")?;
+
+        de::Deserializer::deserialize_struct(deserializer, "", fields, visitor)
+            .map_err(|error| error.at_key(variant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{from_value, to_value};
+    use crate::{evaluate_script, JSContext, JSValue};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Segment(f64, f64),
+        Rectangle { width: f64, height: f64 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Scene {
+        name: String,
+        visible: bool,
+        layer: Option<u8>,
+        shapes: Vec<Shape>,
+        matrix: Vec<Vec<i32>>,
+        labels: BTreeMap<String, String>,
+    }
+
+    fn scene() -> Scene {
+        Scene {
+            name: "main".to_owned(),
+            visible: true,
+            layer: None,
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Segment(0., 2.),
+                Shape::Rectangle {
+                    width: 3.,
+                    height: 4.,
+                },
+            ],
+            matrix: vec![vec![1, 0], vec![0, -1]],
+            labels: BTreeMap::from([("en".to_owned(), "Scene".to_owned())]),
+        }
+    }
+
+    #[test]
+    fn serialize() {
+        let ctx = JSContext::default();
+        let value = to_value(&ctx, &scene()).unwrap();
+
+        assert_eq!(
+            value.to_json_string(0).unwrap(),
+            concat!(
+                r#"{"name":"main","visible":true,"layer":null,"#,
+                r#""shapes":["Empty",{"Circle":1.5},{"Segment":[0,2]},{"Rectangle":{"width":3,"height":4}}],"#,
+                r#""matrix":[[1,0],[0,-1]],"labels":{"en":"Scene"}}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let ctx = JSContext::default();
+        let value = to_value(&ctx, &scene()).unwrap();
+
+        assert_eq!(from_value::<Scene>(&value).unwrap(), scene());
+    }
+
+    #[test]
+    fn deserialize_from_script() {
+        let ctx = JSContext::default();
+        let value = evaluate_script(
+            &ctx,
+            "({ name: 'js', visible: false, layer: 2, shapes: [{ Circle: 2 }], matrix: [], labels: {} })",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        let scene: Scene = from_value(&value).unwrap();
+
+        assert_eq!(scene.name, "js");
+        assert_eq!(scene.layer, Some(2));
+        assert_eq!(scene.shapes, vec![Shape::Circle(2.)]);
+    }
+
+    #[test]
+    fn undefined_is_none() {
+        let ctx = JSContext::default();
+
+        assert_eq!(
+            from_value::<Option<f64>>(&JSValue::new_undefined(&ctx)).unwrap(),
+            None
+        );
+        assert_eq!(
+            from_value::<Option<f64>>(&JSValue::new_number(&ctx, 1.)).unwrap(),
+            Some(1.)
+        );
+    }
+
+    #[test]
+    fn integers_out_of_range() {
+        let ctx = JSContext::default();
+
+        assert!(to_value(&ctx, &(1u64 << 53)).is_err());
+        assert!(to_value(&ctx, &((1u64 << 53) - 1)).is_ok());
+        assert!(from_value::<u8>(&JSValue::new_number(&ctx, 256.)).is_err());
+        assert!(from_value::<u8>(&JSValue::new_number(&ctx, 1.5)).is_err());
+    }
+
+    #[test]
+    fn map_keys_are_property_names() {
+        let ctx = JSContext::default();
+
+        let map = BTreeMap::from([(1, true), (2, false)]);
+        let value = to_value(&ctx, &map).unwrap();
+        assert_eq!(value.to_json_string(0).unwrap(), r#"{"1":true,"2":false}"#);
+        assert_eq!(from_value::<BTreeMap<u32, bool>>(&value).unwrap(), map);
+
+        let map = HashMap::from([(-1i64, 'a'), (i64::MAX, 'b')]);
+        let value = to_value(&ctx, &map).unwrap();
+        assert_eq!(from_value::<HashMap<i64, char>>(&value).unwrap(), map);
+
+        let map = BTreeMap::from([(true, 1.5), (false, -1.)]);
+        let value = to_value(&ctx, &map).unwrap();
+        assert_eq!(from_value::<BTreeMap<bool, f64>>(&value).unwrap(), map);
+
+        let map = BTreeMap::from([('x', 1), ('y', 2)]);
+        let value = to_value(&ctx, &map).unwrap();
+        assert_eq!(from_value::<BTreeMap<char, u8>>(&value).unwrap(), map);
+
+        let value = JSValue::new_from_json(&ctx, r#"{"1": true, "x": false}"#).unwrap();
+        let error = from_value::<BTreeMap<u32, bool>>(&value).unwrap_err();
+        assert_eq!(error.path(), "x");
+
+        let map = BTreeMap::from([((1, 2), true)]);
+        assert!(to_value(&ctx, &map).is_err());
+    }
+
+    #[test]
+    fn errors_have_a_path() {
+        let ctx = JSContext::default();
+        let value = JSValue::new_from_json(
+            &ctx,
+            r#"{"name": "x", "visible": true, "shapes": [{"Rectangle": {"width": 1, "height": "2"}}], "matrix": [], "labels": {}}"#,
+        )
+        .unwrap();
+
+        let error = from_value::<Scene>(&value).unwrap_err();
+
+        assert_eq!(error.path(), "shapes[0].Rectangle.height");
+        assert!(error
+            .to_string()
+            .ends_with("at `shapes[0].Rectangle.height`"));
+    }
+}
@@ -0,0 +1,258 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::sync::PoisonError;
+
+use crate::{
+    state::{with_existing_state, with_state},
+    sys, JSContext, JSException, JSString, StackFrame,
+};
+
+impl JSContext {
+    /// Registers the source map of the script evaluated with `source_url`,
+    /// replacing the previous one, if any.
+    ///
+    /// The locations of the exceptions thrown in this script, like
+    /// [`JSException::stack_frames()`] or [`JSException::line()`], are then
+    /// remapped to the original sources. Scripts ending with an inline
+    /// `//# sourceMappingURL=data:` map don't need to be registered, as
+    /// [`evaluate_script`](crate::evaluate_script) registers it.
+    ///
+    /// This requires the `sourcemap` feature, which relies on the private
+    /// API of JavaScriptCore to drop the source maps with the context.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let map = r#"{
+    ///     "version": 3,
+    ///     "sources": ["app.ts"],
+    ///     "names": [],
+    ///     "mappings": "AASA"
+    /// }"#;
+    /// ctx.register_source_map("bundle.js", map.as_bytes()).unwrap();
+    ///
+    /// let exception = evaluate_script(&ctx, "oops()", None, "bundle.js", 1).unwrap_err();
+    /// assert_eq!(exception.source_url().unwrap(), "app.ts");
+    /// assert_eq!(exception.line(), Some(10));
+    ///
+    /// assert!(ctx.register_source_map("bundle.js", b"{").is_err());
+    /// ```
+    pub fn register_source_map<U: Into<String>>(
+        &self,
+        source_url: U,
+        source_map: &[u8],
+    ) -> Result<(), JSException> {
+        let source_map = sourcemap::decode_slice(source_map)
+            .map_err(|error| JSException::from_error(self, &error))?;
+
+        with_state(self.raw, |state| {
+            state
+                .source_maps
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(source_url.into(), source_map);
+        });
+
+        Ok(())
+    }
+}
+
+/// Registers the inline source map of `script`, if any. Invalid maps are
+/// ignored, as they shouldn't prevent the script from running.
+///
+/// Only the last line of the script is converted, as the map is referenced
+/// at the end of the script.
+pub(crate) fn register_inline_source_map(
+    ctx: sys::JSContextRef,
+    source_url: &JSString,
+    script: &JSString,
+) {
+    if source_url.is_empty() {
+        return;
+    }
+
+    let last_line = last_line(script);
+
+    if !last_line.contains("sourceMappingURL=data:") {
+        return;
+    }
+
+    let source_map = sourcemap::locate_sourcemap_reference_slice(last_line.as_bytes())
+        .ok()
+        .flatten()
+        .and_then(|reference| reference.get_embedded_sourcemap().ok().flatten());
+
+    if let Some(source_map) = source_map {
+        with_state(ctx, |state| {
+            state
+                .source_maps
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(source_url.to_string(), source_map);
+        });
+    }
+}
+
+/// Returns the last line of `script` which isn't blank.
+fn last_line(script: &JSString) -> String {
+    let characters = script.code_units();
+    let is_line_break = |character: &u16| matches!(character, 0x0a | 0x0d | 0x2028 | 0x2029);
+    let is_blank =
+        |character: &u16| char::from_u32(u32::from(*character)).is_some_and(char::is_whitespace);
+
+    let end = characters
+        .iter()
+        .rposition(|character| !is_blank(character))
+        .map_or(0, |index| index + 1);
+    let start = characters[..end]
+        .iter()
+        .rposition(is_line_break)
+        .map_or(0, |index| index + 1);
+
+    String::from_utf16_lossy(&characters[start..end])
+}
+
+/// Remaps the location of `frame` to the original source, if a source map
+/// has been registered for its script.
+pub(crate) fn remap(ctx: sys::JSContextRef, frame: &mut StackFrame) {
+    let (Some(source_url), Some(line), Some(column)) =
+        (&frame.source_url, frame.line, frame.column)
+    else {
+        return;
+    };
+
+    let original = with_existing_state(ctx, |state| {
+        let source_maps = state
+            .source_maps
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (line, column) = (line.checked_sub(1)?, column.saturating_sub(1));
+        let token = source_maps.get(source_url)?.lookup_token(line, column)?;
+
+        // The closest token may belong to a previous line, which isn't
+        // related to this location.
+        if token.get_dst_line() != line {
+            return None;
+        }
+
+        Some((
+            token.get_source()?.to_owned(),
+            token.get_src_line() + 1,
+            token.get_src_col() + 1,
+        ))
+    })
+    .flatten();
+
+    if let Some((source_url, line, column)) = original {
+        frame.source_url = Some(source_url);
+        frame.line = Some(line);
+        frame.column = Some(column);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sourcemap::SourceMapBuilder;
+
+    use crate::{evaluate_script, JSContext};
+
+    /// The script, and the source map of its two lines to `app.ts`.
+    fn bundle() -> (&'static str, sourcemap::SourceMap) {
+        let script = "function fail(){throw new Error('oops')}\nfail();";
+
+        let mut builder = SourceMapBuilder::new(Some("bundle.js"));
+        builder.add(0, 0, 4, 0, Some("app.ts"), None, false);
+        builder.add(0, 16, 5, 2, Some("app.ts"), None, false);
+        builder.add(1, 0, 8, 0, Some("app.ts"), None, false);
+
+        (script, builder.into_sourcemap())
+    }
+
+    #[test]
+    fn registered_source_map() {
+        let ctx = JSContext::default();
+        let (script, source_map) = bundle();
+        let mut json = Vec::new();
+        source_map.to_writer(&mut json).unwrap();
+        ctx.register_source_map("bundle.js", &json).unwrap();
+
+        let exception = evaluate_script(&ctx, script, None, "bundle.js", 1).unwrap_err();
+
+        let frames = exception.stack_frames();
+        assert_eq!(frames[0].function.as_deref(), Some("fail"));
+        assert_eq!(frames[0].source_url.as_deref(), Some("app.ts"));
+        assert_eq!((frames[0].line, frames[0].column), (Some(6), Some(3)));
+        assert_eq!(frames[1].function.as_deref(), Some("global code"));
+        assert_eq!(frames[1].source_url.as_deref(), Some("app.ts"));
+        assert_eq!((frames[1].line, frames[1].column), (Some(9), Some(1)));
+
+        assert_eq!(exception.source_url().unwrap(), "app.ts");
+        assert_eq!(exception.line(), Some(6));
+        assert_eq!(exception.column(), Some(3));
+        assert!(format!("{exception:#}").contains("at app.ts:6:3"));
+
+        // Other scripts aren't remapped.
+        let exception = evaluate_script(&ctx, script, None, "other.js", 1).unwrap_err();
+        assert_eq!(exception.source_url().unwrap(), "other.js");
+        assert_eq!(exception.line(), Some(1));
+    }
+
+    #[test]
+    fn inline_source_map() {
+        let ctx = JSContext::default();
+        let (script, source_map) = bundle();
+        let script = format!(
+            "{script}\n//# sourceMappingURL={}\n\n",
+            source_map.to_data_url().unwrap()
+        );
+
+        let exception = evaluate_script(&ctx, script, None, "bundle.js", 1).unwrap_err();
+
+        let frames = exception.stack_frames();
+        assert_eq!(frames[0].source_url.as_deref(), Some("app.ts"));
+        assert_eq!(frames[0].line, Some(6));
+        assert_eq!(frames[1].line, Some(9));
+    }
+
+    #[test]
+    fn invalid_source_map() {
+        let ctx = JSContext::default();
+
+        let exception = ctx.register_source_map("bundle.js", b"{").unwrap_err();
+        assert_eq!(exception.name().unwrap(), "Error");
+
+        // An invalid inline map doesn't prevent the script from running.
+        let result = evaluate_script(
+            &ctx,
+            "42\n//# sourceMappingURL=data:application/json;base64,e30-",
+            None,
+            "bundle.js",
+            1,
+        );
+        assert_eq!(result.unwrap().as_number().unwrap(), 42.);
+    }
+
+    #[test]
+    fn state_is_hidden_from_scripts() {
+        let ctx = JSContext::default();
+        let (script, source_map) = bundle();
+        let mut json = Vec::new();
+        source_map.to_writer(&mut json).unwrap();
+
+        let globals = "Object.getOwnPropertyNames(globalThis).join()";
+        let before = evaluate_script(&ctx, globals, None, "test.js", 1).unwrap();
+        ctx.register_source_map("bundle.js", &json).unwrap();
+        let after = evaluate_script(&ctx, globals, None, "test.js", 1).unwrap();
+        assert_eq!(
+            before.as_string().unwrap().to_string(),
+            after.as_string().unwrap().to_string()
+        );
+
+        let exception = evaluate_script(&ctx, script, None, "bundle.js", 1).unwrap_err();
+        assert_eq!(exception.source_url().unwrap(), "app.ts");
+    }
+}
@@ -0,0 +1,270 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{future::Future, pin::Pin};
+
+use crate::{JSContext, JSException, JSObject, JSPromise, JSString, JSValue, Persistent};
+
+/// An executor running the futures of the async functions, see
+/// [`JSValue::new_async_function`].
+///
+/// The futures aren't `Send`, as they hold JavaScript values: they must be
+/// spawned on the thread of the context. The trait is implemented for the
+/// closures receiving the future, so that any local executor can be
+/// plugged in, e.g. `|future| { tokio::task::spawn_local(future); }` in a
+/// `tokio::task::LocalSet`, or
+/// `move |future| spawner.spawn_local(future).unwrap()` with the
+/// `LocalSpawner` of a `futures::executor::LocalPool`.
+pub trait Spawner {
+    /// Runs `future` to completion on the current thread.
+    fn spawn_local(&self, future: Pin<Box<dyn Future<Output = ()>>>);
+}
+
+impl<F> Spawner for F
+where
+    F: Fn(Pin<Box<dyn Future<Output = ()>>>),
+{
+    fn spawn_local(&self, future: Pin<Box<dyn Future<Output = ()>>>) {
+        self(future)
+    }
+}
+
+impl<'ctx> JSValue<'ctx> {
+    /// Creates a JavaScript function implemented by an async Rust closure.
+    ///
+    /// The function returns a promise right away, and the future returned
+    /// by the closure is spawned with `spawner`. The promise is fulfilled
+    /// with the value of the future, or rejected with its exception.
+    ///
+    /// As the future outlives the call, the closure receives handles which
+    /// don't borrow the context: the context itself, retained, the `this`
+    /// object if any, and the arguments.
+    ///
+    /// ```
+    /// # use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc};
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let futures = Rc::new(RefCell::new(Vec::new()));
+    /// let spawner = {
+    ///     let futures = Rc::clone(&futures);
+    ///     move |future: Pin<Box<dyn Future<Output = ()>>>| futures.borrow_mut().push(future)
+    /// };
+    ///
+    /// let double = JSValue::new_async_function(&ctx, "double", spawner, |ctx, _this, arguments| {
+    ///     async move {
+    ///         let number = arguments[0].get().as_number()?;
+    ///
+    ///         Ok(JSValue::new_number(&ctx, number * 2.).to_persistent())
+    ///     }
+    /// });
+    /// ctx.global_object().unwrap().set_property("double", double).unwrap();
+    ///
+    /// evaluate_script(&ctx, "double(21).then(value => answer = value)", None, "test.js", 1)
+    ///     .unwrap();
+    ///
+    /// // Run the spawned futures.
+    /// for mut future in futures.take() {
+    ///     assert!(ctx.poll_future(future.as_mut()).is_ready());
+    /// }
+    ///
+    /// let answer = evaluate_script(&ctx, "answer", None, "test.js", 1).unwrap();
+    /// assert_eq!(answer.as_number().unwrap(), 42.);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::new_function_from_closure()`]
+    /// - [`JSPromise`]
+    pub fn new_async_function<N, S, F, Fut>(
+        ctx: &'ctx JSContext,
+        name: N,
+        spawner: S,
+        function: F,
+    ) -> Self
+    where
+        N: Into<JSString>,
+        S: Spawner + 'static,
+        F: Fn(JSContext, Option<Persistent<JSObject<'static>>>, Vec<Persistent>) -> Fut + 'static,
+        Fut: Future<Output = Result<Persistent, JSException>> + 'static,
+    {
+        Self::new_function_from_closure(ctx, name, move |ctx, this, arguments| {
+            let (promise, resolver) = JSPromise::new(ctx)?;
+
+            let future = function(
+                unsafe { JSContext::retain(ctx.raw) },
+                this.map(JSObject::to_persistent),
+                arguments.iter().map(JSValue::to_persistent).collect(),
+            );
+
+            spawner.spawn_local(Box::pin(async move {
+                // Settling fails only if the context is being torn down.
+                let _ = match future.await {
+                    Ok(value) => resolver.resolve(&value.get()),
+                    Err(exception) => resolver.reject(&exception.underlying_value()),
+                };
+            }));
+
+            Ok(promise.into())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        future::{self, Future},
+        pin::Pin,
+        rc::Rc,
+        task::Poll,
+    };
+
+    use crate::{evaluate_script, JSContext, JSException, JSValue};
+
+    type Task = Pin<Box<dyn Future<Output = ()>>>;
+    type Tasks = Rc<RefCell<Vec<Task>>>;
+
+    /// Returns a spawner queuing the futures, to be run by [`run`].
+    fn spawner() -> (Tasks, impl Fn(Task)) {
+        let tasks = Tasks::default();
+        let queue = Rc::clone(&tasks);
+
+        (tasks, move |future| queue.borrow_mut().push(future))
+    }
+
+    /// Polls the spawned futures until they're ready.
+    fn run(ctx: &JSContext, tasks: &Tasks) {
+        while !tasks.borrow().is_empty() {
+            let mut pending = tasks.take();
+            pending.retain_mut(|future| ctx.poll_future(future.as_mut()).is_pending());
+            tasks.borrow_mut().extend(pending);
+        }
+    }
+
+    /// A future which is pending once, like a future awaiting I/O.
+    fn yield_now() -> impl Future<Output = ()> {
+        let mut yielded = false;
+
+        future::poll_fn(move |context| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                context.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+    }
+
+    fn result(ctx: &JSContext) -> String {
+        evaluate_script(ctx, "String(result)", None, "test.js", 1)
+            .unwrap()
+            .as_string()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn async_function_fulfills_promise() {
+        let ctx = JSContext::default();
+        let (tasks, spawner) = spawner();
+
+        let greet = JSValue::new_async_function(
+            &ctx,
+            "greet",
+            spawner,
+            |ctx, this, arguments| async move {
+                yield_now().await;
+
+                let greeting = this.unwrap().get().get_property("greeting").as_string()?;
+                let who = arguments[0].get().as_string()?;
+
+                Ok(JSValue::new_string(&ctx, format!("{greeting}, {who}!")).to_persistent())
+            },
+        );
+        ctx.global_object()
+            .unwrap()
+            .set_property("greet", greet)
+            .unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var result = 'pending';
+             ({ greeting: 'Hello', greet }).greet('Gordon').then(value => result = value);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        assert_eq!(result(&ctx), "pending");
+        assert_eq!(tasks.borrow().len(), 1);
+
+        run(&ctx, &tasks);
+        assert_eq!(result(&ctx), "Hello, Gordon!");
+    }
+
+    #[test]
+    fn async_function_rejects_promise() {
+        let ctx = JSContext::default();
+        let (tasks, spawner) = spawner();
+
+        let fail = JSValue::new_async_function(
+            &ctx,
+            "fail",
+            spawner,
+            |ctx, _this, _arguments| async move {
+                yield_now().await;
+
+                Err(JSException::new_range_error(&ctx, "too far"))
+            },
+        );
+        ctx.global_object()
+            .unwrap()
+            .set_property("fail", fail)
+            .unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var result = 'pending';
+             fail().catch(error => result = `${error.name}: ${error.message}`);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        run(&ctx, &tasks);
+        assert_eq!(result(&ctx), "RangeError: too far");
+    }
+
+    #[test]
+    fn dropped_future_rejects_promise() {
+        let ctx = JSContext::default();
+        let (tasks, spawner) = spawner();
+
+        let never =
+            JSValue::new_async_function(&ctx, "never", spawner, |_ctx, _this, _arguments| {
+                future::pending()
+            });
+        ctx.global_object()
+            .unwrap()
+            .set_property("never", never)
+            .unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var result = 'pending'; never().catch(() => result = 'rejected');",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        tasks.borrow_mut().clear();
+        ctx.run_microtasks();
+        assert_eq!(result(&ctx), "rejected");
+    }
+}
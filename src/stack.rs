@@ -0,0 +1,174 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+/// A frame of the stack trace of a [`JSException`](crate::JSException).
+///
+/// JavaScriptCore formats each frame of the `stack` property of an error as
+/// `function@url:line:column`. The function is missing for anonymous
+/// functions, and the location is `[native code]` for built-in functions:
+///
+/// ```
+/// # use javascriptcore::*;
+/// let ctx = JSContext::default();
+/// let exception = evaluate_script(
+///     &ctx,
+///     "function fail() { throw new Error('oops') }\n[1].map(fail)",
+///     None,
+///     "test.js",
+///     1,
+/// ).unwrap_err();
+///
+/// let frames = exception.stack_frames();
+/// assert_eq!(frames[0].function.as_deref(), Some("fail"));
+/// assert_eq!(frames[0].source_url.as_deref(), Some("test.js"));
+/// assert_eq!(frames[0].line, Some(1));
+/// assert_eq!(frames[1].function.as_deref(), Some("map"));
+/// assert_eq!(frames[1].source_url.as_deref(), Some("[native code]"));
+/// assert_eq!(frames[1].line, None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StackFrame {
+    /// The name of the function, e.g. `fail`, `global code` or
+    /// `eval code`, if it has one.
+    pub function: Option<String>,
+    /// The URL of the script, or `[native code]` for built-in functions.
+    pub source_url: Option<String>,
+    /// The line, one-based.
+    pub line: Option<u32>,
+    /// The column, one-based.
+    pub column: Option<u32>,
+}
+
+/// The location of the frames of built-in functions.
+const NATIVE_CODE: &str = "[native code]";
+
+impl StackFrame {
+    /// Parses a frame, like `fail@test.js:1:35`.
+    fn parse(frame: &str) -> Self {
+        let (function, location) = match frame.split_once('@') {
+            Some((function, location)) => (function, location),
+            None if frame == NATIVE_CODE => ("", frame),
+            None => (frame, ""),
+        };
+
+        let mut stack_frame = Self {
+            function: (!function.is_empty()).then(|| function.to_owned()),
+            ..Self::default()
+        };
+
+        // The URL may contain colons too, e.g. before a port: the line and
+        // the column are the last two numbers.
+        let mut parts = location.rsplitn(3, ':');
+        let position = (parts.next(), parts.next(), parts.next());
+
+        if let (Some(column), Some(line), Some(url)) = position {
+            if let (Ok(line), Ok(column)) = (line.parse(), column.parse()) {
+                stack_frame.source_url = (!url.is_empty()).then(|| url.to_owned());
+                stack_frame.line = Some(line);
+                stack_frame.column = Some(column);
+
+                return stack_frame;
+            }
+        }
+
+        stack_frame.source_url = (!location.is_empty()).then(|| location.to_owned());
+
+        stack_frame
+    }
+
+    /// Parses the `stack` property of an error, one frame per line.
+    pub(crate) fn parse_stack(stack: &str) -> Vec<Self> {
+        stack
+            .lines()
+            .map(str::trim)
+            .filter(|frame| !frame.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+}
+
+impl fmt::Display for StackFrame {
+    /// Formats the frame like JavaScriptCore does, e.g. `fail@test.js:1:35`.
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(function) = &self.function {
+            formatter.write_str(function)?;
+        }
+
+        if let Some(source_url) = &self.source_url {
+            write!(formatter, "@{source_url}")?;
+        }
+
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            if self.source_url.is_none() {
+                formatter.write_str("@")?;
+            }
+
+            write!(formatter, ":{line}:{column}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StackFrame;
+
+    fn frame(
+        function: Option<&str>,
+        source_url: Option<&str>,
+        position: Option<(u32, u32)>,
+    ) -> StackFrame {
+        StackFrame {
+            function: function.map(str::to_owned),
+            source_url: source_url.map(str::to_owned),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        }
+    }
+
+    #[test]
+    fn parse_stack() {
+        let stack = "fail@test.js:1:35\n\
+                     @https://example.com:8080/app.js:12:3\n\
+                     map@[native code]\n\
+                     [native code]\n\
+                     global code@test.js:2:5\n\
+                     eval code\n";
+
+        assert_eq!(
+            StackFrame::parse_stack(stack),
+            vec![
+                frame(Some("fail"), Some("test.js"), Some((1, 35))),
+                frame(None, Some("https://example.com:8080/app.js"), Some((12, 3))),
+                frame(Some("map"), Some("[native code]"), None),
+                frame(None, Some("[native code]"), None),
+                frame(Some("global code"), Some("test.js"), Some((2, 5))),
+                frame(Some("eval code"), None, None),
+            ]
+        );
+        assert!(StackFrame::parse_stack("").is_empty());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for frame in [
+            "fail@test.js:1:35",
+            "@https://example.com:8080/app.js:12:3",
+            "map@[native code]",
+            "global code@:3:1",
+            "eval code",
+        ] {
+            assert_eq!(
+                StackFrame::parse_stack(frame)[0].to_string(),
+                frame,
+                "frame {frame}"
+            );
+        }
+    }
+}
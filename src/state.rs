@@ -0,0 +1,123 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::c_void,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::sys;
+
+/// The Rust state attached to a global context, like the registered source
+/// maps.
+#[derive(Default)]
+pub(crate) struct ContextState {
+    /// The source maps, by source URL.
+    pub(crate) source_maps: Mutex<HashMap<String, sourcemap::DecodedMap>>,
+}
+
+/// The states of the global contexts, by address. They aren't visible from
+/// the scripts.
+static STATES: Mutex<BTreeMap<usize, Arc<ContextState>>> = Mutex::new(BTreeMap::new());
+
+/// Locks [`STATES`], even if a thread panicked while holding it.
+fn states() -> MutexGuard<'static, BTreeMap<usize, Arc<ContextState>>> {
+    STATES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Removes the state of the global context whose address is `data`, when
+/// its global object is destroyed.
+///
+/// This happens before the address can be reused by another context.
+unsafe extern "C" fn destroyed(_map: sys::JSWeakObjectMapRef, data: *mut c_void) {
+    states().remove(&(data as usize));
+}
+
+/// Calls `f` with the state of the global context of `ctx`, creating it the
+/// first time.
+///
+/// The state is dropped with the global object of the context, once the
+/// context is released and the object collected, even if other contexts
+/// of its group are alive.
+pub(crate) fn with_state<R>(ctx: sys::JSContextRef, f: impl FnOnce(&ContextState) -> R) -> R {
+    let global_ctx = unsafe { sys::JSContextGetGlobalContext(ctx) };
+    let key = global_ctx as usize;
+
+    let state = states().get(&key).cloned();
+
+    let state = state.unwrap_or_else(|| {
+        // The map stays empty: it's only used to be told when the global
+        // object is destroyed. It's created without holding the lock, as
+        // a collection may destroy other global objects meanwhile.
+        unsafe { sys::JSWeakObjectMapCreate(global_ctx, key as *mut c_void, Some(destroyed)) };
+
+        Arc::clone(states().entry(key).or_default())
+    });
+
+    f(&state)
+}
+
+/// Calls `f` with the state of the global context of `ctx`, if it has one.
+///
+/// Contrary to [`with_state`], no state is created.
+pub(crate) fn with_existing_state<R>(
+    ctx: sys::JSContextRef,
+    f: impl FnOnce(&ContextState) -> R,
+) -> Option<R> {
+    let key = unsafe { sys::JSContextGetGlobalContext(ctx) } as usize;
+    let state = states().get(&key).cloned()?;
+
+    Some(f(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{states, with_existing_state, with_state};
+    use crate::{JSContext, JSContextGroup};
+
+    /// Tests whether the global context at `key` has a state.
+    fn has_state(key: usize) -> bool {
+        states().contains_key(&key)
+    }
+
+    fn has_source_map(ctx: &JSContext) -> bool {
+        with_existing_state(ctx.raw, |state| {
+            state.source_maps.lock().unwrap().contains_key("bundle.js")
+        })
+        .unwrap_or(false)
+    }
+
+    #[test]
+    fn states_belong_to_contexts_not_groups() {
+        let group = JSContextGroup::new();
+        let source_map = br#"{"version": 3, "sources": [], "names": [], "mappings": ""}"#;
+
+        let first = group.new_context();
+        let first_key = first.raw as usize;
+        first.register_source_map("bundle.js", source_map).unwrap();
+        assert!(has_source_map(&first));
+        drop(first);
+
+        let second = group.new_context();
+        let second_key = second.raw as usize;
+
+        // Looking the state up doesn't create it.
+        assert!(!has_source_map(&second));
+        assert!(!has_state(second_key));
+
+        with_state(second.raw, |_| ());
+        assert!(has_state(second_key));
+        assert!(!has_source_map(&second));
+
+        // Both states are dropped with their global objects, at the latest
+        // when the heap of the group is torn down.
+        drop(second);
+        drop(group);
+        assert!(!has_state(first_key));
+        assert!(!has_state(second_key));
+    }
+}
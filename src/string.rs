@@ -7,6 +7,7 @@
 use crate::{sys, JSString};
 use std::ffi::CString;
 use std::fmt;
+use std::slice;
 
 impl JSString {
     /// Return the number of Unicode characters in this JavaScript string.
@@ -37,6 +38,17 @@ impl JSString {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns the UTF-16 code units of the string, without copying them.
+    pub(crate) fn code_units(&self) -> &[u16] {
+        let length = self.len();
+
+        if length == 0 {
+            return &[];
+        }
+
+        unsafe { slice::from_raw_parts(sys::JSStringGetCharactersPtr(self.raw), length) }
+    }
 }
 
 impl fmt::Debug for JSString {
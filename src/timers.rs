@@ -0,0 +1,592 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Timers for a [`JSContext`]: `setTimeout`, `setInterval` and
+//! `queueMicrotask`.
+//!
+//! This module is available when the `timers` feature is enabled.
+//! JavaScriptCore doesn't provide any timer, as it doesn't have an event
+//! loop: [`install`] defines the timer functions on the global object of a
+//! context, and returns the [`Timers`] queue, which runs the callbacks
+//! when the embedder drives it with [`Timers::run_until_idle`] or
+//! [`Timers::run_for`].
+//!
+//! The timers follow a [`Clock`]: the system clock, or a virtual clock
+//! which jumps to the next timer instead of waiting for it, so that tests
+//! are fast and deterministic.
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use javascriptcore::{evaluate_script, timers::{self, Clock}, JSContext};
+//! let ctx = JSContext::default();
+//! let timers = timers::install(&ctx, Clock::Virtual).unwrap();
+//!
+//! evaluate_script(
+//!     &ctx,
+//!     "var log = [];
+//!      setTimeout(() => log.push('later'), 1000);
+//!      setTimeout(() => log.push('soon'), 10);
+//!      queueMicrotask(() => log.push('now'));",
+//!     None,
+//!     "test.js",
+//!     1,
+//! )
+//! .unwrap();
+//!
+//! timers.run_for(Duration::from_millis(100)).unwrap();
+//! let log = evaluate_script(&ctx, "log.join()", None, "test.js", 1).unwrap();
+//! assert_eq!(log.as_string().unwrap(), "now,soon");
+//!
+//! timers.run_until_idle().unwrap();
+//! let log = evaluate_script(&ctx, "log.join()", None, "test.js", 1).unwrap();
+//! assert_eq!(log.as_string().unwrap(), "now,soon,later");
+//! assert_eq!(timers.now(), Duration::from_secs(1));
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    rc::{Rc, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{JSContext, JSException, JSObject, JSValue, Persistent};
+
+/// The clock of the timers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Clock {
+    /// The system clock: the timers wait for their delay.
+    #[default]
+    System,
+    /// A virtual clock, starting at zero, which only advances when the
+    /// timers are run: it jumps to the next timer instead of waiting.
+    Virtual,
+}
+
+/// The queue of the timers of a context, created by [`install`].
+///
+/// The timers only run when the queue is driven, by
+/// [`Timers::run_until_idle`] or [`Timers::run_for`]. The microtasks, like
+/// the promise jobs, run after each callback.
+///
+/// Like a [`Persistent`], a pending timer keeps its callback and its
+/// context alive. The timer functions don't keep the queue alive: once all
+/// the clones of `Timers` are dropped, the pending timers are dropped too,
+/// and `setTimeout` and `setInterval` throw an `Error`.
+#[derive(Clone, Debug)]
+pub struct Timers {
+    queue: Rc<RefCell<Queue>>,
+}
+
+/// A pending timer.
+struct Timer {
+    callback: Persistent<JSObject<'static>>,
+    arguments: Vec<Persistent>,
+    deadline: Duration,
+    /// The period of the intervals.
+    interval: Option<Duration>,
+}
+
+/// The state shared by the timer functions and [`Timers`].
+#[derive(Debug)]
+struct Queue {
+    clock: Clock,
+    /// The origin of the system clock.
+    origin: Instant,
+    /// The time of the virtual clock.
+    virtual_now: Duration,
+    /// The identifier of the next timer.
+    next_id: u32,
+    /// The pending timers, by identifier.
+    timers: HashMap<u32, Timer>,
+    /// The identifiers of the pending timers, in the order they're due:
+    /// by deadline, then by scheduling order.
+    schedule: BTreeMap<(Duration, u64), u32>,
+    /// The number of timers scheduled so far.
+    scheduled: u64,
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Timer")
+            .field("deadline", &self.deadline)
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Queue {
+    fn now(&self) -> Duration {
+        match self.clock {
+            Clock::System => self.origin.elapsed(),
+            Clock::Virtual => self.virtual_now,
+        }
+    }
+
+    /// Schedules the timer `id`.
+    fn schedule(&mut self, id: u32, timer: Timer) {
+        self.scheduled += 1;
+        self.schedule.insert((timer.deadline, self.scheduled), id);
+        self.timers.insert(id, timer);
+    }
+
+    /// Removes the next timer due at or before `limit`, if any, skipping
+    /// the cleared timers.
+    fn pop(&mut self, limit: Option<Duration>) -> Option<(u32, Timer)> {
+        loop {
+            let entry = self.schedule.first_entry()?;
+            let (deadline, _) = *entry.key();
+
+            if limit.is_some_and(|limit| deadline > limit) {
+                return None;
+            }
+
+            let id = entry.remove();
+
+            // A timer cleared, or rescheduled, leaves a stale entry.
+            match self.timers.remove(&id) {
+                Some(timer) if timer.deadline == deadline => return Some((id, timer)),
+                Some(timer) => {
+                    self.timers.insert(id, timer);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Installs `setTimeout`, `clearTimeout`, `setInterval`, `clearInterval`
+/// and `queueMicrotask` on the global object of `ctx`, and returns the
+/// queue of the timers.
+///
+/// The `queueMicrotask` of JavaScriptCore is kept if it provides one.
+///
+/// The callbacks must be functions, as strings aren't evaluated. The delays
+/// are in milliseconds, and an interval lasts one millisecond at least.
+pub fn install(ctx: &JSContext, clock: Clock) -> Result<Timers, JSException> {
+    let queue = Rc::new(RefCell::new(Queue {
+        clock,
+        origin: Instant::now(),
+        virtual_now: Duration::ZERO,
+        next_id: 1,
+        timers: HashMap::new(),
+        schedule: BTreeMap::new(),
+        scheduled: 0,
+    }));
+    let global = ctx.global_object()?;
+
+    for (name, repeat) in [("setTimeout", false), ("setInterval", true)] {
+        let queue = Rc::downgrade(&queue);
+        let function =
+            JSValue::new_function_from_closure(ctx, name, move |ctx, _this, arguments| {
+                let queue = queue
+                    .upgrade()
+                    .ok_or_else(|| JSException::new_error(ctx, "the timers have been dropped"))?;
+                let id = set_timer(&queue, ctx, arguments, repeat)?;
+
+                Ok(JSValue::new_number(ctx, f64::from(id)))
+            });
+
+        global.set_property(name, function)?;
+    }
+
+    for name in ["clearTimeout", "clearInterval"] {
+        let queue = Rc::downgrade(&queue);
+        let function =
+            JSValue::new_function_from_closure(ctx, name, move |ctx, _this, arguments| {
+                if let Some(id) = arguments.first() {
+                    let id = id.as_number()?;
+
+                    if id.fract() == 0. && id >= 1. && id <= f64::from(u32::MAX) {
+                        if let Some(queue) = Weak::upgrade(&queue) {
+                            queue.borrow_mut().timers.remove(&(id as u32));
+                        }
+                    }
+                }
+
+                Ok(JSValue::new_undefined(ctx))
+            });
+
+        global.set_property(name, function)?;
+    }
+
+    if !global.get_property("queueMicrotask").is_object() {
+        let function =
+            JSValue::new_function_from_closure(ctx, "queueMicrotask", |ctx, _this, arguments| {
+                let callback = callback(ctx, arguments)?;
+
+                // A reaction of a fulfilled promise is a microtask.
+                let promise_constructor =
+                    ctx.global_object()?.get_property("Promise").as_object()?;
+                let promise = promise_constructor
+                    .get_property("resolve")
+                    .as_object()?
+                    .call_as_function(Some(&promise_constructor), &[])?
+                    .as_object()?;
+                promise
+                    .get_property("then")
+                    .as_object()?
+                    .call_as_function(Some(&promise), &[callback.into()])?;
+
+                Ok(JSValue::new_undefined(ctx))
+            });
+
+        global.set_property("queueMicrotask", function)?;
+    }
+
+    Ok(Timers { queue })
+}
+
+/// Returns the callback of a timer, the first of `arguments`.
+fn callback<'ctx>(
+    ctx: &'ctx JSContext,
+    arguments: &[JSValue<'ctx>],
+) -> Result<JSObject<'ctx>, JSException> {
+    arguments
+        .first()
+        .and_then(|callback| callback.as_object().ok())
+        .filter(JSObject::is_function)
+        .ok_or_else(|| JSException::new_type_error(ctx, "the callback must be a function"))
+}
+
+/// Schedules the timer of `setTimeout` or `setInterval`, and returns its
+/// identifier.
+fn set_timer(
+    queue: &RefCell<Queue>,
+    ctx: &JSContext,
+    arguments: &[JSValue<'_>],
+    repeat: bool,
+) -> Result<u32, JSException> {
+    let callback = callback(ctx, arguments)?.to_persistent();
+
+    // Like in browsers, invalid delays are zero.
+    let delay = match arguments.get(1) {
+        Some(delay) => delay.as_number()?,
+        None => 0.,
+    };
+    let delay = if delay.is_finite() && delay > 0. {
+        Duration::from_secs_f64(delay.min(f64::from(u32::MAX)) / 1000.)
+    } else {
+        Duration::ZERO
+    };
+
+    let mut queue = queue.borrow_mut();
+    let id = queue.next_id;
+    queue.next_id = queue.next_id.checked_add(1).unwrap_or(1);
+    let deadline = queue.now() + delay;
+
+    queue.schedule(
+        id,
+        Timer {
+            callback,
+            arguments: arguments
+                .iter()
+                .skip(2)
+                .map(JSValue::to_persistent)
+                .collect(),
+            deadline,
+            interval: repeat.then(|| delay.max(Duration::from_millis(1))),
+        },
+    );
+
+    Ok(id)
+}
+
+impl Timers {
+    /// Returns the time elapsed since the timers have been installed, on
+    /// their clock.
+    pub fn now(&self) -> Duration {
+        self.queue.borrow().now()
+    }
+
+    /// Returns whether no timer is pending.
+    pub fn is_idle(&self) -> bool {
+        self.queue.borrow().timers.is_empty()
+    }
+
+    /// Runs the timers until none is pending, waiting for them with the
+    /// system clock.
+    ///
+    /// This doesn't return while an interval is set, unless a callback
+    /// throws: the exception is returned, and the other timers remain
+    /// pending.
+    pub fn run_until_idle(&self) -> Result<(), JSException> {
+        while self.run_next(None)? {}
+
+        Ok(())
+    }
+
+    /// Runs the timers due within `duration`, waiting for them with the
+    /// system clock, and advances the clock by `duration`.
+    ///
+    /// If a callback throws, its exception is returned right away, and the
+    /// other timers remain pending.
+    pub fn run_for(&self, duration: Duration) -> Result<(), JSException> {
+        let limit = self.now() + duration;
+
+        while self.run_next(Some(limit))? {}
+
+        let mut queue = self.queue.borrow_mut();
+        match queue.clock {
+            Clock::System => {
+                let now = queue.now();
+                drop(queue);
+
+                if let Some(remaining) = limit.checked_sub(now) {
+                    thread::sleep(remaining);
+                }
+            }
+            Clock::Virtual => queue.virtual_now = queue.virtual_now.max(limit),
+        }
+
+        Ok(())
+    }
+
+    /// Runs the next timer due at or before `limit`, if any, and returns
+    /// whether one has been run.
+    fn run_next(&self, limit: Option<Duration>) -> Result<bool, JSException> {
+        let (id, timer) = {
+            let mut queue = self.queue.borrow_mut();
+            let Some((id, timer)) = queue.pop(limit) else {
+                return Ok(false);
+            };
+
+            match queue.clock {
+                Clock::System => {
+                    let now = queue.now();
+                    drop(queue);
+
+                    if let Some(remaining) = timer.deadline.checked_sub(now) {
+                        thread::sleep(remaining);
+                    }
+                }
+                Clock::Virtual => queue.virtual_now = queue.virtual_now.max(timer.deadline),
+            }
+
+            (id, timer)
+        };
+
+        // The callback may set or clear timers, so the queue isn't borrowed
+        // while it runs. An interval is rescheduled first, so that it can
+        // clear itself.
+        let callback = timer.callback.clone();
+        let arguments = timer.arguments.clone();
+
+        if let Some(interval) = timer.interval {
+            let mut queue = self.queue.borrow_mut();
+            let deadline = queue.now().max(timer.deadline) + interval;
+
+            queue.schedule(id, Timer { deadline, ..timer });
+        }
+
+        let callback = callback.get();
+        let arguments = arguments
+            .iter()
+            .map(|argument| argument.get())
+            .collect::<Vec<_>>();
+        callback.call_as_function(None, &arguments)?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use super::{install, Clock};
+    use crate::{evaluate_script, JSContext, JSValue};
+
+    fn log(ctx: &JSContext) -> String {
+        evaluate_script(ctx, "log.join()", None, "test.js", 1)
+            .unwrap()
+            .as_string()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn timeouts_run_in_order() {
+        let ctx = JSContext::default();
+        let timers = install(&ctx, Clock::Virtual).unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var log = [];
+             setTimeout(() => log.push('c'), 20);
+             setTimeout(() => log.push('a'), 10);
+             setTimeout((first, second) => log.push(first + second), 10, 'b', '!');
+             const cleared = setTimeout(() => log.push('cleared'), 5);
+             clearTimeout(cleared);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        assert!(!timers.is_idle());
+
+        timers.run_for(Duration::from_millis(15)).unwrap();
+        assert_eq!(log(&ctx), "a,b!");
+        assert_eq!(timers.now(), Duration::from_millis(15));
+
+        timers.run_until_idle().unwrap();
+        assert_eq!(log(&ctx), "a,b!,c");
+        assert_eq!(timers.now(), Duration::from_millis(20));
+        assert!(timers.is_idle());
+    }
+
+    #[test]
+    fn intervals_repeat_until_cleared() {
+        let ctx = JSContext::default();
+        let timers = install(&ctx, Clock::Virtual).unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var log = [];
+             const interval = setInterval(() => {
+                 log.push(log.length);
+                 if (log.length === 3) clearInterval(interval);
+             }, 100);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        timers.run_until_idle().unwrap();
+        assert_eq!(log(&ctx), "0,1,2");
+        assert_eq!(timers.now(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn microtasks_run_before_timers() {
+        let ctx = JSContext::default();
+        let timers = install(&ctx, Clock::Virtual).unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var log = [];
+             setTimeout(() => {
+                 log.push('timeout');
+                 queueMicrotask(() => log.push('microtask'));
+                 Promise.resolve().then(() => log.push('promise'));
+             });
+             setTimeout(() => log.push('next timeout'));",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        timers.run_until_idle().unwrap();
+        assert_eq!(log(&ctx), "timeout,microtask,promise,next timeout");
+        assert_eq!(timers.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn exceptions_stop_the_timers() {
+        let ctx = JSContext::default();
+        let timers = install(&ctx, Clock::Virtual).unwrap();
+
+        evaluate_script(
+            &ctx,
+            "var log = [];
+             setTimeout(() => { throw new RangeError('oops') }, 10);
+             setTimeout(() => log.push('after'), 20);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        let exception = timers.run_until_idle().unwrap_err();
+        assert_eq!(exception.name().unwrap(), "RangeError");
+        assert_eq!(log(&ctx), "");
+
+        timers.run_until_idle().unwrap();
+        assert_eq!(log(&ctx), "after");
+    }
+
+    #[test]
+    fn invalid_callbacks() {
+        let ctx = JSContext::default();
+        install(&ctx, Clock::Virtual).unwrap();
+
+        let exception =
+            evaluate_script(&ctx, "setTimeout('log()', 10)", None, "test.js", 1).unwrap_err();
+        assert_eq!(exception.name().unwrap(), "TypeError");
+    }
+
+    #[test]
+    fn system_clock() {
+        let ctx = JSContext::default();
+        let timers = install(&ctx, Clock::System).unwrap();
+        let start = Instant::now();
+
+        evaluate_script(
+            &ctx,
+            "var log = []; setTimeout(() => log.push('done'), 5);",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+
+        timers.run_until_idle().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        assert_eq!(log(&ctx), "done");
+    }
+
+    #[test]
+    fn dropping_timers_releases_pending_callbacks() {
+        struct Guard(Rc<Cell<bool>>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(false));
+
+        {
+            let ctx = JSContext::default();
+            let timers = install(&ctx, Clock::Virtual).unwrap();
+
+            let guard = Guard(Rc::clone(&dropped));
+            let callback =
+                JSValue::new_function_from_closure(&ctx, "callback", move |ctx, _this, _args| {
+                    let _ = &guard;
+
+                    Ok(JSValue::new_undefined(ctx))
+                });
+            ctx.global_object()
+                .unwrap()
+                .set_property("callback", callback)
+                .unwrap();
+
+            evaluate_script(&ctx, "setInterval(callback, 10)", None, "test.js", 1).unwrap();
+            assert!(!timers.clone().is_idle());
+
+            drop(timers);
+            let exception =
+                evaluate_script(&ctx, "setTimeout(callback)", None, "test.js", 1).unwrap_err();
+            assert_eq!(exception.message().unwrap(), "the timers have been dropped");
+            evaluate_script(&ctx, "clearInterval(1)", None, "test.js", 1).unwrap();
+            assert!(!dropped.get());
+        }
+
+        // The interval doesn't keep the context alive anymore.
+        assert!(dropped.get());
+    }
+}
@@ -5,16 +5,20 @@
 // except according to those terms.
 
 use crate::{sys, JSException, JSObject, JSTypedArray, JSTypedArrayType, JSValue};
-use std::{ptr, slice};
+use std::{marker::PhantomData, ptr, slice};
 
-impl JSTypedArray {
+impl JSTypedArray<'_> {
     /// Create a new [`Self`] from its raw pointer directly.
     ///
     /// # Safety
     ///
     /// Ensure `raw` is valid, and represents a typed array.
     pub(crate) const unsafe fn from_raw(ctx: sys::JSContextRef, raw: sys::JSObjectRef) -> Self {
-        Self { raw, ctx }
+        Self {
+            raw,
+            ctx,
+            context: PhantomData,
+        }
     }
 
     /// Returns a value of type [`JSTypedArrayType`] that identifies value's
@@ -121,6 +125,10 @@ impl JSTypedArray {
     /// Returns a mutable slice of the underlying buffer represented by the
     /// Typed Array.
     ///
+    /// The slice holds all the bytes of the Typed Array, i.e.
+    /// [`byte_length()`](Self::byte_length) bytes, whatever its type: a
+    /// `Uint16Array` of 2 items is a slice of 4 bytes.
+    ///
     /// # Safety
     ///
     /// The pointer of the slice returned by this function is temporary and is not
@@ -171,8 +179,37 @@ impl JSTypedArray {
     }
 
     unsafe fn as_mut_slice_impl(&self) -> Result<&mut [u8], JSException> {
+        let (ptr, length) = self.typed_slice_parts(None)?;
+
+        Ok(slice::from_raw_parts_mut(ptr, length))
+    }
+
+    /// Returns the pointer to the first `T` of the underlying buffer, and
+    /// the number of items, after checking the Typed Array has the type
+    /// `expected`, if any.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `T` is the element type of `expected`, or `u8` if there is no
+    /// expected type.
+    unsafe fn typed_slice_parts<T>(
+        &self,
+        expected: Option<JSTypedArrayType>,
+    ) -> Result<(*mut T, usize), JSException> {
+        if let Some(expected) = expected {
+            let ty = self.ty()?;
+
+            if ty != expected {
+                return Err(JSException::new_builtin(
+                    self.ctx,
+                    "TypeError",
+                    &format!("expected a `{expected:?}`, got a `{ty:?}`"),
+                ));
+            }
+        }
+
         let offset = self.byte_offset()?;
-        let length = self.len()?;
+        let length = self.byte_length()? / size_of::<T>();
 
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let ptr = sys::JSObjectGetTypedArrayBytesPtr(self.ctx, self.raw, &mut exception);
@@ -182,16 +219,60 @@ impl JSTypedArray {
         } else {
             assert!(!ptr.is_null(), "`ptr` must not be null");
 
-            Ok(slice::from_raw_parts_mut(
-                ptr.offset(offset.try_into().unwrap()).cast::<u8>(),
-                length,
-            ))
+            Ok((ptr.cast::<u8>().add(offset).cast::<T>(), length))
         }
     }
 
+    /// Returns a mutable slice of the integers of a `BigInt64Array`.
+    ///
+    /// Returns a `TypeError` if the Typed Array is of another type.
+    ///
+    /// # Safety
+    ///
+    /// The pointer of the slice returned by this function is temporary and is not
+    /// guaranteed to remain valid across JavaScriptCore API calls.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let mut array = evaluate_script(&ctx, "new BigInt64Array([1n, -2n])", None, "foo.js", 1)
+    ///     .unwrap()
+    ///     .as_typed_array()
+    ///     .unwrap();
+    ///
+    /// let slice = unsafe { array.as_mut_i64_slice() }.unwrap();
+    /// slice[0] = i64::MIN;
+    ///
+    /// assert_eq!(array.to_i64_vec().unwrap(), &[i64::MIN, -2]);
+    /// ```
+    pub unsafe fn as_mut_i64_slice(&mut self) -> Result<&mut [i64], JSException> {
+        let (ptr, length) = self.typed_slice_parts(Some(JSTypedArrayType::BigInt64Array))?;
+
+        Ok(slice::from_raw_parts_mut(ptr, length))
+    }
+
+    /// Returns a mutable slice of the integers of a `BigUint64Array`.
+    ///
+    /// Returns a `TypeError` if the Typed Array is of another type.
+    ///
+    /// # Safety
+    ///
+    /// The pointer of the slice returned by this function is temporary and is not
+    /// guaranteed to remain valid across JavaScriptCore API calls.
+    pub unsafe fn as_mut_u64_slice(&mut self) -> Result<&mut [u64], JSException> {
+        let (ptr, length) = self.typed_slice_parts(Some(JSTypedArrayType::BigUint64Array))?;
+
+        Ok(slice::from_raw_parts_mut(ptr, length))
+    }
+
     /// Returns a `Vec` (so a copy) of the underlying buffer represented by the
     /// Typed Array.
     ///
+    /// Like [`as_mut_slice()`](Self::as_mut_slice), this holds all the bytes
+    /// of the Typed Array, whatever its type.
+    ///
     /// ```rust
     /// # use javascriptcore::*;
     /// let ctx = JSContext::default();
@@ -202,21 +283,59 @@ impl JSTypedArray {
     ///     .unwrap();
     ///
     /// assert_eq!(array.to_vec().unwrap(), &[2, 3, 4]);
+    ///
+    /// let array = evaluate_script(&ctx, "new Uint16Array([1, 0x0100])", None, "foo.js", 1)
+    ///     .unwrap()
+    ///     .as_typed_array()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(array.to_vec().unwrap().len(), 4);
     /// ```
     pub fn to_vec(&self) -> Result<Vec<u8>, JSException> {
         Ok(unsafe { self.as_mut_slice_impl() }?.to_vec())
     }
+
+    /// Returns a `Vec` (so a copy) of the integers of a `BigInt64Array`.
+    ///
+    /// Returns a `TypeError` if the Typed Array is of another type.
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let array = evaluate_script(&ctx, "new BigInt64Array([1n, -2n, 3n])", None, "foo.js", 1)
+    ///     .unwrap()
+    ///     .as_typed_array()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(array.to_i64_vec().unwrap(), &[1, -2, 3]);
+    /// ```
+    pub fn to_i64_vec(&self) -> Result<Vec<i64>, JSException> {
+        let (ptr, length) =
+            unsafe { self.typed_slice_parts::<i64>(Some(JSTypedArrayType::BigInt64Array)) }?;
+
+        Ok(unsafe { slice::from_raw_parts(ptr, length) }.to_vec())
+    }
+
+    /// Returns a `Vec` (so a copy) of the integers of a `BigUint64Array`.
+    ///
+    /// Returns a `TypeError` if the Typed Array is of another type.
+    pub fn to_u64_vec(&self) -> Result<Vec<u64>, JSException> {
+        let (ptr, length) =
+            unsafe { self.typed_slice_parts::<u64>(Some(JSTypedArrayType::BigUint64Array)) }?;
+
+        Ok(unsafe { slice::from_raw_parts(ptr, length) }.to_vec())
+    }
 }
 
-impl From<&JSTypedArray> for JSObject {
-    fn from(array: &JSTypedArray) -> Self {
+impl<'ctx> From<&JSTypedArray<'ctx>> for JSObject<'ctx> {
+    fn from(array: &JSTypedArray<'ctx>) -> Self {
         // SAFETY: `ctx` and `raw` is valid, it's safe to use them.
         unsafe { JSObject::from_raw(array.ctx, array.raw) }
     }
 }
 
-impl From<JSTypedArray> for JSObject {
-    fn from(array: JSTypedArray) -> Self {
+impl<'ctx> From<JSTypedArray<'ctx>> for JSObject<'ctx> {
+    fn from(array: JSTypedArray<'ctx>) -> Self {
         (&array).into()
     }
 }
@@ -365,6 +484,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn as_mut_slice_covers_all_bytes() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+        let mut array = evaluate_script(&ctx, "new Uint16Array([1, 2])", None, "foo.js", 1)?
+            .as_typed_array()?;
+
+        assert_eq!(array.len()?, 2);
+        assert_eq!(unsafe { array.as_mut_slice()? }.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bigint_arrays() -> Result<(), JSException> {
+        let ctx = JSContext::default();
+
+        let array = JSValue::new_bigint64_array(&ctx, &[i64::MIN, 0, i64::MAX])?;
+        ctx.global_object()?.set_property("signed", array)?;
+        let array = JSValue::new_biguint64_array(&ctx, &[0, u64::MAX])?;
+        ctx.global_object()?.set_property("unsigned", array)?;
+
+        let result = evaluate_script(
+            &ctx,
+            "signed[0] === -(2n ** 63n) && unsigned[1] === 2n ** 64n - 1n",
+            None,
+            "foo.js",
+            1,
+        )?;
+        assert!(result.as_boolean());
+
+        let mut array =
+            evaluate_script(&ctx, "signed.subarray(1)", None, "foo.js", 1)?.as_typed_array()?;
+        assert_eq!(unsafe { array.as_mut_i64_slice() }?, &[0, i64::MAX]);
+
+        // The element type is checked.
+        assert!(array.to_u64_vec().is_err());
+        assert!(unsafe { array.as_mut_u64_slice() }.is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn as_mut_slice_is_mutable() -> Result<(), JSException> {
         let ctx = JSContext::default();
@@ -7,19 +7,33 @@
 use sys::JSObjectCallAsFunctionCallback;
 
 use crate::{
-    sys, JSClass, JSContext, JSException, JSObject, JSString, JSType, JSTypedArray,
-    JSTypedArrayType, JSValue,
+    closure::{self, Closure},
+    convert, sys, JSClass, JSContext, JSException, JSObject, JSString, JSType, JSTypedArray,
+    JSTypedArrayType, JSValue, Persistent,
 };
-use std::ptr;
+use std::{
+    cell::RefCell,
+    cmp,
+    marker::PhantomData,
+    ptr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The largest time value of a `Date`, in milliseconds: 100,000,000 days.
+const MAX_TIME_VALUE: f64 = 8.64e15;
 
-impl JSValue {
+impl<'ctx> JSValue<'ctx> {
     /// Create a new [`Self`] from its raw pointer directly.
     ///
     /// # Safety
     ///
     /// Ensure `raw` is valid.
     pub const unsafe fn from_raw(ctx: sys::JSContextRef, raw: sys::JSValueRef) -> Self {
-        Self { raw, ctx }
+        Self {
+            raw,
+            ctx,
+            context: PhantomData,
+        }
     }
 
     /// Creates a JavaScript value of the `undefined` type.
@@ -39,7 +53,7 @@ impl JSValue {
     /// # See also
     ///
     /// - [`JSValue::is_undefined()`]
-    pub fn new_undefined(ctx: &JSContext) -> Self {
+    pub fn new_undefined(ctx: &'ctx JSContext) -> Self {
         unsafe { Self::from_raw(ctx.raw, sys::JSValueMakeUndefined(ctx.raw)) }
     }
 
@@ -60,7 +74,7 @@ impl JSValue {
     /// # See also
     ///
     /// - [`JSValue::is_null()`]
-    pub fn new_null(ctx: &JSContext) -> Self {
+    pub fn new_null(ctx: &'ctx JSContext) -> Self {
         unsafe { Self::from_raw(ctx.raw, sys::JSValueMakeNull(ctx.raw)) }
     }
 
@@ -83,7 +97,7 @@ impl JSValue {
     ///
     /// - [`JSValue::is_boolean()`]
     /// - [`JSValue::new_boolean()`]
-    pub fn new_boolean(ctx: &JSContext, boolean: bool) -> Self {
+    pub fn new_boolean(ctx: &'ctx JSContext, boolean: bool) -> Self {
         unsafe { Self::from_raw(ctx.raw, sys::JSValueMakeBoolean(ctx.raw, boolean)) }
     }
 
@@ -112,7 +126,7 @@ impl JSValue {
     ///
     /// - [`JSValue::is_number()`]
     /// - [`JSValue::new_number()`]
-    pub fn new_number(ctx: &JSContext, number: f64) -> Self {
+    pub fn new_number(ctx: &'ctx JSContext, number: f64) -> Self {
         unsafe { Self::from_raw(ctx.raw, sys::JSValueMakeNumber(ctx.raw, number)) }
     }
 
@@ -137,7 +151,7 @@ impl JSValue {
     ///
     /// - [`JSValue::is_string()`]
     /// - [`JSValue::new_string()`]
-    pub fn new_string<S: Into<JSString>>(ctx: &JSContext, string: S) -> Self {
+    pub fn new_string<S: Into<JSString>>(ctx: &'ctx JSContext, string: S) -> Self {
         Self::new_string_inner(ctx.raw, string)
     }
 
@@ -148,6 +162,240 @@ impl JSValue {
         unsafe { Self::from_raw(ctx, sys::JSValueMakeString(ctx, string.into().raw)) }
     }
 
+    /// Tests whether this value can be used in `ctx`, i.e. whether both
+    /// belong to the same [`JSContextGroup`](crate::JSContextGroup): the
+    /// contexts of a group share their values, but the values of other
+    /// groups are unknown to their heap.
+    pub(crate) fn same_group(&self, ctx: sys::JSContextRef) -> bool {
+        self.ctx == ctx
+            || unsafe { sys::JSContextGetGroup(self.ctx) == sys::JSContextGetGroup(ctx) }
+    }
+
+    /// Returns a `TypeError` if this value cannot be used in `ctx`, see
+    /// [`Self::same_group`].
+    pub(crate) fn check_context(&self, ctx: sys::JSContextRef) -> Result<(), JSException> {
+        if self.same_group(ctx) {
+            return Ok(());
+        }
+
+        Err(JSException::new_builtin(
+            ctx,
+            "TypeError",
+            "the value belongs to another context group",
+        ))
+    }
+
+    /// Creates a JavaScript value of the `bigint` type from an integral
+    /// double.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `number`: The value of the `BigInt`. A `RangeError` is thrown if it
+    ///   isn't an integer.
+    ///
+    /// Requires the `bigint` feature.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint_from_f64(&ctx, 42.).unwrap();
+    /// assert!(v.is_bigint());
+    ///
+    /// assert!(JSValue::new_bigint_from_f64(&ctx, 0.5).is_err());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::is_bigint()`]
+    #[cfg(feature = "bigint")]
+    pub fn new_bigint_from_f64(ctx: &'ctx JSContext, number: f64) -> Result<Self, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let value = unsafe { sys::JSBigIntCreateWithDouble(ctx.raw, number, &mut exception) };
+
+        unsafe { Self::from_raw_or_exception(ctx.raw, value, exception) }
+    }
+
+    /// Creates a JavaScript value of the `bigint` type from an `i64`.
+    ///
+    /// Requires the `bigint` feature.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint_from_i64(&ctx, i64::MIN).unwrap();
+    /// assert_eq!(v.as_i64().unwrap(), i64::MIN);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_i64()`]
+    #[cfg(feature = "bigint")]
+    pub fn new_bigint_from_i64(ctx: &'ctx JSContext, integer: i64) -> Result<Self, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let value = unsafe { sys::JSBigIntCreateWithInt64(ctx.raw, integer, &mut exception) };
+
+        unsafe { Self::from_raw_or_exception(ctx.raw, value, exception) }
+    }
+
+    /// Creates a JavaScript value of the `bigint` type from a `u64`.
+    ///
+    /// Requires the `bigint` feature.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint_from_u64(&ctx, u64::MAX).unwrap();
+    /// assert_eq!(v.as_u64().unwrap(), u64::MAX);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_u64()`]
+    #[cfg(feature = "bigint")]
+    pub fn new_bigint_from_u64(ctx: &'ctx JSContext, integer: u64) -> Result<Self, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let value = unsafe { sys::JSBigIntCreateWithUInt64(ctx.raw, integer, &mut exception) };
+
+        unsafe { Self::from_raw_or_exception(ctx.raw, value, exception) }
+    }
+
+    /// Creates a JavaScript value of the `bigint` type from an `i128`.
+    ///
+    /// Requires the `bigint` feature.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint_from_i128(&ctx, i128::MAX).unwrap();
+    /// assert_eq!(v.as_i128().unwrap(), i128::MAX);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_i128()`]
+    #[cfg(feature = "bigint")]
+    pub fn new_bigint_from_i128(ctx: &'ctx JSContext, integer: i128) -> Result<Self, JSException> {
+        Self::new_bigint_from_str(ctx, integer.to_string())
+    }
+
+    /// Creates a JavaScript value of the `bigint` type from a string, as
+    /// `BigInt(string)` would.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `string`: The representation of the `BigInt`, e.g. `"-123"` or
+    ///   `"0x7b"`. A `SyntaxError` is thrown if it isn't a valid integer.
+    ///
+    /// Requires the `bigint` feature.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint_from_str(&ctx, "123456789012345678901234567890").unwrap();
+    /// assert_eq!(v.as_string().unwrap(), "123456789012345678901234567890");
+    ///
+    /// assert!(JSValue::new_bigint_from_str(&ctx, "abc").is_err());
+    /// ```
+    #[cfg(feature = "bigint")]
+    pub fn new_bigint_from_str<S: Into<JSString>>(
+        ctx: &'ctx JSContext,
+        string: S,
+    ) -> Result<Self, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let value =
+            unsafe { sys::JSBigIntCreateWithString(ctx.raw, string.into().raw, &mut exception) };
+
+        unsafe { Self::from_raw_or_exception(ctx.raw, value, exception) }
+    }
+
+    /// Wraps `raw`, or returns `exception` if `raw` is null.
+    ///
+    /// # Safety
+    ///
+    /// Ensure `ctx` is valid, and `raw` or `exception` is valid.
+    #[cfg(feature = "bigint")]
+    unsafe fn from_raw_or_exception(
+        ctx: sys::JSContextRef,
+        raw: sys::JSValueRef,
+        exception: sys::JSValueRef,
+    ) -> Result<Self, JSException> {
+        if raw.is_null() {
+            Err(Self::from_raw(ctx, exception).into())
+        } else {
+            Ok(Self::from_raw(ctx, raw))
+        }
+    }
+
+    /// Creates a `BigInt64Array` holding a copy of `integers`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_bigint64_array(&ctx, &[-1, 2, 3]).unwrap();
+    /// let array = v.as_typed_array().unwrap();
+    ///
+    /// assert_eq!(array.ty().unwrap(), JSTypedArrayType::BigInt64Array);
+    /// assert_eq!(array.to_i64_vec().unwrap(), &[-1, 2, 3]);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSTypedArray::to_i64_vec()`]
+    pub fn new_bigint64_array(ctx: &'ctx JSContext, integers: &[i64]) -> Result<Self, JSException> {
+        let mut array =
+            Self::new_typed_array(ctx, JSTypedArrayType::BigInt64Array, integers.len())?;
+        unsafe { array.as_mut_i64_slice() }?.copy_from_slice(integers);
+
+        Ok(JSObject::from(array).into())
+    }
+
+    /// Creates a `BigUint64Array` holding a copy of `integers`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_biguint64_array(&ctx, &[1, 2, u64::MAX]).unwrap();
+    /// let array = v.as_typed_array().unwrap();
+    ///
+    /// assert_eq!(array.ty().unwrap(), JSTypedArrayType::BigUint64Array);
+    /// assert_eq!(array.to_u64_vec().unwrap(), &[1, 2, u64::MAX]);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSTypedArray::to_u64_vec()`]
+    pub fn new_biguint64_array(
+        ctx: &'ctx JSContext,
+        integers: &[u64],
+    ) -> Result<Self, JSException> {
+        let mut array =
+            Self::new_typed_array(ctx, JSTypedArrayType::BigUint64Array, integers.len())?;
+        unsafe { array.as_mut_u64_slice() }?.copy_from_slice(integers);
+
+        Ok(JSObject::from(array).into())
+    }
+
+    /// Creates a zero-filled Typed Array of `length` elements.
+    fn new_typed_array(
+        ctx: &'ctx JSContext,
+        ty: JSTypedArrayType,
+        length: usize,
+    ) -> Result<JSTypedArray<'ctx>, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let result = unsafe { sys::JSObjectMakeTypedArray(ctx.raw, ty, length, &mut exception) };
+
+        if result.is_null() {
+            Err(unsafe { Self::from_raw(ctx.raw, exception) }.into())
+        } else {
+            Ok(unsafe { JSTypedArray::from_raw(ctx.raw, result) })
+        }
+    }
+
     /// Creates a JavaScript value of the `symbol` type.
     ///
     /// * `ctx`: The execution context to use.
@@ -167,7 +415,7 @@ impl JSValue {
     /// # See also
     ///
     /// - [`JSValue::is_symbol()`]
-    pub fn new_symbol<S: Into<JSString>>(ctx: &JSContext, description: S) -> Self {
+    pub fn new_symbol<S: Into<JSString>>(ctx: &'ctx JSContext, description: S) -> Self {
         unsafe {
             Self::from_raw(
                 ctx.raw,
@@ -196,11 +444,11 @@ impl JSValue {
     /// # See also
     ///
     /// - [`JSValue::is_array()`]
-    pub fn new_array(ctx: &JSContext, items: &[JSValue]) -> Result<Self, JSException> {
+    pub fn new_array(ctx: &'ctx JSContext, items: &[JSValue<'ctx>]) -> Result<Self, JSException> {
         let items = items
             .iter()
-            .map(|argument| argument.raw)
-            .collect::<Vec<_>>();
+            .map(|item| item.check_context(ctx.raw).map(|()| item.raw))
+            .collect::<Result<Vec<_>, _>>()?;
         let mut exception: sys::JSValueRef = ptr::null_mut();
 
         let result = unsafe {
@@ -217,12 +465,74 @@ impl JSValue {
         }
 
         if result.is_null() {
-            return Err(Self::new_string(ctx, "Failed to make a new array").into());
+            return Err(JSException::new_error(ctx, "Failed to make a new array"));
         }
 
         Ok(unsafe { Self::from_raw(ctx.raw, result) })
     }
 
+    /// Creates a JavaScript `Date` representing `time`.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `time`: The time of the date. It's rounded down to the
+    ///   millisecond, the precision of a `Date`, even before the epoch.
+    ///
+    /// Returns either the `Date`, or a `RangeError` if `time` is more than
+    /// 100,000,000 days away from the Unix epoch.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let ctx = JSContext::default();
+    /// let time = UNIX_EPOCH + Duration::from_millis(1_500_000_000_000);
+    ///
+    /// let v = JSValue::new_date(&ctx, time).unwrap();
+    /// assert!(v.is_date());
+    /// assert_eq!(v.as_date().unwrap(), time);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_date()`]
+    /// - [`JSValue::is_date()`]
+    pub fn new_date(ctx: &'ctx JSContext, time: SystemTime) -> Result<Self, JSException> {
+        let nanos = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_nanos() as i128,
+            Err(error) => -(error.duration().as_nanos() as i128),
+        };
+
+        // Like the other conversions, round towards the past, even before
+        // the epoch.
+        #[allow(clippy::cast_precision_loss)]
+        Self::new_date_from_timestamp(ctx, nanos.div_euclid(1_000_000) as f64)
+    }
+
+    /// Creates a JavaScript `Date` from its time value, in milliseconds
+    /// since the Unix epoch.
+    pub(crate) fn new_date_from_timestamp(
+        ctx: &'ctx JSContext,
+        millis: f64,
+    ) -> Result<Self, JSException> {
+        if millis.is_nan() || millis.abs() > MAX_TIME_VALUE {
+            return Err(JSException::new_builtin(
+                ctx.raw,
+                "RangeError",
+                &format!("{millis}ms since the Unix epoch is out of the range of a `Date`"),
+            ));
+        }
+
+        let argument = unsafe { sys::JSValueMakeNumber(ctx.raw, millis) };
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let result = unsafe { sys::JSObjectMakeDate(ctx.raw, 1, &argument, &mut exception) };
+
+        if result.is_null() {
+            Err(unsafe { Self::from_raw(ctx.raw, exception) }.into())
+        } else {
+            Ok(unsafe { Self::from_raw(ctx.raw, result) })
+        }
+    }
+
     /// Creates a JavaScript value of the `TypedArray` type.
     ///
     /// * `ctx`: The execution context to use.
@@ -256,7 +566,7 @@ impl JSValue {
     /// - [`JSValue::as_typed_array()`]
     /// - [`JSValue::is_typed_array()`]
     pub unsafe fn new_typed_array_with_bytes(
-        ctx: &JSContext,
+        ctx: &'ctx JSContext,
         // `&mut` instead of &` because the typed array borrows mutably the bytes.
         //
         // The argument is named `_bytes` instead of `bytes` to avoid a
@@ -284,7 +594,10 @@ impl JSValue {
         }
 
         if result.is_null() {
-            return Err(Self::new_string(ctx, "Failed to make a new typed array").into());
+            return Err(JSException::new_error(
+                ctx,
+                "Failed to make a new typed array",
+            ));
         }
 
         Ok(Self::from_raw(ctx.raw, result))
@@ -312,7 +625,7 @@ impl JSValue {
     ///     arguments: &[JSValue],
     /// ) -> Result<JSValue, JSException> {
     ///     if arguments.len() != 1 {
-    ///         return Err(JSValue::new_string(ctx, "must receive 1 argument").into());
+    ///         return Err(JSException::new_type_error(ctx, "must receive 1 argument"));
     ///     }
     ///
     ///     let who = arguments[0].as_string()?;
@@ -329,8 +642,31 @@ impl JSValue {
     ///
     /// assert_eq!(result.as_string().unwrap().to_string(), "Hello, Gordon!");
     /// ```
+    ///
+    /// Functions with typed arguments can be declared with the
+    /// [`crate::js_function`] procedural macro instead, which converts the
+    /// arguments and the returned value automatically:
+    ///
+    /// ```rust
+    /// use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// #[js_function]
+    /// fn greet(who: String) -> String {
+    ///     format!("Hello, {who}!")
+    /// }
+    ///
+    /// let greet = JSValue::new_function(&ctx, "greet", Some(greet)).as_object().unwrap();
+    ///
+    /// let result = greet.call_as_function(
+    ///     None,
+    ///     &[JSValue::new_string(&ctx, "Gordon")],
+    /// ).unwrap();
+    ///
+    /// assert_eq!(result.as_string().unwrap().to_string(), "Hello, Gordon!");
+    /// ```
     pub fn new_function<N>(
-        ctx: &JSContext,
+        ctx: &'ctx JSContext,
         name: N,
         function: JSObjectCallAsFunctionCallback,
     ) -> Self
@@ -345,6 +681,96 @@ impl JSValue {
         }
     }
 
+    /// Creates a JavaScript function implemented by a Rust closure.
+    ///
+    /// * `ctx`: The execution context to use.
+    /// * `name`: The function name.
+    /// * `function`: The function implementation. It receives the context,
+    ///   the `this` object if any, and the arguments.
+    ///
+    /// Contrary to [`JSValue::new_function`], the closure can capture some
+    /// state. The closure is dropped when the function is garbage collected.
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let greeting = String::from("Hello");
+    ///
+    /// let greet = JSValue::new_function_from_closure(&ctx, "greet", move |ctx, _this, arguments| {
+    ///     let who = arguments.first().map(JSValue::as_string).transpose()?;
+    ///     let who = who.map(|who| who.to_string()).unwrap_or_default();
+    ///
+    ///     Ok(JSValue::new_string(ctx, format!("{greeting}, {who}!")))
+    /// });
+    ///
+    /// let result = greet.as_object().unwrap().call_as_function(
+    ///     None,
+    ///     &[JSValue::new_string(&ctx, "Gordon")],
+    /// ).unwrap();
+    ///
+    /// assert_eq!(result.as_string().unwrap().to_string(), "Hello, Gordon!");
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::new_function_from_closure_mut()`]
+    pub fn new_function_from_closure<N, F>(ctx: &'ctx JSContext, name: N, function: F) -> Self
+    where
+        N: Into<JSString>,
+        F: for<'c> Fn(
+                &'c JSContext,
+                Option<&JSObject<'c>>,
+                &[JSValue<'c>],
+            ) -> Result<JSValue<'c>, JSException>
+            + 'static,
+    {
+        closure::new_function(ctx, name.into(), Closure::Fn(Box::new(function)))
+    }
+
+    /// Creates a JavaScript function implemented by a mutable Rust closure.
+    ///
+    /// This is the same as [`JSValue::new_function_from_closure`], except
+    /// that the closure can mutate its state. Since the closure cannot be
+    /// borrowed mutably twice, calling the function reentrantly, i.e. from
+    /// JavaScript code called by the closure itself, throws an `Error`.
+    ///
+    /// ```rust
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let mut counter = 0.;
+    ///
+    /// let increment = JSValue::new_function_from_closure_mut(&ctx, "increment", move |ctx, _this, _arguments| {
+    ///     counter += 1.;
+    ///
+    ///     Ok(JSValue::new_number(ctx, counter))
+    /// });
+    ///
+    /// let increment = increment.as_object().unwrap();
+    /// increment.call_as_function(None, &[]).unwrap();
+    ///
+    /// assert_eq!(increment.call_as_function(None, &[]).unwrap().as_number().unwrap(), 2.);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::new_function_from_closure()`]
+    pub fn new_function_from_closure_mut<N, F>(ctx: &'ctx JSContext, name: N, function: F) -> Self
+    where
+        N: Into<JSString>,
+        F: for<'c> FnMut(
+                &'c JSContext,
+                Option<&JSObject<'c>>,
+                &[JSValue<'c>],
+            ) -> Result<JSValue<'c>, JSException>
+            + 'static,
+    {
+        closure::new_function(
+            ctx,
+            name.into(),
+            Closure::FnMut(RefCell::new(Box::new(function))),
+        )
+    }
+
     /// Creates a JavaScript value from a JSON formatted string.
     ///
     /// * `ctx`: The execution context to use.
@@ -361,7 +787,7 @@ impl JSValue {
     /// let v = JSValue::new_from_json(&ctx, "true").expect("value");
     /// assert!(v.is_boolean());
     /// ```
-    pub fn new_from_json<S: Into<JSString>>(ctx: &JSContext, string: S) -> Option<Self> {
+    pub fn new_from_json<S: Into<JSString>>(ctx: &'ctx JSContext, string: S) -> Option<Self> {
         let value = unsafe { sys::JSValueMakeFromJSONString(ctx.raw, string.into().raw) };
 
         if value.is_null() {
@@ -538,141 +964,608 @@ impl JSValue {
         unsafe { sys::JSValueIsSymbol(self.ctx, self.raw) }
     }
 
+    /// Tests whether a JavaScript value's type is the `bigint` type.
+    ///
+    /// Returns `true` if `value`'s type is the `bigint` type, otherwise `false`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = evaluate_script(&ctx, "42n", None, "test.js", 1).unwrap();
+    /// assert!(v.is_bigint());
+    ///
+    /// let v = JSValue::new_number(&ctx, 42.);
+    /// assert!(!v.is_bigint());
+    /// ```
+    pub fn is_bigint(&self) -> bool {
+        self.get_type() == JSType::BigInt
+    }
+
     /// Tests whether a JavaScript value's type is the `object` type.
     ///
-    /// Returns `true` if `value`'s type is the `object` type, otherwise `false`.
+    /// Returns `true` if `value`'s type is the `object` type, otherwise `false`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_from_json(&ctx, "{\"id\": 123}").expect("valid object");
+    /// assert!(v.is_object());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_object()`]
+    /// - [`JSValue::is_object_of_class()`]
+    pub fn is_object(&self) -> bool {
+        unsafe { sys::JSValueIsObject(self.ctx, self.raw) }
+    }
+
+    /// Tests whether a JavaScript value is an `object` with a given class in its class chain.
+    ///
+    /// * `js_class`: The `JSClass` to test against.
+    ///
+    /// Returns `true` if `value` is an `object` and has `jsClass` in its
+    /// class chain, otherwise `false`.
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_object()`]
+    /// - [`JSValue::is_object()`]
+    pub fn is_object_of_class(&self, js_class: &JSClass<'_>) -> bool {
+        unsafe { sys::JSValueIsObjectOfClass(self.ctx, self.raw, js_class.raw) }
+    }
+
+    /// Tests whether a JavaScript value is an `array`.
+    ///
+    /// Returns `true` if `value` is an `array`, otherwise `false`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_array(&ctx, &[JSValue::new_number(&ctx, 123.), JSValue::new_number(&ctx, 456.)]).unwrap();
+    /// assert!(v.is_array());
+    ///
+    /// // But an object is not an array.
+    /// let v = JSValue::new_from_json(&ctx, "{\"id\": 123}").expect("valid object");
+    /// assert!(!v.is_array());
+    /// ```
+    pub fn is_array(&self) -> bool {
+        unsafe { sys::JSValueIsArray(self.ctx, self.raw) }
+    }
+
+    /// Tests whether a JavaScript value is a Typed Array.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let value = JSValue::new_number(&ctx, 123.);
+    /// assert!(!value.is_typed_array());
+    ///
+    /// let mut bytes = vec![1u8, 2, 3, 4, 5];
+    /// let value = unsafe {
+    ///     JSValue::new_typed_array_with_bytes(&ctx, bytes.as_mut_slice())
+    ///         .unwrap()
+    /// };
+    /// assert!(value.is_typed_array());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_typed_array()`]
+    /// - [`JSValue::new_typed_array_with_bytes()`]
+    pub fn is_typed_array(&self) -> bool {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let value = unsafe { sys::JSValueGetTypedArrayType(self.ctx, self.raw, &mut exception) };
+
+        value != JSTypedArrayType::None
+    }
+
+    /// Tests whether a JavaScript value is a `date`.
+    ///
+    /// Returns `true` if `value` is a `date`, otherwise `false`.
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::as_date()`]
+    /// - [`JSValue::new_date()`]
+    pub fn is_date(&self) -> bool {
+        unsafe { sys::JSValueIsDate(self.ctx, self.raw) }
+    }
+
+    /// Tests whether two JavaScript values are equal, as compared by the JS
+    /// `==` operator.
+    ///
+    /// Returns either the result of the comparison, or an
+    /// [exception](JSException) if one was thrown, e.g. by a `valueOf`
+    /// method. Strict equality (`===`) is implemented by [`PartialEq`].
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let number = JSValue::new_number(&ctx, 1.);
+    /// let string = JSValue::new_string(&ctx, "1");
+    ///
+    /// assert!(number.loose_eq(&string).unwrap());
+    /// assert_ne!(number, string);
+    /// ```
+    pub fn loose_eq(&self, other: &JSValue<'_>) -> Result<bool, JSException> {
+        other.check_context(self.ctx)?;
+
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let result = unsafe { sys::JSValueIsEqual(self.ctx, self.raw, other.raw, &mut exception) };
+
+        if !exception.is_null() {
+            Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Tests whether a JavaScript value is an object constructed by
+    /// `constructor`, as compared by the JS `instanceof` operator.
+    ///
+    /// Returns either the result of the test, or an [exception](JSException)
+    /// if one was thrown, e.g. a `TypeError` when `constructor` isn't a
+    /// constructor.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let array = evaluate_script(&ctx, "[]", None, "test.js", 1).unwrap();
+    /// let constructor = evaluate_script(&ctx, "Array", None, "test.js", 1)
+    ///     .unwrap()
+    ///     .as_object()
+    ///     .unwrap();
+    ///
+    /// assert!(array.is_instance_of(&constructor).unwrap());
+    /// assert!(!JSValue::new_number(&ctx, 1.).is_instance_of(&constructor).unwrap());
+    /// ```
+    pub fn is_instance_of(&self, constructor: &JSObject<'_>) -> Result<bool, JSException> {
+        constructor.check_context(self.ctx)?;
+
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let result = unsafe {
+            sys::JSValueIsInstanceOfConstructor(self.ctx, self.raw, constructor.raw, &mut exception)
+        };
+
+        if !exception.is_null() {
+            Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Compares two JavaScript values with the JS `<` operator.
+    ///
+    /// Returns either the result of the comparison, or an
+    /// [exception](JSException) if one was thrown, e.g. by a `valueOf`
+    /// method. Like in JavaScript, comparing with `NaN` returns `false`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let one = JSValue::new_number(&ctx, 1.);
+    /// let two = JSValue::new_string(&ctx, "2");
+    ///
+    /// assert!(one.less_than(&two).unwrap());
+    /// assert!(!two.less_than(&one).unwrap());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::less_than_or_equal()`]
+    /// - [`JSValue::greater_than()`]
+    pub fn less_than(&self, other: &JSValue<'_>) -> Result<bool, JSException> {
+        self.compare(other, Relation::Less)
+    }
+
+    /// Compares two JavaScript values with the JS `<=` operator.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let null = JSValue::new_null(&ctx);
+    /// let zero = JSValue::new_number(&ctx, 0.);
+    ///
+    /// // `null` isn't equal to `0`, but it's less than or equal to it.
+    /// assert!(!null.loose_eq(&zero).unwrap());
+    /// assert!(null.less_than_or_equal(&zero).unwrap());
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::less_than()`]
+    pub fn less_than_or_equal(&self, other: &JSValue<'_>) -> Result<bool, JSException> {
+        self.compare(other, Relation::LessOrEqual)
+    }
+
+    /// Compares two JavaScript values with the JS `>` operator.
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::less_than()`]
+    pub fn greater_than(&self, other: &JSValue<'_>) -> Result<bool, JSException> {
+        self.compare(other, Relation::Greater)
+    }
+
+    /// Compares two JavaScript values with the JS `>=` operator.
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::less_than_or_equal()`]
+    pub fn greater_than_or_equal(&self, other: &JSValue<'_>) -> Result<bool, JSException> {
+        self.compare(other, Relation::GreaterOrEqual)
+    }
+
+    /// Evaluates `self <operator> other`.
+    ///
+    /// The primitives other than symbols and `BigInt`s are compared in Rust,
+    /// as their conversions have no side effects. With the `bigint` feature,
+    /// `JSValueCompare` is used when both values are numbers, `BigInt`s or
+    /// strings: it tests loose equality before ordering the values, which
+    /// differs from `<=` for `null` or objects, and calls their `valueOf`
+    /// methods more than once.
+    ///
+    /// Other values, like objects, are compared by a function applying the
+    /// operator. It's created by each comparison, which costs an allocation
+    /// and a lookup in the code cache of JavaScriptCore, besides the call.
+    fn compare(&self, other: &JSValue<'_>, relation: Relation) -> Result<bool, JSException> {
+        other.check_context(self.ctx)?;
+
+        if let Some(result) = self.compare_primitives(other, relation) {
+            return Ok(result);
+        }
+
+        #[cfg(feature = "bigint")]
+        {
+            let is_comparable = |value: &JSValue<'_>| {
+                matches!(
+                    value.get_type(),
+                    JSType::Number | JSType::BigInt | JSType::String
+                )
+            };
+
+            if is_comparable(self) && is_comparable(other) {
+                let mut exception: sys::JSValueRef = ptr::null_mut();
+                let condition =
+                    unsafe { sys::JSValueCompare(self.ctx, self.raw, other.raw, &mut exception) };
+
+                return if !exception.is_null() {
+                    Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+                } else {
+                    Ok(relation.holds(condition))
+                };
+            }
+        }
+
+        let names = ["left", "right"].map(JSString::from);
+        let body = JSString::from(format!("return left {} right;", relation.operator()));
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+
+        let function = unsafe {
+            sys::JSObjectMakeFunction(
+                self.ctx,
+                ptr::null_mut(),
+                2,
+                names.map(|name| name.raw).as_ptr(),
+                body.raw,
+                ptr::null_mut(),
+                1,
+                &mut exception,
+            )
+        };
+
+        if function.is_null() {
+            return Err(unsafe { Self::from_raw(self.ctx, exception) }.into());
+        }
+
+        let result = unsafe { JSObject::from_raw(self.ctx, function) }.call_as_function(
+            None,
+            &[self.clone(), unsafe { Self::from_raw(self.ctx, other.raw) }],
+        )?;
+
+        Ok(result.as_boolean())
+    }
+
+    /// Evaluates `self <operator> other` if both values are `undefined`,
+    /// `null`, booleans, numbers or strings, like JavaScript: two strings
+    /// are compared by their UTF-16 code units, other values are converted
+    /// to numbers.
+    fn compare_primitives(&self, other: &JSValue<'_>, relation: Relation) -> Option<bool> {
+        let is_primitive = |value: &JSValue<'_>| {
+            matches!(
+                value.get_type(),
+                JSType::Undefined
+                    | JSType::Null
+                    | JSType::Boolean
+                    | JSType::Number
+                    | JSType::String
+            )
+        };
+
+        if !is_primitive(self) || !is_primitive(other) {
+            return None;
+        }
+
+        let ordering = if self.is_string() && other.is_string() {
+            let (left, right) = (self.as_string().ok()?, other.as_string().ok()?);
+
+            Some(left.code_units().cmp(right.code_units()))
+        } else {
+            self.as_number().ok()?.partial_cmp(&other.as_number().ok()?)
+        };
+
+        Some(relation.holds_for(ordering))
+    }
+
+    /// Converts a JavaScript value to boolean and returns the resulting boolean.
+    ///
+    /// Returns the boolean result of conversion.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    ///
+    /// let v = JSValue::new_boolean(&ctx, false);
+    /// assert_eq!(v.as_boolean(), false);
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::is_boolean()`]
+    /// - [`JSValue::new_boolean()`]
+    pub fn as_boolean(&self) -> bool {
+        unsafe { sys::JSValueToBoolean(self.ctx, self.raw) }
+    }
+
+    /// Converts a JavaScript value to number and returns the resulting number.
+    ///
+    /// Returns either the numeric result of conversion, or an [exception](JSException)
+    /// if one was thrown.
     ///
     /// ```
     /// # use javascriptcore::*;
     /// let ctx = JSContext::default();
     ///
-    /// let v = JSValue::new_from_json(&ctx, "{\"id\": 123}").expect("valid object");
-    /// assert!(v.is_object());
+    /// let v = JSValue::new_number(&ctx, 5.0);
+    /// let n = v.as_number().expect("valid number");
+    /// assert_eq!(n, 5.0);
     /// ```
     ///
     /// # See also
     ///
-    /// - [`JSValue::as_object()`]
-    /// - [`JSValue::is_object_of_class()`]
-    pub fn is_object(&self) -> bool {
-        unsafe { sys::JSValueIsObject(self.ctx, self.raw) }
-    }
+    /// - [`JSValue::is_number()`]
+    /// - [`JSValue::new_number()`]
+    pub fn as_number(&self) -> Result<f64, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let number = unsafe { sys::JSValueToNumber(self.ctx, self.raw, &mut exception) };
 
-    /// Tests whether a JavaScript value is an `object` with a given class in its class chain.
-    ///
-    /// * `js_class`: The `JSClass` to test against.
-    ///
-    /// Returns `true` if `value` is an `object` and has `jsClass` in its
-    /// class chain, otherwise `false`.
-    ///
-    /// # See also
-    ///
-    /// - [`JSValue::as_object()`]
-    /// - [`JSValue::is_object()`]
-    pub fn is_object_of_class(&self, js_class: &JSClass) -> bool {
-        unsafe { sys::JSValueIsObjectOfClass(self.ctx, self.raw, js_class.raw) }
+        if number.is_nan() {
+            Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else {
+            Ok(number)
+        }
     }
 
-    /// Tests whether a JavaScript value is an `array`.
+    /// Converts a JavaScript value to an `i64`.
     ///
-    /// Returns `true` if `value` is an `array`, otherwise `false`.
+    /// Returns either the integer, or an [exception](JSException) if one was
+    /// thrown. A `RangeError` is returned if the value isn't an integer
+    /// fitting in an `i64`, instead of truncating it.
+    ///
+    /// Requires the `bigint` feature.
     ///
     /// ```
     /// # use javascriptcore::*;
     /// let ctx = JSContext::default();
     ///
-    /// let v = JSValue::new_array(&ctx, &[JSValue::new_number(&ctx, 123.), JSValue::new_number(&ctx, 456.)]).unwrap();
-    /// assert!(v.is_array());
+    /// let v = evaluate_script(&ctx, "-(2n ** 63n)", None, "test.js", 1).unwrap();
+    /// assert_eq!(v.as_i64().unwrap(), i64::MIN);
     ///
-    /// // But an object is not an array.
-    /// let v = JSValue::new_from_json(&ctx, "{\"id\": 123}").expect("valid object");
-    /// assert!(!v.is_array());
+    /// let v = evaluate_script(&ctx, "2n ** 63n", None, "test.js", 1).unwrap();
+    /// assert!(v.as_i64().is_err());
+    ///
+    /// let v = JSValue::new_number(&ctx, 42.);
+    /// assert_eq!(v.as_i64().unwrap(), 42);
     /// ```
-    pub fn is_array(&self) -> bool {
-        unsafe { sys::JSValueIsArray(self.ctx, self.raw) }
+    ///
+    /// # See also
+    ///
+    /// - [`JSValue::new_bigint_from_i64()`]
+    #[cfg(feature = "bigint")]
+    pub fn as_i64(&self) -> Result<i64, JSException> {
+        let mut exception: sys::JSValueRef = ptr::null_mut();
+        let integer = unsafe { sys::JSValueToInt64(self.ctx, self.raw, &mut exception) };
+
+        if !exception.is_null() {
+            return Err(unsafe { Self::from_raw(self.ctx, exception) }.into());
+        }
+
+        let relation =
+            unsafe { sys::JSValueCompareInt64(self.ctx, self.raw, integer, &mut exception) };
+
+        if !exception.is_null() {
+            Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else if relation != sys::JSRelationCondition::Equal {
+            Err(self.out_of_range("i64"))
+        } else {
+            Ok(integer)
+        }
     }
 
-    /// Tests whether a JavaScript value is a Typed Array.
+    /// Converts a JavaScript value to a `u64`.
+    ///
+    /// Returns either the integer, or an [exception](JSException) if one was
+    /// thrown. A `RangeError` is returned if the value isn't an integer
+    /// fitting in a `u64`, instead of truncating it.
+    ///
+    /// Requires the `bigint` feature.
     ///
     /// ```
     /// # use javascriptcore::*;
     /// let ctx = JSContext::default();
     ///
-    /// let value = JSValue::new_number(&ctx, 123.);
-    /// assert!(!value.is_typed_array());
+    /// let v = evaluate_script(&ctx, "2n ** 64n - 1n", None, "test.js", 1).unwrap();
+    /// assert_eq!(v.as_u64().unwrap(), u64::MAX);
     ///
-    /// let mut bytes = vec![1u8, 2, 3, 4, 5];
-    /// let value = unsafe {
-    ///     JSValue::new_typed_array_with_bytes(&ctx, bytes.as_mut_slice())
-    ///         .unwrap()
-    /// };
-    /// assert!(value.is_typed_array());
+    /// let v = evaluate_script(&ctx, "-1n", None, "test.js", 1).unwrap();
+    /// assert!(v.as_u64().is_err());
     /// ```
     ///
     /// # See also
     ///
-    /// - [`JSValue::as_typed_array()`]
-    /// - [`JSValue::new_typed_array_with_bytes()`]
-    pub fn is_typed_array(&self) -> bool {
+    /// - [`JSValue::new_bigint_from_u64()`]
+    #[cfg(feature = "bigint")]
+    pub fn as_u64(&self) -> Result<u64, JSException> {
         let mut exception: sys::JSValueRef = ptr::null_mut();
-        let value = unsafe { sys::JSValueGetTypedArrayType(self.ctx, self.raw, &mut exception) };
+        let integer = unsafe { sys::JSValueToUInt64(self.ctx, self.raw, &mut exception) };
 
-        value != JSTypedArrayType::None
-    }
+        if !exception.is_null() {
+            return Err(unsafe { Self::from_raw(self.ctx, exception) }.into());
+        }
 
-    /// Tests whether a JavaScript value is a `date`.
-    ///
-    /// Returns `true` if `value` is a `date`, otherwise `false`.
-    pub fn is_date(&self) -> bool {
-        unsafe { sys::JSValueIsDate(self.ctx, self.raw) }
+        let relation =
+            unsafe { sys::JSValueCompareUInt64(self.ctx, self.raw, integer, &mut exception) };
+
+        if !exception.is_null() {
+            Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else if relation != sys::JSRelationCondition::Equal {
+            Err(self.out_of_range("u64"))
+        } else {
+            Ok(integer)
+        }
     }
 
-    /// Converts a JavaScript value to boolean and returns the resulting boolean.
+    /// Converts a `BigInt`, or an integral number, to an `i128`.
     ///
-    /// Returns the boolean result of conversion.
+    /// Returns either the integer, or an [exception](JSException) if one was
+    /// thrown. A `RangeError` is returned if the value isn't an integer
+    /// fitting in an `i128`.
+    ///
+    /// Contrary to [`JSValue::as_i64()`], this method doesn't require the
+    /// `bigint` feature, as it goes through the decimal representation of
+    /// the `BigInt`.
     ///
     /// ```
     /// # use javascriptcore::*;
     /// let ctx = JSContext::default();
     ///
-    /// let v = JSValue::new_boolean(&ctx, false);
-    /// assert_eq!(v.as_boolean(), false);
-    /// ```
+    /// let v = evaluate_script(&ctx, "2n ** 100n", None, "test.js", 1).unwrap();
+    /// assert_eq!(v.as_i128().unwrap(), 1 << 100);
     ///
-    /// # See also
+    /// let v = JSValue::new_number(&ctx, -42.);
+    /// assert_eq!(v.as_i128().unwrap(), -42);
     ///
-    /// - [`JSValue::is_boolean()`]
-    /// - [`JSValue::new_boolean()`]
-    pub fn as_boolean(&self) -> bool {
-        unsafe { sys::JSValueToBoolean(self.ctx, self.raw) }
+    /// let v = JSValue::new_number(&ctx, 0.5);
+    /// assert!(v.as_i128().is_err());
+    /// ```
+    pub fn as_i128(&self) -> Result<i128, JSException> {
+        if self.is_bigint() {
+            return self
+                .as_string()?
+                .to_string()
+                .parse()
+                .map_err(|_| self.out_of_range("i128"));
+        }
+
+        let number = self.as_number()?;
+
+        // 2^127, the first double out of the `i128` range.
+        #[allow(clippy::cast_precision_loss)]
+        const LIMIT: f64 = i128::MAX as f64;
+
+        if number.fract() != 0.0 || !(-LIMIT..LIMIT).contains(&number) {
+            return Err(self.out_of_range("i128"));
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(number as i128)
     }
 
-    /// Converts a JavaScript value to number and returns the resulting number.
+    /// Builds a `RangeError` stating that the value doesn't fit in `ty`.
+    fn out_of_range(&self, ty: &str) -> JSException {
+        let value = self
+            .as_string()
+            .map(|string| string.to_string())
+            .unwrap_or_default();
+
+        JSException::new_builtin(
+            self.ctx,
+            "RangeError",
+            &format!("`{value}` is not an integer representable as `{ty}`"),
+        )
+    }
+
+    /// Converts a JavaScript `Date` to the time it represents.
     ///
-    /// Returns either the numeric result of conversion, or an [exception](JSException)
-    /// if one was thrown.
+    /// Returns either the time, a `TypeError` if the value isn't a `Date`,
+    /// or a `RangeError` if it's an invalid date, i.e. whose time value is
+    /// `NaN`, like `new Date('nope')`.
     ///
     /// ```
     /// # use javascriptcore::*;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
     /// let ctx = JSContext::default();
     ///
-    /// let v = JSValue::new_number(&ctx, 5.0);
-    /// let n = v.as_number().expect("valid number");
-    /// assert_eq!(n, 5.0);
+    /// let v = evaluate_script(&ctx, "new Date(Date.UTC(1969, 11, 31))", None, "test.js", 1).unwrap();
+    /// assert_eq!(v.as_date().unwrap(), UNIX_EPOCH - Duration::from_secs(24 * 60 * 60));
+    ///
+    /// let v = evaluate_script(&ctx, "new Date('nope')", None, "test.js", 1).unwrap();
+    /// assert_eq!(v.as_date().unwrap_err().name().unwrap(), "RangeError");
     /// ```
     ///
     /// # See also
     ///
-    /// - [`JSValue::is_number()`]
-    /// - [`JSValue::new_number()`]
-    pub fn as_number(&self) -> Result<f64, JSException> {
+    /// - [`JSValue::is_date()`]
+    /// - [`JSValue::new_date()`]
+    pub fn as_date(&self) -> Result<SystemTime, JSException> {
+        let millis = self.as_timestamp()?;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let time = if millis >= 0. {
+            UNIX_EPOCH.checked_add(Duration::from_millis(millis as u64))
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_millis(-millis as u64))
+        };
+
+        time.ok_or_else(|| {
+            JSException::new_builtin(
+                self.ctx,
+                "RangeError",
+                &format!("{millis}ms since the Unix epoch cannot be represented as a `SystemTime`"),
+            )
+        })
+    }
+
+    /// Returns the time value of a `Date`, in milliseconds since the Unix
+    /// epoch, or an error if it's not a valid `Date`.
+    pub(crate) fn as_timestamp(&self) -> Result<f64, JSException> {
+        if !self.is_date() {
+            return Err(convert::type_error(self, "a `Date`"));
+        }
+
         let mut exception: sys::JSValueRef = ptr::null_mut();
-        let number = unsafe { sys::JSValueToNumber(self.ctx, self.raw, &mut exception) };
+        let millis = unsafe { sys::JSValueToNumber(self.ctx, self.raw, &mut exception) };
 
-        if number.is_nan() {
+        if !exception.is_null() {
             Err(unsafe { Self::from_raw(self.ctx, exception) }.into())
+        } else if millis.is_nan() {
+            Err(JSException::new_builtin(
+                self.ctx,
+                "RangeError",
+                "invalid date: its time value is `NaN`",
+            ))
         } else {
-            Ok(number)
+            Ok(millis)
         }
     }
 
@@ -722,7 +1615,7 @@ impl JSValue {
     /// # See also
     ///
     /// - [`JSValue::is_object()`]
-    pub fn as_object(&self) -> Result<JSObject, JSException> {
+    pub fn as_object(&self) -> Result<JSObject<'ctx>, JSException> {
         let mut exception: sys::JSValueRef = ptr::null_mut();
         let object = unsafe { sys::JSValueToObject(self.ctx, self.raw, &mut exception) };
 
@@ -759,15 +1652,13 @@ impl JSValue {
     ///
     /// - [`JSValue::is_typed_array()`]
     /// - [`JSValue::new_typed_array_with_bytes()`]
-    pub fn as_typed_array(&self) -> Result<JSTypedArray, JSException> {
+    pub fn as_typed_array(&self) -> Result<JSTypedArray<'ctx>, JSException> {
         if !self.is_typed_array() {
-            return Err(unsafe {
-                Self::from_raw(
-                    self.ctx,
-                    JSString::from("Value is not a Typed Array").raw as *const _,
-                )
-            }
-            .into());
+            return Err(JSException::new_builtin(
+                self.ctx,
+                "TypeError",
+                "Value is not a Typed Array",
+            ));
         }
 
         let object = self.as_object()?;
@@ -775,11 +1666,36 @@ impl JSValue {
         Ok(unsafe { JSTypedArray::from_raw(object.ctx, object.raw) })
     }
 
+    /// Returns a handle protecting this value from garbage collection
+    /// until it's dropped, and which doesn't borrow the context.
+    ///
+    /// Use this method when you want to store a [`JSValue`] in a
+    /// global or on the heap, e.g. an argument received by a callback.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let values: Vec<Persistent> = vec![
+    ///     JSValue::new_number(&ctx, 1.).to_persistent(),
+    ///     JSValue::new_string(&ctx, "two").to_persistent(),
+    /// ];
+    /// ```
+    ///
+    /// # See also
+    ///
+    /// * [`JSObject::to_persistent()`]
+    /// * [`Persistent`]
+    pub fn to_persistent(&self) -> Persistent {
+        unsafe { Persistent::from_raw(self.ctx, self.raw) }
+    }
+
     /// Protects a JavaScript value from garbage collection.
     ///
     /// Use this method when you want to store a [`JSValue`] in a
     /// global or on the heap, where the garbage collector will
     /// not be able to discover your reference to it.
+    /// [`JSValue::to_persistent()`] does it without having to
+    /// unprotect the value by hand.
     ///
     /// A value may be protected multiple times and must be
     /// [unprotected] an equal number of times before becoming
@@ -788,6 +1704,7 @@ impl JSValue {
     /// # See also
     ///
     /// * [`garbage_collect()`]
+    /// * [`JSValue::to_persistent()`]
     /// * [`JSValue::unprotect()`]
     ///
     /// [`garbage_collect()`]: crate::garbage_collect
@@ -814,30 +1731,81 @@ impl JSValue {
     }
 }
 
+/// A relational operator, see [`JSValue::compare`].
+#[derive(Clone, Copy)]
+enum Relation {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Relation {
+    const fn operator(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+
+    /// Returns whether the relation holds for two values ordered by
+    /// `ordering`, which is `None` if they can't be ordered, e.g. `NaN`.
+    fn holds_for(self, ordering: Option<cmp::Ordering>) -> bool {
+        use cmp::Ordering::{Equal, Greater, Less};
+
+        match self {
+            Self::Less => ordering == Some(Less),
+            Self::LessOrEqual => matches!(ordering, Some(Less | Equal)),
+            Self::Greater => ordering == Some(Greater),
+            Self::GreaterOrEqual => matches!(ordering, Some(Greater | Equal)),
+        }
+    }
+
+    /// Returns whether the relation holds for two values compared by
+    /// `JSValueCompare`.
+    #[cfg(feature = "bigint")]
+    fn holds(self, condition: sys::JSRelationCondition) -> bool {
+        use sys::JSRelationCondition::{Equal, GreaterThan, LessThan};
+
+        match self {
+            Self::Less => condition == LessThan,
+            Self::LessOrEqual => matches!(condition, LessThan | Equal),
+            Self::Greater => condition == GreaterThan,
+            Self::GreaterOrEqual => matches!(condition, GreaterThan | Equal),
+        }
+    }
+}
+
 /// Implement partial equality checks for `JSValue`.
 ///
 /// These are performed in the same manner as `===` (strict
 /// equality) in JavaScript.
-impl PartialEq for JSValue {
-    fn eq(&self, other: &JSValue) -> bool {
-        unsafe { sys::JSValueIsStrictEqual(self.ctx, self.raw, other.raw) }
+impl PartialEq for JSValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // The values of different context groups are never equal.
+        other.same_group(self.ctx)
+            && unsafe { sys::JSValueIsStrictEqual(self.ctx, self.raw, other.raw) }
     }
 }
 
-impl From<JSValue> for sys::JSValueRef {
-    fn from(value: JSValue) -> Self {
+impl From<JSValue<'_>> for sys::JSValueRef {
+    fn from(value: JSValue<'_>) -> Self {
         value.raw
     }
 }
 
-impl From<JSValue> for sys::JSObjectRef {
-    fn from(value: JSValue) -> Self {
+impl From<JSValue<'_>> for sys::JSObjectRef {
+    fn from(value: JSValue<'_>) -> Self {
         value.raw as *mut _
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
     use crate::{evaluate_script, function_callback, sys, JSContext, JSException, JSType, JSValue};
 
     #[test]
@@ -855,6 +1823,159 @@ mod tests {
         assert_ne!(t, f);
     }
 
+    #[test]
+    fn loose_equality() {
+        let ctx = JSContext::default();
+
+        let null = JSValue::new_null(&ctx);
+        let undefined = JSValue::new_undefined(&ctx);
+        assert!(null.loose_eq(&undefined).unwrap());
+        assert_ne!(null, undefined);
+
+        let zero = JSValue::new_number(&ctx, 0.);
+        assert!(!null.loose_eq(&zero).unwrap());
+
+        let object = evaluate_script(
+            &ctx,
+            "({ valueOf() { throw 'oops'; } })",
+            None,
+            "test.js",
+            1,
+        )
+        .unwrap();
+        assert!(object.loose_eq(&zero).is_err());
+    }
+
+    #[test]
+    fn instance_of() {
+        let ctx = JSContext::default();
+
+        let error = evaluate_script(&ctx, "new TypeError()", None, "test.js", 1).unwrap();
+        let constructor = |name| {
+            evaluate_script(&ctx, name, None, "test.js", 1)
+                .unwrap()
+                .as_object()
+                .unwrap()
+        };
+
+        assert!(error.is_instance_of(&constructor("Error")).unwrap());
+        assert!(!error.is_instance_of(&constructor("RangeError")).unwrap());
+        assert!(error.is_instance_of(&constructor("({})")).is_err());
+    }
+
+    #[test]
+    fn relational_comparisons() {
+        let ctx = JSContext::default();
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1).unwrap();
+
+        let one = JSValue::new_number(&ctx, 1.);
+        let two = JSValue::new_number(&ctx, 2.);
+        assert!(one.less_than(&two).unwrap());
+        assert!(one.less_than_or_equal(&one).unwrap());
+        assert!(!one.greater_than(&two).unwrap());
+        assert!(two.greater_than_or_equal(&one).unwrap());
+
+        // Strings are compared lexicographically.
+        let a = JSValue::new_string(&ctx, "10");
+        let b = JSValue::new_string(&ctx, "9");
+        assert!(a.less_than(&b).unwrap());
+
+        // Any comparison with `NaN` is false.
+        let nan = JSValue::new_number(&ctx, f64::NAN);
+        assert!(!nan.less_than_or_equal(&nan).unwrap());
+        assert!(!nan.greater_than_or_equal(&one).unwrap());
+
+        // `null` is converted to `0`.
+        let null = JSValue::new_null(&ctx);
+        let zero = JSValue::new_number(&ctx, 0.);
+        assert!(null.greater_than_or_equal(&zero).unwrap());
+        assert!(!null.greater_than(&zero).unwrap());
+
+        // `valueOf` is called once per operand.
+        let object = eval("var calls = 0; ({ valueOf() { calls += 1; return 1; } })");
+        assert!(object.less_than_or_equal(&one).unwrap());
+        assert_eq!(eval("calls").as_number().unwrap(), 1.);
+
+        // BigInts are compared with numbers.
+        let big = eval("2n ** 64n");
+        assert!(big.greater_than(&two).unwrap());
+        assert!(!big.less_than(&eval("2 ** 64")).unwrap());
+
+        let throwing = eval("({ valueOf() { throw 'oops'; } })");
+        assert!(throwing.less_than(&one).is_err());
+    }
+
+    #[test]
+    fn relational_comparisons_without_compare() {
+        let ctx = JSContext::default();
+        let eval = |script| evaluate_script(&ctx, script, None, "test.js", 1).unwrap();
+
+        // These values aren't compared by `JSValueCompare`, whatever the
+        // features.
+        let null = JSValue::new_null(&ctx);
+        let undefined = JSValue::new_undefined(&ctx);
+        let zero = JSValue::new_number(&ctx, 0.);
+        let yes = JSValue::new_boolean(&ctx, true);
+        assert!(null.less_than_or_equal(&zero).unwrap());
+        assert!(!null.less_than(&zero).unwrap());
+        assert!(!undefined.less_than_or_equal(&zero).unwrap());
+        assert!(!undefined.greater_than_or_equal(&undefined).unwrap());
+        assert!(yes.greater_than(&null).unwrap());
+        assert!(yes
+            .greater_than_or_equal(&JSValue::new_string(&ctx, "1"))
+            .unwrap());
+
+        // A string is converted to a number when compared with a number,
+        // and strings are compared by their UTF-16 code units.
+        let ten = JSValue::new_string(&ctx, "10");
+        assert!(!ten.less_than(&JSValue::new_number(&ctx, 9.)).unwrap());
+        assert!(ten.less_than(&JSValue::new_string(&ctx, "9")).unwrap());
+        assert!(!JSValue::new_string(&ctx, "\u{ffff}")
+            .less_than(&JSValue::new_string(&ctx, "\u{10000}"))
+            .unwrap());
+
+        // Objects are converted by their `valueOf` method, once.
+        let object = eval("var calls = 0; ({ valueOf() { calls += 1; return 1; } })");
+        assert!(object.greater_than(&null).unwrap());
+        assert!(object.less_than_or_equal(&yes).unwrap());
+        assert!(!object.less_than(&object).unwrap());
+        assert_eq!(eval("calls").as_number().unwrap(), 4.);
+
+        let symbol = eval("Symbol()");
+        assert!(symbol.less_than(&zero).is_err());
+    }
+
+    #[test]
+    fn dates() {
+        let ctx = JSContext::default();
+
+        let before_epoch = UNIX_EPOCH - Duration::from_millis(1_234);
+        let v = JSValue::new_date(&ctx, before_epoch).unwrap();
+        assert!(v.is_date());
+        assert_eq!(v.as_date().unwrap(), before_epoch);
+
+        // Dates have a millisecond precision.
+        let time = UNIX_EPOCH + Duration::from_micros(1_999);
+        let v = JSValue::new_date(&ctx, time).unwrap();
+        assert_eq!(v.as_date().unwrap(), UNIX_EPOCH + Duration::from_millis(1));
+        let time = UNIX_EPOCH - Duration::from_micros(1_500);
+        let v = JSValue::new_date(&ctx, time).unwrap();
+        assert_eq!(v.as_date().unwrap(), UNIX_EPOCH - Duration::from_millis(2));
+
+        let v = evaluate_script(&ctx, "new Date(8.64e15)", None, "test.js", 1).unwrap();
+        assert!(v.as_date().is_ok());
+        let too_far = UNIX_EPOCH + Duration::from_millis(8_640_000_000_000_001);
+        assert!(JSValue::new_date(&ctx, too_far).is_err());
+
+        let invalid = evaluate_script(&ctx, "new Date(NaN)", None, "test.js", 1).unwrap();
+        assert!(invalid.is_date());
+        let error = invalid.as_date().unwrap_err();
+        assert_eq!(error.name().unwrap(), "RangeError");
+
+        let error = JSValue::new_number(&ctx, 0.).as_date().unwrap_err();
+        assert_eq!(error.name().unwrap(), "TypeError");
+    }
+
     #[test]
     fn undefined() {
         let ctx = JSContext::default();
@@ -1016,11 +2137,12 @@ mod tests {
             let y = arguments.get(1).unwrap();
 
             let ctx = JSContext::from_raw(ctx as *mut _);
-            let result = JSValue::new_number(&ctx, x.as_number().unwrap() + y.as_number().unwrap());
+            let result =
+                JSValue::new_number(&ctx, x.as_number().unwrap() + y.as_number().unwrap()).raw;
 
             mem::forget(ctx);
 
-            result.raw
+            result
         }
 
         // Let's try from Rust.
@@ -1057,7 +2179,7 @@ mod tests {
             arguments: &[JSValue],
         ) -> Result<JSValue, JSException> {
             if arguments.len() != 2 {
-                return Err(JSValue::new_string(ctx, "must receive 2 arguments").into());
+                return Err(JSException::new_type_error(ctx, "must receive 2 arguments"));
             }
 
             let x = arguments[0].as_number()?;
@@ -1134,7 +2256,7 @@ mod tests {
             M: Math,
         {
             if arguments.len() != 1 {
-                return Err(JSValue::new_string(ctx, "must receive 1 argument").into());
+                return Err(JSException::new_type_error(ctx, "must receive 1 argument"));
             }
 
             let x = arguments[0].as_number()?;
@@ -1170,6 +2292,118 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn function_with_typed_arguments() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::js_function;
+
+        let ctx = JSContext::default();
+
+        #[js_function]
+        fn add(a: f64, b: f64) -> f64 {
+            a + b
+        }
+
+        #[js_function]
+        fn greet(
+            _ctx: &JSContext,
+            name: String,
+            punctuation: Option<String>,
+        ) -> Result<String, String> {
+            if name.is_empty() {
+                return Err("`name` must not be empty".to_owned());
+            }
+
+            Ok(format!(
+                "Hello, {name}{}",
+                punctuation.as_deref().unwrap_or("!")
+            ))
+        }
+
+        #[js_function]
+        fn count(first: u32, rest: &[JSValue]) -> usize {
+            first as usize + rest.len()
+        }
+
+        #[js_function]
+        fn nothing() {}
+
+        let global_object = ctx.global_object()?;
+        global_object.set_property("add", JSValue::new_function(&ctx, "add", Some(add)))?;
+        global_object.set_property("greet", JSValue::new_function(&ctx, "greet", Some(greet)))?;
+        global_object.set_property("count", JSValue::new_function(&ctx, "count", Some(count)))?;
+        global_object.set_property(
+            "nothing",
+            JSValue::new_function(&ctx, "nothing", Some(nothing)),
+        )?;
+
+        let eval = |script: &str| evaluate_script(&ctx, script, None, "test.js", 1);
+        let message = |exception: JSException| -> Result<String, JSException> {
+            Ok(exception
+                .underlying_value()
+                .as_object()?
+                .get_property("message")
+                .as_string()?
+                .to_string())
+        };
+
+        assert_eq!(eval("add(1, 2)")?.as_number()?, 3.);
+        assert_eq!(eval("greet('Gordon')")?.as_string()?, "Hello, Gordon!");
+        assert_eq!(eval("greet('Gordon', '?')")?.as_string()?, "Hello, Gordon?");
+        assert_eq!(eval("count(1)")?.as_number()?, 1.);
+        assert_eq!(eval("count(1, 'a', 'b')")?.as_number()?, 3.);
+        assert!(eval("nothing()")?.is_undefined());
+
+        let error = eval("add(1)").unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+        assert_eq!(message(error)?, "expected 2 arguments, got 1");
+
+        let error = eval("greet()").unwrap_err();
+        assert_eq!(message(error)?, "expected between 1 and 2 arguments, got 0");
+
+        let error = eval("count()").unwrap_err();
+        assert_eq!(message(error)?, "expected at least 1 argument, got 0");
+
+        let error = eval("add(1, '2')").unwrap_err();
+        assert_eq!(error.name()?, "TypeError");
+        assert_eq!(
+            message(error)?,
+            "argument 1: expected `Number`, got `String`"
+        );
+
+        let error = eval("greet('')").unwrap_err();
+        assert_eq!(error.name()?, "Error");
+        assert_eq!(message(error)?, "`name` must not be empty");
+
+        Ok(())
+    }
+
+    #[test]
+    fn function_returning_values() -> Result<(), JSException> {
+        use crate as javascriptcore;
+        use crate::js_function;
+
+        let ctx = JSContext::default();
+
+        // The elided lifetimes are bound to the context.
+        #[js_function]
+        fn first_or(fallback: JSValue, values: &[JSValue]) -> JSValue {
+            values.first().cloned().unwrap_or(fallback)
+        }
+
+        ctx.global_object()?.set_property(
+            "firstOr",
+            JSValue::new_function(&ctx, "firstOr", Some(first_or)),
+        )?;
+
+        let eval = |script: &str| evaluate_script(&ctx, script, None, "test.js", 1);
+
+        assert_eq!(eval("firstOr(1, 2, 3)")?.as_number()?, 2.);
+        assert_eq!(eval("firstOr(1)")?.as_number()?, 1.);
+
+        Ok(())
+    }
+
     #[test]
     fn json_boolean_true() {
         let ctx = JSContext::default();
@@ -1248,4 +2482,70 @@ mod tests {
         let v = JSValue::new_from_json(&ctx, "3 +");
         assert!(v.is_none());
     }
+
+    #[test]
+    fn bigint_type() {
+        let ctx = JSContext::default();
+
+        let v = evaluate_script(&ctx, "12345678901234567890n", None, "test.js", 1).unwrap();
+        assert_eq!(v.get_type(), JSType::BigInt);
+        assert!(v.is_bigint());
+        assert!(!v.is_number());
+        assert_eq!(v.as_i128().unwrap(), 12_345_678_901_234_567_890);
+
+        let v = evaluate_script(&ctx, "-(2n ** 127n) - 1n", None, "test.js", 1).unwrap();
+        assert!(v.as_i128().is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_conversions() {
+        let ctx = JSContext::default();
+
+        let v = JSValue::new_bigint_from_i64(&ctx, -42).unwrap();
+        assert!(v.is_bigint());
+        assert_eq!(v.as_i64().unwrap(), -42);
+        assert!(v.as_u64().is_err());
+
+        let v = JSValue::new_bigint_from_u64(&ctx, u64::MAX).unwrap();
+        assert!(v.as_i64().is_err());
+        assert_eq!(v.as_u64().unwrap(), u64::MAX);
+        assert_eq!(v.as_i128().unwrap(), i128::from(u64::MAX));
+
+        let v = JSValue::new_bigint_from_i128(&ctx, i128::MIN).unwrap();
+        assert_eq!(v.as_i128().unwrap(), i128::MIN);
+
+        let v = JSValue::new_bigint_from_f64(&ctx, 2f64.powi(60)).unwrap();
+        assert_eq!(v.as_i64().unwrap(), 1 << 60);
+
+        // Non-integral numbers aren't truncated.
+        let v = JSValue::new_number(&ctx, 1.5);
+        assert!(v.as_i64().is_err());
+        assert!(JSValue::new_bigint_from_f64(&ctx, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn values_of_other_groups_are_rejected() {
+        let ctx = JSContext::default();
+        let other_ctx = JSContext::default();
+        let global = ctx.global_object().unwrap();
+
+        let value = JSValue::new_string(&other_ctx, "elsewhere");
+        let object = other_ctx.global_object().unwrap();
+
+        assert!(global.set_property("value", value.clone()).is_err());
+        assert!(global.set_property_at_index(0, value.clone()).is_err());
+        assert!(JSValue::new_array(&ctx, std::slice::from_ref(&value)).is_err());
+        assert!(JSValue::new_string(&ctx, "elsewhere")
+            .loose_eq(&value)
+            .is_err());
+        assert_ne!(JSValue::new_string(&ctx, "elsewhere"), value);
+
+        let string = global.get_property("String").as_object().unwrap();
+        let exception = string
+            .call_as_function(None, std::slice::from_ref(&value))
+            .unwrap_err();
+        assert_eq!(exception.name().unwrap(), "TypeError");
+        assert!(string.call_as_function(Some(&object), &[]).is_err());
+    }
 }
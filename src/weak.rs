@@ -0,0 +1,128 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use crate::{sys, JSObject, JSWeak, Persistent};
+
+impl JSWeak {
+    /// Creates a weak reference to `object`.
+    ///
+    /// ```
+    /// # use javascriptcore::*;
+    /// let ctx = JSContext::default();
+    /// let object = JSObject::new(&ctx);
+    /// let weak = JSWeak::new(&object);
+    ///
+    /// assert_eq!(*weak.upgrade().unwrap().get(), *object);
+    /// ```
+    pub fn new(object: &JSObject<'_>) -> Self {
+        unsafe {
+            let ctx = sys::JSGlobalContextRetain(sys::JSContextGetGlobalContext(object.value.ctx));
+            let raw = sys::JSWeakCreate(sys::JSContextGetGroup(ctx), object.raw);
+
+            Self { ctx, raw }
+        }
+    }
+
+    /// Returns a handle to the object, protecting it from garbage
+    /// collection, or `None` if it has already been collected.
+    pub fn upgrade(&self) -> Option<Persistent<JSObject<'static>>> {
+        let object = unsafe { sys::JSWeakGetObject(self.raw) };
+
+        if object.is_null() {
+            None
+        } else {
+            Some(unsafe { Persistent::from_raw(self.ctx, object) })
+        }
+    }
+}
+
+impl Persistent<JSObject<'static>> {
+    /// Creates a weak reference to the object held by this handle.
+    ///
+    /// # See also
+    ///
+    /// - [`JSWeak::new()`]
+    pub fn downgrade(&self) -> JSWeak {
+        JSWeak::new(&self.get())
+    }
+}
+
+impl Clone for JSWeak {
+    fn clone(&self) -> Self {
+        unsafe {
+            sys::JSWeakRetain(sys::JSContextGetGroup(self.ctx), self.raw);
+
+            Self {
+                ctx: sys::JSGlobalContextRetain(self.ctx),
+                raw: self.raw,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for JSWeak {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("JSWeak")
+            .field("ctx", &self.ctx)
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
+
+impl Drop for JSWeak {
+    fn drop(&mut self) {
+        unsafe {
+            sys::JSWeakRelease(sys::JSContextGetGroup(self.ctx), self.raw);
+            sys::JSGlobalContextRelease(self.ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{garbage_collect, JSContext, JSObject, JSValue, JSWeak};
+
+    #[test]
+    fn weak_reference_can_be_upgraded() {
+        let ctx = JSContext::default();
+        let object = JSObject::new(&ctx).to_persistent();
+        object
+            .get()
+            .set_property("answer", JSValue::new_number(&ctx, 42.))
+            .unwrap();
+
+        let weak = object.downgrade();
+        let clone = weak.clone();
+        drop(weak);
+        garbage_collect(&ctx);
+
+        let upgraded = clone.upgrade().unwrap();
+        assert_eq!(
+            upgraded.get().get_property("answer").as_number().unwrap(),
+            42.
+        );
+    }
+
+    #[test]
+    fn weak_reference_doesnt_keep_object_alive() {
+        let ctx = JSContext::default();
+
+        // The collector scans the stack conservatively, so a few objects
+        // may survive: let's only check that some have been collected.
+        #[inline(never)]
+        fn new_weak_references(ctx: &JSContext) -> Vec<JSWeak> {
+            (0..100).map(|_| JSWeak::new(&JSObject::new(ctx))).collect()
+        }
+
+        let weak_references = new_weak_references(&ctx);
+        garbage_collect(&ctx);
+
+        assert!(weak_references.iter().any(|weak| weak.upgrade().is_none()));
+    }
+}